@@ -1,5 +1,5 @@
 use futures::prelude::*;
-use hubcaps::branches::Protection;
+use hubcaps::branches::{BranchListOptions, Protection};
 use hubcaps::{Credentials, Github};
 use std::env;
 use std::error::Error;
@@ -16,7 +16,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     if let Err(err) = github
         .repo("softprops", "hubcaps")
         .branches()
-        .iter()
+        .iter(&BranchListOptions::default())
         .try_for_each(|branch| async move {
             println!("{:#?}", branch);
             Ok(())
@@ -43,10 +43,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .protection(
             "master",
             &Protection {
-                required_status_checks: None,
                 enforce_admins: false,
-                required_pull_request_reviews: None,
-                restrictions: None,
+                ..Default::default()
             },
         )
         .await
@@ -23,7 +23,10 @@ async fn main() -> Result<()> {
         .list("")
         .await?;
     for commit in commits {
-        println!(" - {}", commit.author.login);
+        match commit.author {
+            Some(author) => println!(" - {}", author.login),
+            None => println!(" - {}", commit.commit.author.name),
+        }
     }
     println!("Thank you for your help!");
     Ok(())
@@ -97,7 +97,7 @@ impl InstallationTokenGenerator {
     }
 
     pub(crate) fn jwt(&self) -> &crate::Credentials {
-        &*self.jwt_credential
+        &self.jwt_credential
     }
 }
 
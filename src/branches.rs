@@ -2,9 +2,16 @@
 //!
 //! For more information, visit the official
 //! [Github docs](https://developer.github.com/v3/repos/branches/)
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
 
-use crate::{Future, Github, MediaType, Stream};
+use crate::git::{Git, Reference};
+use crate::repo_commits::RepoCommit;
+use crate::teams::Team;
+use crate::users::User;
+use crate::{Future, Github, MediaType, Paginator};
 
 /// reference to gists associated with a github user
 pub struct Branches {
@@ -28,21 +35,29 @@ impl Branches {
     }
 
     /// list of branches for this repo
-    pub fn list(&self) -> Future<Vec<Branch>> {
-        self.github.get(&format!(
+    pub fn list(&self, options: &BranchListOptions) -> Future<Vec<Branch>> {
+        let mut uri = vec![format!(
             "/repos/{owner}/{repo}/branches",
             owner = self.owner,
             repo = self.repo
-        ))
+        )];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get(&uri.join("?"))
     }
 
     /// provides an stream over branches for this repo
-    pub fn iter(&self) -> Stream<Branch> {
-        self.github.get_stream(&format!(
+    pub fn iter(&self, options: &BranchListOptions) -> Paginator<Branch> {
+        let mut uri = vec![format!(
             "/repos/{owner}/{repo}/branches",
             owner = self.owner,
             repo = self.repo
-        ))
+        )];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
     }
 
     /// gets a branch for this repo by name
@@ -58,7 +73,8 @@ impl Branches {
         ))
     }
 
-    /// update branch production for a given branch
+    /// update branch production for a given branch. equivalent to
+    /// `protection_rules(branch).update(pro)`
     ///
     /// https://developer.github.com/v3/repos/branches/#update-branch-protection
     pub fn protection<B>(&self, branch: B, pro: &Protection) -> Future<ProtectionState>
@@ -77,6 +93,77 @@ impl Branches {
         )
     }
 
+    /// create a branch pointing at a commit
+    pub fn create<B, S>(&self, branch: B, sha: S) -> Future<Reference>
+    where
+        B: Into<String>,
+        S: Into<String>,
+    {
+        self.git()
+            .create_reference(format!("heads/{}", branch.into()), sha)
+    }
+
+    /// delete a branch
+    pub fn delete<B>(&self, branch: B) -> Future<()>
+    where
+        B: Into<String>,
+    {
+        self.git()
+            .delete_reference(format!("heads/{}", branch.into()))
+    }
+
+    /// merge `head`, a branch or commit sha, into the `base` branch. resolves
+    /// to `None` when `base` already contains `head`. fails with a 409 when
+    /// the merge conflicts
+    ///
+    /// https://docs.github.com/en/rest/branches/branches#merge-a-branch
+    pub fn merge<B, H>(&self, base: B, head: H, message: Option<&str>) -> Future<Option<RepoCommit>>
+    where
+        B: Into<String>,
+        H: Into<String>,
+    {
+        let merge = BranchMerge {
+            base: base.into(),
+            head: head.into(),
+            commit_message: message.map(String::from),
+        };
+        self.github.post(
+            &format!("/repos/{}/{}/merges", self.owner, self.repo),
+            json!(merge),
+        )
+    }
+
+    /// bring a branch of a fork up to date with its upstream repository
+    ///
+    /// https://docs.github.com/en/rest/branches/branches#sync-a-fork-branch-with-the-upstream-repository
+    pub fn merge_upstream<B>(&self, branch: B) -> Future<UpstreamMerge>
+    where
+        B: Into<String>,
+    {
+        let branch = branch.into();
+        self.github.post(
+            &format!("/repos/{}/{}/merge-upstream", self.owner, self.repo),
+            json_lit!({ "branch": branch }),
+        )
+    }
+
+    fn git(&self) -> Git {
+        Git::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
+    }
+
+    /// get a reference to the protection rules of a given branch
+    pub fn protection_rules<B>(&self, branch: B) -> BranchProtection
+    where
+        B: Into<String>,
+    {
+        BranchProtection::new(
+            self.github.clone(),
+            self.owner.as_str(),
+            self.repo.as_str(),
+            branch,
+        )
+    }
+
     /// Rename a branch
     ///
     /// https://docs.github.com/en/rest/reference/repos#rename-a-branch
@@ -96,22 +183,368 @@ impl Branches {
     }
 }
 
+/// A structure for interfacing with the protection rules of a branch
+///
+/// https://docs.github.com/en/rest/branches/branch-protection
+pub struct BranchProtection {
+    github: Github,
+    owner: String,
+    repo: String,
+    branch: String,
+}
+
+impl BranchProtection {
+    #[doc(hidden)]
+    pub fn new<O, R, B>(github: Github, owner: O, repo: R, branch: B) -> Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+        B: Into<String>,
+    {
+        BranchProtection {
+            github,
+            owner: owner.into(),
+            repo: repo.into(),
+            branch: branch.into(),
+        }
+    }
+
+    fn path(&self, more: &str) -> String {
+        format!(
+            "/repos/{}/{}/branches/{}/protection{}",
+            self.owner, self.repo, self.branch, more
+        )
+    }
+
+    /// get the branch's protection. fails with a 404 when the branch isn't
+    /// protected
+    pub fn get(&self) -> Future<ProtectionState> {
+        self.github
+            .get_media(&self.path(""), MediaType::Preview("luke-cage"))
+    }
+
+    /// replace the branch's protection
+    pub fn update(&self, pro: &Protection) -> Future<ProtectionState> {
+        self.github
+            .put_media(&self.path(""), json!(pro), MediaType::Preview("luke-cage"))
+    }
+
+    /// remove all of the branch's protection
+    pub fn delete(&self) -> Future<()> {
+        self.github.delete(&self.path(""))
+    }
+
+    /// get the status checks required to pass before merging
+    pub fn required_status_checks(&self) -> Future<StatusChecks> {
+        self.github.get(&self.path("/required_status_checks"))
+    }
+
+    /// update the status checks required to pass before merging
+    pub fn update_required_status_checks(
+        &self,
+        checks: &StatusChecksOptions,
+    ) -> Future<StatusChecks> {
+        self.github
+            .patch(&self.path("/required_status_checks"), json!(checks))
+    }
+
+    /// stop requiring status checks to pass before merging
+    pub fn remove_required_status_checks(&self) -> Future<()> {
+        self.github.delete(&self.path("/required_status_checks"))
+    }
+
+    /// list the names of the required status checks
+    pub fn contexts(&self) -> Future<Vec<String>> {
+        self.github
+            .get(&self.path("/required_status_checks/contexts"))
+    }
+
+    /// require more status checks, returning all required status checks
+    pub fn add_contexts(&self, contexts: &[&str]) -> Future<Vec<String>> {
+        self.github.post(
+            &self.path("/required_status_checks/contexts"),
+            json_lit!({ "contexts": contexts }),
+        )
+    }
+
+    /// replace the required status checks, returning them
+    pub fn set_contexts(&self, contexts: &[&str]) -> Future<Vec<String>> {
+        self.github.put(
+            &self.path("/required_status_checks/contexts"),
+            json_lit!({ "contexts": contexts }),
+        )
+    }
+
+    /// stop requiring some status checks
+    pub fn remove_contexts(&self, contexts: &[&str]) -> Future<()> {
+        self.github.delete_message(
+            &self.path("/required_status_checks/contexts"),
+            json_lit!({ "contexts": contexts }),
+        )
+    }
+
+    /// get whether the branch's protection applies to administrators
+    pub fn enforce_admins(&self) -> Future<EnforceAdmins> {
+        self.github.get(&self.path("/enforce_admins"))
+    }
+
+    /// apply the branch's protection to administrators
+    pub fn enable_enforce_admins(&self) -> Future<EnforceAdmins> {
+        self.github.post(&self.path("/enforce_admins"), Vec::new())
+    }
+
+    /// let administrators bypass the branch's protection
+    pub fn disable_enforce_admins(&self) -> Future<()> {
+        self.github.delete(&self.path("/enforce_admins"))
+    }
+
+    /// get whether commits pushed to the branch must be signed
+    pub fn required_signatures(&self) -> Future<ProtectionSetting> {
+        self.github.get(&self.path("/required_signatures"))
+    }
+
+    /// require commits pushed to the branch to be signed
+    pub fn enable_required_signatures(&self) -> Future<ProtectionSetting> {
+        self.github
+            .post(&self.path("/required_signatures"), Vec::new())
+    }
+
+    /// stop requiring commits pushed to the branch to be signed
+    pub fn disable_required_signatures(&self) -> Future<()> {
+        self.github.delete(&self.path("/required_signatures"))
+    }
+
+    /// get the reviews required before merging
+    pub fn required_pull_request_reviews(&self) -> Future<PullRequestReviewsState> {
+        self.github
+            .get(&self.path("/required_pull_request_reviews"))
+    }
+
+    /// update the reviews required before merging
+    pub fn update_required_pull_request_reviews(
+        &self,
+        reviews: &RequiredPullRequestReviews,
+    ) -> Future<PullRequestReviewsState> {
+        self.github
+            .patch(&self.path("/required_pull_request_reviews"), json!(reviews))
+    }
+
+    /// stop requiring reviews before merging
+    pub fn remove_required_pull_request_reviews(&self) -> Future<()> {
+        self.github
+            .delete(&self.path("/required_pull_request_reviews"))
+    }
+
+    /// get who may push to the branch. only available for organization
+    /// repositories
+    pub fn restrictions(&self) -> Future<RestrictionsState> {
+        self.github.get(&self.path("/restrictions"))
+    }
+
+    /// let anyone with write access push to the branch
+    pub fn remove_restrictions(&self) -> Future<()> {
+        self.github.delete(&self.path("/restrictions"))
+    }
+
+    /// list the apps which may push to the branch
+    pub fn restricted_apps(&self) -> Future<Vec<RestrictedApp>> {
+        self.github.get(&self.path("/restrictions/apps"))
+    }
+
+    /// let more apps, by slug, push to the branch
+    pub fn add_restricted_apps(&self, apps: &[&str]) -> Future<Vec<RestrictedApp>> {
+        self.github.post(
+            &self.path("/restrictions/apps"),
+            json_lit!({ "apps": apps }),
+        )
+    }
+
+    /// replace the apps which may push to the branch
+    pub fn set_restricted_apps(&self, apps: &[&str]) -> Future<Vec<RestrictedApp>> {
+        self.github.put(
+            &self.path("/restrictions/apps"),
+            json_lit!({ "apps": apps }),
+        )
+    }
+
+    /// stop letting some apps push to the branch
+    pub fn remove_restricted_apps(&self, apps: &[&str]) -> Future<()> {
+        self.github.delete_message(
+            &self.path("/restrictions/apps"),
+            json_lit!({ "apps": apps }),
+        )
+    }
+
+    /// list the teams which may push to the branch
+    pub fn restricted_teams(&self) -> Future<Vec<Team>> {
+        self.github.get(&self.path("/restrictions/teams"))
+    }
+
+    /// let more teams, by slug, push to the branch
+    pub fn add_restricted_teams(&self, teams: &[&str]) -> Future<Vec<Team>> {
+        self.github.post(
+            &self.path("/restrictions/teams"),
+            json_lit!({ "teams": teams }),
+        )
+    }
+
+    /// replace the teams which may push to the branch
+    pub fn set_restricted_teams(&self, teams: &[&str]) -> Future<Vec<Team>> {
+        self.github.put(
+            &self.path("/restrictions/teams"),
+            json_lit!({ "teams": teams }),
+        )
+    }
+
+    /// stop letting some teams push to the branch
+    pub fn remove_restricted_teams(&self, teams: &[&str]) -> Future<()> {
+        self.github.delete_message(
+            &self.path("/restrictions/teams"),
+            json_lit!({ "teams": teams }),
+        )
+    }
+
+    /// list the users who may push to the branch
+    pub fn restricted_users(&self) -> Future<Vec<User>> {
+        self.github.get(&self.path("/restrictions/users"))
+    }
+
+    /// let more users, by login, push to the branch
+    pub fn add_restricted_users(&self, users: &[&str]) -> Future<Vec<User>> {
+        self.github.post(
+            &self.path("/restrictions/users"),
+            json_lit!({ "users": users }),
+        )
+    }
+
+    /// replace the users who may push to the branch
+    pub fn set_restricted_users(&self, users: &[&str]) -> Future<Vec<User>> {
+        self.github.put(
+            &self.path("/restrictions/users"),
+            json_lit!({ "users": users }),
+        )
+    }
+
+    /// stop letting some users push to the branch
+    pub fn remove_restricted_users(&self, users: &[&str]) -> Future<()> {
+        self.github.delete_message(
+            &self.path("/restrictions/users"),
+            json_lit!({ "users": users }),
+        )
+    }
+}
+
 // representations
 
 #[derive(Debug, Deserialize)]
 pub struct Branch {
     pub name: String,
+    /// the branch's head
+    pub commit: BranchCommit,
     pub protected: Option<bool>,
     pub protection_url: Option<String>,
-    // todo: commit ref
+    /// only provided when getting a single branch
+    pub _links: Option<BranchLinks>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize)]
+pub struct BranchCommit {
+    pub sha: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BranchLinks {
+    #[serde(rename = "self")]
+    pub _self: String,
+    pub html: String,
+}
+
+#[derive(Default)]
+pub struct BranchListOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl BranchListOptions {
+    pub fn builder() -> BranchListOptionsBuilder {
+        BranchListOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            let encoded: String = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&self.params)
+                .finish();
+            Some(encoded)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BranchListOptionsBuilder(BranchListOptions);
+
+impl BranchListOptionsBuilder {
+    /// only list protected branches, or only unprotected ones
+    pub fn protected(&mut self, protected: bool) -> &mut Self {
+        self.0.params.insert("protected", protected.to_string());
+        self
+    }
+
+    pub fn per_page(&mut self, n: usize) -> &mut Self {
+        self.0.params.insert("per_page", n.to_string());
+        self
+    }
+
+    pub fn build(&self) -> BranchListOptions {
+        BranchListOptions {
+            params: self.0.params.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BranchMerge {
+    base: String,
+    head: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_message: Option<String>,
+}
+
+/// How a fork's branch was brought up to date
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpstreamMergeType {
+    Merge,
+    FastForward,
+    /// the branch was already up to date
+    None,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpstreamMerge {
+    pub message: String,
+    pub merge_type: UpstreamMergeType,
+    /// the upstream branch merged, like `upstream-owner:main`
+    pub base_branch: String,
+}
+
+/// A branch's protection, as read back from GitHub
+#[derive(Debug, Deserialize)]
 pub struct ProtectionState {
+    pub url: Option<String>,
     pub required_status_checks: Option<StatusChecks>,
     pub enforce_admins: Option<EnforceAdmins>,
-    //pub required_pull_request_reviews: Option<RequiredPullRequestReviews>,
-    //pub restrictions: Option<Restrictions>,
+    pub required_pull_request_reviews: Option<PullRequestReviewsState>,
+    pub restrictions: Option<RestrictionsState>,
+    pub required_signatures: Option<ProtectionSetting>,
+    pub required_linear_history: Option<ProtectionSetting>,
+    pub allow_force_pushes: Option<ProtectionSetting>,
+    pub allow_deletions: Option<ProtectionSetting>,
+    pub required_conversation_resolution: Option<ProtectionSetting>,
+    pub block_creations: Option<ProtectionSetting>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -120,36 +553,222 @@ pub struct EnforceAdmins {
     pub enabled: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A protection rule which is either enabled or not
+#[derive(Debug, Deserialize)]
+pub struct ProtectionSetting {
+    pub url: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequestReviewsState {
+    pub url: Option<String>,
+    /// who may dismiss reviews. when absent, anyone with write access may
+    pub dismissal_restrictions: Option<RestrictionsState>,
+    pub dismiss_stale_reviews: bool,
+    pub require_code_owner_reviews: bool,
+    #[serde(default)]
+    pub required_approving_review_count: u8,
+    #[serde(default)]
+    pub require_last_push_approval: bool,
+    /// who may merge without the required reviews
+    pub bypass_pull_request_allowances: Option<RestrictionsState>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestrictionsState {
+    pub url: Option<String>,
+    pub users: Vec<User>,
+    pub teams: Vec<Team>,
+    #[serde(default)]
+    pub apps: Vec<RestrictedApp>,
+}
+
+/// A GitHub App which may push to a protected branch
+#[derive(Debug, Deserialize)]
+pub struct RestrictedApp {
+    pub id: u64,
+    pub slug: String,
+    pub name: String,
+    pub owner: Option<User>,
+    pub description: Option<String>,
+    pub html_url: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Protection {
     pub required_status_checks: Option<StatusChecks>,
     pub enforce_admins: bool,
     pub required_pull_request_reviews: Option<RequiredPullRequestReviews>,
+    /// who may push to the branch. only available for organization
+    /// repositories
     pub restrictions: Option<Restrictions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_linear_history: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_force_pushes: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_deletions: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_conversation_resolution: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_creations: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Restrictions {
     pub users: Vec<String>,
     pub teams: Vec<String>,
+    /// app slugs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct RequiredPullRequestReviews {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dismissal_restrictions: Option<Restrictions>,
     pub dismiss_stale_reviews: bool,
     pub require_code_owner_reviews: bool,
     pub required_approving_review_count: u8,
+    /// require approval from someone other than the last pusher
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_last_push_approval: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct StatusChecks {
+    /// require branches to be up to date with the base branch
     pub strict: bool,
+    /// superseded by `checks`
+    #[serde(default)]
     pub contexts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<StatusCheck>,
+}
+
+/// A required status check, optionally expected from a specific app
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StatusCheck {
+    pub context: String,
+    /// when absent, any app may set the status
+    pub app_id: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct StatusChecksOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contexts: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<Vec<StatusCheck>>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Rename {
     pub new_name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_branch() {
+        let branch: Branch = serde_json::from_value(serde_json::json!({
+            "name": "main",
+            "commit": {
+                "sha": "7fd1a60b01f91b314f59955a4e4d4e80d8edf11d",
+                "url": "https://api.github.com/repos/octocat/hello-world/commits/7fd1a60b01f91b314f59955a4e4d4e80d8edf11d"
+            },
+            "protected": true,
+            "protection_url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection"
+        }))
+        .unwrap();
+        assert_eq!(
+            branch.commit.sha,
+            "7fd1a60b01f91b314f59955a4e4d4e80d8edf11d"
+        );
+        assert!(branch._links.is_none());
+        assert_eq!(
+            BranchListOptions::builder()
+                .protected(true)
+                .build()
+                .serialize(),
+            Some("protected=true".into())
+        );
+    }
+
+    #[test]
+    fn serialize_protection() {
+        let protection = Protection {
+            required_status_checks: Some(StatusChecks {
+                strict: true,
+                contexts: vec!["ci".into()],
+                ..Default::default()
+            }),
+            enforce_admins: true,
+            allow_force_pushes: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&protection).unwrap(),
+            serde_json::json!({
+                "required_status_checks": { "strict": true, "contexts": ["ci"] },
+                "enforce_admins": true,
+                "required_pull_request_reviews": null,
+                "restrictions": null,
+                "allow_force_pushes": false
+            })
+        );
+    }
+
+    #[test]
+    fn deserialize_protection_state() {
+        let state: ProtectionState = serde_json::from_value(serde_json::json!({
+            "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection",
+            "required_status_checks": {
+                "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection/required_status_checks",
+                "strict": true,
+                "contexts": ["continuous-integration/travis-ci"],
+                "checks": [{ "context": "continuous-integration/travis-ci", "app_id": null }]
+            },
+            "required_pull_request_reviews": {
+                "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection/required_pull_request_reviews",
+                "dismiss_stale_reviews": true,
+                "require_code_owner_reviews": true,
+                "required_approving_review_count": 2,
+                "require_last_push_approval": true
+            },
+            "enforce_admins": {
+                "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection/enforce_admins",
+                "enabled": true
+            },
+            "required_signatures": {
+                "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection/required_signatures",
+                "enabled": false
+            },
+            "required_linear_history": { "enabled": true },
+            "allow_force_pushes": { "enabled": false },
+            "allow_deletions": { "enabled": false },
+            "required_conversation_resolution": { "enabled": true }
+        }))
+        .unwrap();
+        let checks = state.required_status_checks.unwrap();
+        assert_eq!(
+            checks.checks,
+            vec![StatusCheck {
+                context: "continuous-integration/travis-ci".into(),
+                app_id: None
+            }]
+        );
+        let reviews = state.required_pull_request_reviews.unwrap();
+        assert_eq!(reviews.required_approving_review_count, 2);
+        assert!(reviews.dismissal_restrictions.is_none());
+        assert!(state.enforce_admins.unwrap().enabled);
+        assert!(state.required_linear_history.unwrap().enabled);
+        assert!(!state.allow_force_pushes.unwrap().enabled);
+        assert!(state.restrictions.is_none());
+    }
+}
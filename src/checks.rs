@@ -1,8 +1,9 @@
 //! Checks interface
 // see: https://developer.github.com/v3/checks/suites/
+use futures::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use self::super::{AuthenticationConstraint, Future, Github, MediaType};
+use self::super::{AuthenticationConstraint, Future, Github, MediaType, Paginator};
 
 pub struct CheckRuns {
     github: Github,
@@ -10,7 +11,7 @@ pub struct CheckRuns {
     repo: String,
 }
 
-impl<'a> CheckRuns {
+impl CheckRuns {
     #[doc(hidden)]
     pub(crate) fn new<O, R>(github: Github, owner: O, repo: R) -> Self
     where
@@ -56,18 +57,44 @@ impl<'a> CheckRuns {
         }
     }
 
+    /// lists the check runs of a check suite
+    /// https://developer.github.com/v3/checks/runs/#list-check-runs-in-a-check-suite
     pub fn list_for_suite(&self, suite_id: &str) -> Future<Vec<CheckRun>> {
-        // !!! does this actually work?
-        // https://developer.github.com/v3/checks/runs/#list-check-runs-in-a-check-suite
-        self.github.get_media::<Vec<CheckRun>>(
-            &self.path(&format!("/{}/check-runs", suite_id)),
+        Box::pin(
+            self.github
+                .get_media::<CheckRunList>(
+                    &self.suite_path(suite_id),
+                    MediaType::Preview("antiope"),
+                )
+                .map_ok(|list| list.check_runs),
+        )
+    }
+
+    /// provides a stream over all pages of a check suite's check runs
+    pub fn iter_for_suite(&self, suite_id: &str) -> Paginator<CheckRun> {
+        self.github.get_paginator_media(
+            &self.suite_path(suite_id),
             MediaType::Preview("antiope"),
+            |list: CheckRunList| (list.check_runs, Some(list.total_count)),
+        )
+    }
+
+    fn suite_path(&self, suite_id: &str) -> String {
+        format!(
+            "/repos/{}/{}/check-suites/{}/check-runs",
+            self.owner, self.repo, suite_id
         )
     }
 }
 
 // representations
 
+#[derive(Debug, Deserialize)]
+struct CheckRunList {
+    total_count: u64,
+    check_runs: Vec<CheckRun>,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum CheckRunState {
@@ -218,5 +245,5 @@ pub struct CheckRun {
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct CheckSuite {
-    pub id: u32,
+    pub id: u64,
 }
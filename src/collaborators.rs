@@ -1,23 +1,20 @@
 use crate::users::User;
 use crate::Future;
-use crate::{Error, Github};
+use crate::{Error, Github, Paginator};
 use futures::prelude::*;
 use http::StatusCode;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::fmt;
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Permissions {
     Admin,
+    #[default]
     Push,
     Pull,
 }
 
-impl Default for Permissions {
-    fn default() -> Permissions {
-        Permissions::Push
-    }
-}
-
 impl fmt::Display for Permissions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -59,6 +56,23 @@ impl Collaborators {
         self.github.get::<Vec<User>>(&self.path(""))
     }
 
+    /// provides a stream over all pages of this repo's collaborators
+    pub fn iter(&self) -> Paginator<User> {
+        self.github.get_stream(&self.path(""))
+    }
+
+    /// provides a stream over the collaborators added to this repo directly,
+    /// rather than through its organization
+    pub fn iter_direct(&self) -> Paginator<User> {
+        self.github.get_stream(&self.path("?affiliation=direct"))
+    }
+
+    /// get a collaborator's permission on this repo
+    pub fn permission(&self, username: &str) -> Future<CollaboratorPermission> {
+        self.github
+            .get(&self.path(&format!("/{}/permission", username)))
+    }
+
     pub fn is_collaborator(&self, username: &str) -> Future<bool> {
         Box::pin(
             self.github
@@ -93,3 +107,12 @@ impl Collaborators {
         self.github.delete(&self.path(&format!("/{}", username)))
     }
 }
+
+#[derive(Debug, Deserialize)]
+pub struct CollaboratorPermission {
+    /// one of "admin", "write", "read" or "none"
+    pub permission: String,
+    /// the collaborator's role, which may also be "maintain" or "triage"
+    pub role_name: Option<String>,
+    pub user: Option<User>,
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use url::form_urlencoded;
 
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator};
 
 /// A structure for interfacing with a issue comments
 pub struct Comments {
@@ -44,6 +44,15 @@ impl Comments {
         self.github.get(&uri.join("?"))
     }
 
+    /// provides a stream over all pages of this issue's comments
+    pub fn iter(&self, options: &CommentListOptions) -> Paginator<Comment> {
+        let mut uri = vec![self.path()];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
+
     fn path(&self) -> String {
         format!(
             "/repos/{}/{}/issues/{}/comments",
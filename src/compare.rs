@@ -0,0 +1,128 @@
+//! Compare interface
+//!
+//! See the [github docs](https://docs.github.com/en/rest/commits/commits#compare-two-commits)
+//! for more information
+use serde::Deserialize;
+
+use crate::pulls::FileDiff;
+use crate::repo_commits::RepoCommit;
+use crate::{Future, Github, MediaType, Paginator};
+
+/// A structure for interfacing with the comparison of two commits
+pub struct Compare {
+    github: Github,
+    owner: String,
+    repo: String,
+    base: String,
+    head: String,
+}
+
+impl Compare {
+    #[doc(hidden)]
+    pub fn new<O, R, B, H>(github: Github, owner: O, repo: R, base: B, head: H) -> Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+        B: Into<String>,
+        H: Into<String>,
+    {
+        Compare {
+            github,
+            owner: owner.into(),
+            repo: repo.into(),
+            base: base.into(),
+            head: head.into(),
+        }
+    }
+
+    fn path(&self) -> String {
+        format!(
+            "/repos/{}/{}/compare/{}...{}",
+            self.owner, self.repo, self.base, self.head
+        )
+    }
+
+    /// Request the comparison. Only the first 250 commits of the range are
+    /// included, use `iter_commits` to list every commit
+    pub fn get(&self) -> Future<Comparison> {
+        self.github.get(&self.path())
+    }
+
+    /// provides a stream over all pages of the commits reachable from head
+    /// but not from base, oldest first
+    pub fn iter_commits(&self) -> Paginator<RepoCommit> {
+        self.github
+            .get_paginator(&self.path(), |comparison: Comparison| {
+                (comparison.commits, Some(comparison.total_commits))
+            })
+    }
+
+    /// Request the comparison as a unified diff, which may be parsed with
+    /// `hubcaps::diff::Diff::parse`
+    pub fn diff(&self) -> Future<String> {
+        self.github.get_text(&self.path(), MediaType::Diff)
+    }
+
+    /// Request the comparison's commits as a patch series
+    pub fn patch(&self) -> Future<String> {
+        self.github.get_text(&self.path(), MediaType::Patch)
+    }
+}
+
+// representations
+
+/// How head relates to base
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareStatus {
+    /// head has commits base doesn't
+    Ahead,
+    /// base has commits head doesn't
+    Behind,
+    /// both have commits the other doesn't
+    Diverged,
+    Identical,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Comparison {
+    pub url: String,
+    pub html_url: String,
+    pub permalink_url: String,
+    pub diff_url: String,
+    pub patch_url: String,
+    pub base_commit: RepoCommit,
+    /// the best common ancestor of base and head
+    pub merge_base_commit: RepoCommit,
+    pub status: CompareStatus,
+    pub ahead_by: u64,
+    pub behind_by: u64,
+    /// the number of commits reachable from head but not from base
+    pub total_commits: u64,
+    /// the commits reachable from head but not from base, oldest first
+    pub commits: Vec<RepoCommit>,
+    /// the files changed between the merge base and head. only provided on
+    /// the first page of a comparison
+    #[serde(default)]
+    pub files: Vec<FileDiff>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_statuses() {
+        for (json, status) in &[
+            (r#""ahead""#, CompareStatus::Ahead),
+            (r#""behind""#, CompareStatus::Behind),
+            (r#""diverged""#, CompareStatus::Diverged),
+            (r#""identical""#, CompareStatus::Identical),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<CompareStatus>(json).unwrap(),
+                *status
+            );
+        }
+    }
+}
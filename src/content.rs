@@ -3,12 +3,18 @@ use std::fmt;
 use std::ops;
 
 use data_encoding::BASE64;
+use futures::prelude::*;
+use http::StatusCode;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
+use crate::errors::{ClientError, Error};
+use crate::git::{CommitBuilder, Git};
 use crate::repo_commits::CommitDetails;
+use crate::repositories::Repo;
 use crate::utils::{percent_encode, PATH};
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, MediaType, Paginator, Stream};
 
 /// Provides access to the content information for a repository
 pub struct Content {
@@ -50,14 +56,115 @@ impl Content {
 
     /// Information on a single file.
     ///
-    /// GitHub only supports downloading files up to 1 megabyte in size. If you
-    /// need to retrieve larger files, the Git Data API must be used instead.
+    /// GitHub only supports downloading files up to 1 megabyte in size. Use
+    /// `download` to retrieve larger files.
     pub fn file(&self, location: &str, ref_: &str) -> Future<File> {
         self.github.get(&self.path(location, ref_))
     }
 
+    /// Streams the raw bytes of a file, without buffering them.
+    ///
+    /// Files too large for the contents API are downloaded from the Git
+    /// Data API instead, which supports blobs up to 100 megabytes in size.
+    ///
+    /// ```no_run
+    /// # use futures::prelude::*;
+    /// # async fn run(github: hubcaps::Github) -> hubcaps::Result<()> {
+    /// let mut bytes = github
+    ///     .repo("owner", "repo")
+    ///     .content()
+    ///     .download("/assets/dataset.csv", "main");
+    /// while let Some(chunk) = bytes.try_next().await? {
+    ///     println!("{} bytes", chunk.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn download(&self, location: &str, ref_: &str) -> Stream<Vec<u8>> {
+        let github = self.github.clone();
+        let path = self.path(location, ref_);
+        let content = Content::new(self.github.clone(), self.owner.as_str(), self.repo.as_str());
+        let location = location.to_string();
+        let ref_ = ref_.to_string();
+        Box::pin(
+            async move {
+                match github.get_bytes(&path, MediaType::Raw).await {
+                    Err(ref err) if too_large(err) => {
+                        let sha = content.sha(&location, &ref_).await?;
+                        Ok(Git::new(github, content.owner, content.repo).download_blob(sha))
+                    }
+                    bytes => bytes,
+                }
+            }
+            .try_flatten_stream(),
+        )
+    }
+
+    /// Downloads a file into `writer`, resolving to the number of bytes
+    /// written. See `download`.
+    pub fn download_to<W>(&self, location: &str, ref_: &str, mut writer: W) -> Future<u64>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut bytes = self.download(location, ref_);
+        Box::pin(async move {
+            let mut written = 0;
+            while let Some(chunk) = bytes.try_next().await? {
+                writer.write_all(&chunk).await?;
+                written += chunk.len() as u64;
+            }
+            writer.flush().await?;
+            Ok(written)
+        })
+    }
+
+    /// the blob sha of a file, looked up in its directory's git tree, which
+    /// unlike the file itself is available whatever the file's size
+    fn sha(&self, location: &str, ref_: &str) -> Future<String> {
+        let location = location.trim_matches('/');
+        let (directory, name) = match location.rfind('/') {
+            Some(index) => (
+                location[..index].to_string(),
+                location[index + 1..].to_string(),
+            ),
+            None => (String::new(), location.to_string()),
+        };
+        let github = self.github.clone();
+        let repo = format!("/repos/{}/{}", self.owner, self.repo);
+        let git = Git::new(self.github.clone(), self.owner.as_str(), self.repo.as_str());
+        let ref_ = ref_.to_string();
+        let missing = format!("{} not found", location);
+        Box::pin(async move {
+            let ref_ = if ref_.is_empty() {
+                github.get::<Repo>(&repo).await?.default_branch
+            } else {
+                ref_
+            };
+            let tree = git.tree(format!("{}:{}", ref_, directory), false).await?;
+            let fault = |code, message| Error::Fault {
+                code,
+                error: ClientError {
+                    message,
+                    errors: None,
+                    documentation_url: None,
+                },
+                request_id: None,
+            };
+            let truncated = tree.truncated;
+            match tree.tree.into_iter().find(|file| file.path == name) {
+                Some(file) => Ok(file.sha),
+                // a truncated listing may have left the file out
+                None if truncated => Err(fault(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!("directory {} has too many files to list", directory),
+                )),
+                None => Err(fault(StatusCode::NOT_FOUND, missing)),
+            }
+        })
+    }
+
     /// List the root directory.
-    pub fn root(&self, ref_: &str) -> Stream<DirectoryItem> {
+    pub fn root(&self, ref_: &str) -> Paginator<DirectoryItem> {
         self.iter("/", ref_)
     }
 
@@ -65,7 +172,7 @@ impl Content {
     ///
     /// GitHub limits the number of items returned to 1000 for this API. If you
     /// need to retrieve more items, the Git Data API must be used instead.
-    pub fn iter(&self, location: &str, ref_: &str) -> Stream<DirectoryItem> {
+    pub fn iter(&self, location: &str, ref_: &str) -> Paginator<DirectoryItem> {
         self.github.get_stream(&self.path(location, ref_))
     }
 
@@ -97,6 +204,20 @@ impl Content {
         self.github.put(&self.path(location, ""), json!(file))
     }
 
+    /// Stage changes to several files of a branch, to commit them all at
+    /// once rather than with a commit per file.
+    pub fn commit_builder<B>(&self, branch: B) -> CommitBuilder
+    where
+        B: Into<String>,
+    {
+        CommitBuilder::new(
+            self.github.clone(),
+            self.owner.as_str(),
+            self.repo.as_str(),
+            branch,
+        )
+    }
+
     /// Deletes a file.
     pub fn delete(&self, location: &str, message: &str, sha: &str) -> Future<()> {
         let file = &NewFile {
@@ -109,6 +230,21 @@ impl Content {
     }
 }
 
+/// whether the contents api refused to serve a file because of its size
+fn too_large(err: &Error) -> bool {
+    match err {
+        Error::Fault { code, error, .. } => {
+            *code == StatusCode::FORBIDDEN
+                && error
+                    .errors
+                    .iter()
+                    .flatten()
+                    .any(|err| err.code == "too_large")
+        }
+        _ => false,
+    }
+}
+
 /// Contents of a path in a repository.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -274,3 +410,159 @@ impl<'de> Deserialize<'de> for DecodedContents {
         deserializer.deserialize_str(DecodedContentsVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transport;
+    use http::header::ACCEPT;
+    use http::{Request, Response};
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::AsyncReadExt;
+
+    /// responds with scripted bodies, streaming them a few bytes at a time
+    #[derive(Clone, Debug, Default)]
+    struct Scripted {
+        responses: Arc<Mutex<VecDeque<(StatusCode, &'static str)>>>,
+        requested: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Scripted {
+        fn respond(&self, status: StatusCode, body: &'static str) {
+            self.responses.lock().unwrap().push_back((status, body));
+        }
+
+        fn respond_to(&self, request: &Request<Vec<u8>>) -> Response<&'static str> {
+            self.requested.lock().unwrap().push(format!(
+                "{} {}",
+                request.headers()[ACCEPT].to_str().unwrap(),
+                request.uri()
+            ));
+            let (status, body) = self
+                .responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected request");
+            let mut response = Response::new(body);
+            *response.status_mut() = status;
+            response
+        }
+    }
+
+    impl Transport for Scripted {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Vec<u8>>> {
+            let response = self
+                .respond_to(&request)
+                .map(|body| body.as_bytes().to_vec());
+            Box::pin(future::ok(response))
+        }
+
+        fn send_streaming(&self, request: Request<Vec<u8>>) -> Future<Response<Stream<Vec<u8>>>> {
+            let response = self.respond_to(&request).map(|body| {
+                let chunks = body
+                    .as_bytes()
+                    .chunks(4)
+                    .map(|chunk| Ok(chunk.to_vec()))
+                    .collect::<Vec<_>>();
+                Box::pin(stream::iter(chunks)) as Stream<Vec<u8>>
+            });
+            Box::pin(future::ok(response))
+        }
+    }
+
+    #[cfg(not(feature = "httpcache"))]
+    fn content(transport: Scripted) -> Content {
+        Content::new(
+            Github::custom("https://api.github.com", "test", None, transport),
+            "o",
+            "r",
+        )
+    }
+
+    #[cfg(feature = "httpcache")]
+    fn content(transport: Scripted) -> Content {
+        Content::new(
+            Github::custom(
+                "https://api.github.com",
+                "test",
+                None,
+                transport,
+                <dyn crate::HttpCache>::noop(),
+            ),
+            "o",
+            "r",
+        )
+    }
+
+    #[tokio::test]
+    async fn downloads_raw_content() {
+        let transport = Scripted::default();
+        transport.respond(StatusCode::OK, "hello, world");
+        let (mut reader, writer) = tokio::io::duplex(64);
+        let size = content(transport.clone())
+            .download_to("/docs/hello.txt", "main", writer)
+            .await
+            .unwrap();
+        assert_eq!(size, 12);
+        let mut written = Vec::new();
+        reader.read_to_end(&mut written).await.unwrap();
+        assert_eq!(written, b"hello, world");
+        assert_eq!(
+            *transport.requested.lock().unwrap(),
+            vec![
+                "application/vnd.github.v3.raw https://api.github.com/repos/o/r/contents/docs/hello.txt?ref=main"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn downloads_large_files_as_blobs() {
+        let transport = Scripted::default();
+        transport.respond(
+            StatusCode::FORBIDDEN,
+            r#"{"message":"too large","errors":[{"resource":"Blob","field":"data","code":"too_large"}]}"#,
+        );
+        transport.respond(
+            StatusCode::OK,
+            r#"{"sha":"def","url":"u","truncated":false,"tree":[{"path":"big.bin","mode":"100644","type":"blob","size":104857600,"sha":"abc","url":"u"}]}"#,
+        );
+        transport.respond(StatusCode::OK, "large file");
+        let chunks = content(transport.clone())
+            .download("/docs/big.bin", "main")
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(chunks.concat(), b"large file");
+        assert_eq!(
+            *transport.requested.lock().unwrap(),
+            vec![
+                "application/vnd.github.v3.raw https://api.github.com/repos/o/r/contents/docs/big.bin?ref=main",
+                "application/vnd.github.v3+json https://api.github.com/repos/o/r/git/trees/main:docs",
+                "application/vnd.github.v3.raw https://api.github.com/repos/o/r/git/blobs/abc",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn large_files_missing_from_truncated_trees_fail() {
+        let transport = Scripted::default();
+        transport.respond(
+            StatusCode::FORBIDDEN,
+            r#"{"message":"too large","errors":[{"resource":"Blob","field":"data","code":"too_large"}]}"#,
+        );
+        transport.respond(
+            StatusCode::OK,
+            r#"{"sha":"def","url":"u","truncated":true,"tree":[]}"#,
+        );
+        match content(transport)
+            .download("/docs/big.bin", "main")
+            .try_collect::<Vec<_>>()
+            .await
+        {
+            Err(Error::Fault { code, .. }) => assert_eq!(code, StatusCode::UNPROCESSABLE_ENTITY),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
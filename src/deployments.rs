@@ -6,7 +6,7 @@ use url::form_urlencoded;
 
 use crate::statuses::State;
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator};
 
 /// Interface for repository deployments
 pub struct Deployments {
@@ -50,6 +50,11 @@ impl DeploymentStatuses {
         self.github.get(&self.path(""))
     }
 
+    /// provides a stream over all pages of this deployment's statuses
+    pub fn iter(&self) -> Paginator<DeploymentStatus> {
+        self.github.get_stream(&self.path(""))
+    }
+
     /// creates a new deployment status. For convenience, a DeploymentStatusOptions.builder
     /// interface is required for building up a request
     pub fn create(&self, status: &DeploymentStatusOptions) -> Future<DeploymentStatus> {
@@ -84,6 +89,15 @@ impl Deployments {
         self.github.get(&uri.join("?"))
     }
 
+    /// provides a stream over all pages of this repo's deployments
+    pub fn iter(&self, opts: &DeploymentListOptions) -> Paginator<Deployment> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = opts.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
+
     /// creates a new deployment for this repository
     pub fn create(&self, dep: &DeploymentOptions) -> Future<Deployment> {
         self.github.post(&self.path(""), json!(dep))
@@ -0,0 +1,380 @@
+//! Unified diffs
+//!
+//! Parses the unified diffs and patches GitHub serves for pull requests,
+//! commits and comparisons into files, hunks and lines, numbering each line
+//! on both sides of the diff along with the diff position GitHub uses to
+//! anchor review comments.
+//!
+//! ```no_run
+//! use hubcaps::diff::Diff;
+//! use hubcaps::review_comments::{ReviewCommentOptions, Side};
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", None)?;
+//! let pull = github.repo("softprops", "hubcaps").pulls().get(1);
+//! let diff = Diff::parse(&pull.diff().await?);
+//! let head = pull.get().await?.head.sha;
+//! if let Some(file) = diff.file("src/lib.rs") {
+//!     // only lines included in the diff may be commented on
+//!     if file.line(Side::Right, 42).is_some() {
+//!         pull.review_comments()
+//!             .create(&ReviewCommentOptions::new(head, "src/lib.rs", 42, "nit"))
+//!             .await?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::review_comments::Side;
+
+const DEV_NULL: &str = "/dev/null";
+
+/// A parsed unified diff
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diff {
+    pub files: Vec<DiffFile>,
+}
+
+impl Diff {
+    /// Parse the output of `git diff` or `git format-patch`. Lines which
+    /// aren't part of a file's diff, like commit messages and diff stats,
+    /// are skipped
+    pub fn parse(diff: &str) -> Self {
+        let mut files: Vec<DiffFile> = Vec::new();
+        // the numbers of old and new lines remaining in the current hunk
+        let mut remaining = (0, 0);
+        // the old and new line numbers of the current hunk's next line
+        let mut next = (0, 0);
+        for line in diff.lines() {
+            if remaining != (0, 0) {
+                let file = files.last_mut().expect("hunks belong to a file");
+                let hunk = file.hunks.last_mut().expect("lines belong to a hunk");
+                let (kind, content) = match line.chars().next() {
+                    Some('+') => (LineKind::Addition, &line[1..]),
+                    Some('-') => (LineKind::Deletion, &line[1..]),
+                    Some(' ') => (LineKind::Context, &line[1..]),
+                    // marks the preceding line as lacking a trailing newline
+                    Some('\\') => continue,
+                    // some tools strip the space of empty context lines
+                    None => (LineKind::Context, ""),
+                    _ => {
+                        remaining = (0, 0);
+                        continue;
+                    }
+                };
+                let old_line = match kind {
+                    LineKind::Addition => None,
+                    _ => {
+                        remaining.0 -= remaining.0.min(1);
+                        let line = next.0;
+                        next.0 += 1;
+                        Some(line)
+                    }
+                };
+                let new_line = match kind {
+                    LineKind::Deletion => None,
+                    _ => {
+                        remaining.1 -= remaining.1.min(1);
+                        let line = next.1;
+                        next.1 += 1;
+                        Some(line)
+                    }
+                };
+                file.position += 1;
+                hunk.lines.push(Line {
+                    kind,
+                    content: content.to_string(),
+                    old_line,
+                    new_line,
+                    position: file.position,
+                });
+                continue;
+            }
+            if let Some(paths) = line.strip_prefix("diff --git ") {
+                files.push(DiffFile::from_git_header(paths));
+            } else if let Some(file) = files.last_mut() {
+                if let Some(hunk) = Hunk::parse_header(line) {
+                    remaining = (hunk.old_lines, hunk.new_lines);
+                    next = (hunk.old_start, hunk.new_start);
+                    // the first hunk's header is position 0, later headers
+                    // are counted like any other line
+                    if !file.hunks.is_empty() {
+                        file.position += 1;
+                    }
+                    file.hunks.push(hunk);
+                } else if let Some(path) = line.strip_prefix("--- ") {
+                    file.old_path = parse_path(path, "a/");
+                } else if let Some(path) = line.strip_prefix("+++ ") {
+                    file.new_path = parse_path(path, "b/");
+                } else if let Some(path) = line.strip_prefix("rename from ") {
+                    file.old_path = Some(path.to_string());
+                } else if let Some(path) = line.strip_prefix("rename to ") {
+                    file.new_path = Some(path.to_string());
+                } else if line.starts_with("new file mode") {
+                    file.old_path = None;
+                } else if line.starts_with("deleted file mode") {
+                    file.new_path = None;
+                } else if line.starts_with("Binary files") || line == "GIT binary patch" {
+                    file.binary = true;
+                }
+            }
+        }
+        Diff { files }
+    }
+
+    /// Return the file at `path`, matching either side of renames
+    pub fn file(&self, path: &str) -> Option<&DiffFile> {
+        self.files.iter().find(|file| {
+            file.new_path.as_deref() == Some(path) || file.old_path.as_deref() == Some(path)
+        })
+    }
+}
+
+/// The changes to a single file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffFile {
+    /// None for added files
+    pub old_path: Option<String>,
+    /// None for deleted files
+    pub new_path: Option<String>,
+    /// binary files have no hunks
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+    /// the position of the last line parsed
+    position: u64,
+}
+
+impl DiffFile {
+    fn from_git_header(paths: &str) -> Self {
+        // ambiguous for paths containing " b/", which the `---` and `+++`
+        // lines that follow for textual changes correct
+        let (old_path, new_path) = match paths.find(" b/") {
+            Some(index) => (
+                parse_path(&paths[..index], "a/"),
+                parse_path(&paths[index + 1..], "b/"),
+            ),
+            None => (None, None),
+        };
+        DiffFile {
+            old_path,
+            new_path,
+            ..DiffFile::default()
+        }
+    }
+
+    /// Return the file's path, after any rename
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    pub fn is_added(&self) -> bool {
+        self.old_path.is_none()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.new_path.is_none()
+    }
+
+    pub fn is_renamed(&self) -> bool {
+        matches!((&self.old_path, &self.new_path), (Some(old), Some(new)) if old != new)
+    }
+
+    /// Return an iterator over the lines of every hunk
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.hunks.iter().flat_map(|hunk| hunk.lines.iter())
+    }
+
+    /// Return the diff's line for `line` of the given side of the file, if
+    /// the diff includes it. `Side::Left` numbers lines of the file before
+    /// the change, `Side::Right` after it
+    pub fn line(&self, side: Side, line: u64) -> Option<&Line> {
+        self.lines().find(|candidate| match side {
+            Side::Left => candidate.old_line == Some(line),
+            Side::Right => candidate.new_line == Some(line),
+        })
+    }
+}
+
+/// A contiguous region of changes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Hunk {
+    pub old_start: u64,
+    pub old_lines: u64,
+    pub new_start: u64,
+    pub new_lines: u64,
+    /// the text following the hunk's range, typically the enclosing function
+    pub section: String,
+    pub lines: Vec<Line>,
+}
+
+impl Hunk {
+    /// Parse a header like `@@ -1,5 +1,6 @@ fn main() {`
+    fn parse_header(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix("@@ -")?;
+        let (ranges, section) = rest.split_at(rest.find(" @@")?);
+        let (old, new) = ranges.split_at(ranges.find(" +")?);
+        let range = |range: &str| -> Option<(u64, u64)> {
+            match range.find(',') {
+                Some(index) => Some((
+                    range[..index].parse().ok()?,
+                    range[index + 1..].parse().ok()?,
+                )),
+                None => Some((range.parse().ok()?, 1)),
+            }
+        };
+        let (old_start, old_lines) = range(old)?;
+        let (new_start, new_lines) = range(&new[2..])?;
+        Some(Hunk {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+            section: section[3..].trim_start().to_string(),
+            lines: Vec::new(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineKind {
+    Context,
+    Addition,
+    Deletion,
+}
+
+/// A line of a hunk
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub kind: LineKind,
+    /// the line's text, without its `+`, `-` or ` ` prefix
+    pub content: String,
+    /// the line's number in the file before the change. None for additions
+    pub old_line: Option<u64>,
+    /// the line's number in the file after the change. None for deletions
+    pub new_line: Option<u64>,
+    /// the line's position in the file's diff, as accepted by
+    /// `ReviewCommentOptions::position`
+    pub position: u64,
+}
+
+impl Line {
+    /// Return the side of the diff review comments on this line apply to
+    pub fn side(&self) -> Side {
+        match self.kind {
+            LineKind::Deletion => Side::Left,
+            _ => Side::Right,
+        }
+    }
+}
+
+fn parse_path(path: &str, prefix: &str) -> Option<String> {
+    // git appends a tab to paths containing spaces
+    let path = path.trim_end_matches('\t');
+    if path == DEV_NULL {
+        None
+    } else {
+        Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "From 6dcb09b5b57875f334f61aebed695e2e4193db5e Mon Sep 17 00:00:00 2001
+From: Monalisa Octocat <octocat@github.com>
+Subject: [PATCH] Fix all the bugs
+
+---
+ README.md  | 3 ++-
+ src/new.rs | 1 +
+ 2 files changed, 3 insertions(+), 1 deletion(-)
+
+diff --git a/README.md b/README.md
+index 3f4e2a1..8d1c2b7 100644
+--- a/README.md
++++ b/README.md
+@@ -1,3 +1,4 @@
+ # hubcaps
+-a rust interface for github
++a Rust interface for GitHub
++
+ ## install
+@@ -10,2 +11,2 @@ usage
+ --- not a file header
+-old
+\\ No newline at end of file
++new
+\\ No newline at end of file
+diff --git a/src/new.rs b/src/new.rs
+new file mode 100644
+index 0000000..e69de29
+--- /dev/null
++++ b/src/new.rs
+@@ -0,0 +1 @@
++fn main() {}
+diff --git a/logo.png b/assets/logo.png
+similarity index 90%
+rename from logo.png
+rename to assets/logo.png
+Binary files a/logo.png and b/assets/logo.png differ
+--
+2.30.0
+";
+
+    #[test]
+    fn parses_files() {
+        let diff = Diff::parse(PATCH);
+        let paths = diff.files.iter().map(DiffFile::path).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["README.md", "src/new.rs", "assets/logo.png"]);
+        assert!(diff.files[1].is_added());
+        assert!(diff.files[2].is_renamed());
+        assert!(diff.files[2].binary);
+        assert_eq!(diff.file("logo.png"), Some(&diff.files[2]));
+
+        let added = &diff.files[1].hunks[0];
+        assert_eq!((added.new_start, added.new_lines), (1, 1));
+        assert_eq!(added.lines[0].new_line, Some(1));
+        assert_eq!(added.lines[0].old_line, None);
+    }
+
+    #[test]
+    fn numbers_lines() {
+        let diff = Diff::parse(PATCH);
+        let readme = &diff.files[0];
+        assert_eq!(readme.hunks.len(), 2);
+        assert_eq!(readme.hunks[1].section, "usage");
+
+        let numbered = readme
+            .lines()
+            .map(|line| (line.kind, line.old_line, line.new_line, line.position))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            numbered,
+            vec![
+                (LineKind::Context, Some(1), Some(1), 1),
+                (LineKind::Deletion, Some(2), None, 2),
+                (LineKind::Addition, None, Some(2), 3),
+                (LineKind::Addition, None, Some(3), 4),
+                (LineKind::Context, Some(3), Some(4), 5),
+                // the second hunk's header occupies position 6
+                (LineKind::Context, Some(10), Some(11), 7),
+                (LineKind::Deletion, Some(11), None, 8),
+                (LineKind::Addition, None, Some(12), 9),
+            ]
+        );
+        assert_eq!(
+            readme.lines().nth(5).unwrap().content,
+            "--- not a file header"
+        );
+
+        let line = readme.line(Side::Right, 12).unwrap();
+        assert_eq!((line.content.as_str(), line.side()), ("new", Side::Right));
+        assert_eq!(readme.line(Side::Left, 11).unwrap().side(), Side::Left);
+        assert_eq!(readme.line(Side::Right, 7), None);
+    }
+}
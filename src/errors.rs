@@ -1,6 +1,7 @@
 //! Client errors
 #[cfg(feature = "jwt")]
 use crate::jwt::errors::Error as JWTError;
+use http::Error as HttpError;
 use http::StatusCode;
 use reqwest::Error as ReqwestError;
 use serde::Deserialize;
@@ -21,6 +22,9 @@ pub enum Error {
     Fault {
         code: StatusCode,
         error: ClientError,
+        /// the `x-github-request-id` GitHub assigned the request, to quote
+        /// in support tickets
+        request_id: Option<String>,
     },
     /// Error kind returned when a credential's rate limit has been exhausted. Wait for the reset duration before issuing more requests
     RateLimit { reset: Duration },
@@ -28,10 +32,14 @@ pub enum Error {
     Codec(SerdeError),
     /// HTTP client errors
     Reqwest(ReqwestError),
+    /// HTTP request construction errors
+    Http(HttpError),
     /// Url format errors
     Url(ParseError),
     /// Network errors
     IO(IoError),
+    /// Errors reported in the response to a GraphQL query
+    GraphQL(Vec<GraphQLError>),
 
     #[cfg(feature = "jwt")]
     /// JWT validation errors
@@ -50,6 +58,12 @@ impl From<ReqwestError> for Error {
     }
 }
 
+impl From<HttpError> for Error {
+    fn from(err: HttpError) -> Self {
+        Error::Http(err)
+    }
+}
+
 impl From<ParseError> for Error {
     fn from(err: ParseError) -> Self {
         Error::Url(err)
@@ -74,6 +88,7 @@ impl StdError for Error {
         match self {
             Error::Codec(err) => Some(err),
             Error::Reqwest(err) => Some(err),
+            Error::Http(err) => Some(err),
             Error::Url(err) => Some(err),
             Error::IO(err) => Some(err),
             #[cfg(feature = "jwt")]
@@ -86,7 +101,16 @@ impl StdError for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::Fault { code, error } => write!(f, "{}: {}", code, error.message),
+            Error::Fault {
+                code,
+                error,
+                request_id: None,
+            } => write!(f, "{}: {}", code, error.message),
+            Error::Fault {
+                code,
+                error,
+                request_id: Some(request_id),
+            } => write!(f, "{}: {} (request id {})", code, error.message, request_id),
             Error::RateLimit { reset } => write!(
                 f,
                 "Rate limit exhausted. Will reset in {} seconds",
@@ -94,8 +118,18 @@ impl fmt::Display for Error {
             ),
             Error::Codec(err) => write!(f, "{}", err),
             Error::Reqwest(err) => write!(f, "{}", err),
+            Error::Http(err) => write!(f, "{}", err),
             Error::Url(err) => write!(f, "{}", err),
             Error::IO(err) => write!(f, "{}", err),
+            Error::GraphQL(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|error| error.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ),
             #[cfg(feature = "jwt")]
             Error::JWT(err) => write!(f, "{}", err),
         }
@@ -120,13 +154,23 @@ pub struct ClientError {
     pub documentation_url: Option<String>,
 }
 
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// the path of the field which failed, made up of field names and list indices
+    #[serde(default)]
+    pub path: Vec<serde_json::Value>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::{ClientError, FieldErr};
 
     #[test]
     fn deserialize_client_field_errors() {
-        for (json, expect) in vec![
+        for (json, expect) in [
             // see https://github.com/softprops/hubcaps/issues/31
             (
                 r#"{"message": "Validation Failed","errors":
@@ -1,6 +1,6 @@
 //! Gists interface
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -31,6 +31,15 @@ impl UserGists {
         }
         self.github.get(&uri.join("?"))
     }
+
+    /// provides a stream over all pages of this user's gists
+    pub fn iter(&self, options: &GistListOptions) -> Paginator<Gist> {
+        let mut uri = vec![format!("/users/{}/gists", self.owner)];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
 }
 
 pub struct Gists {
@@ -65,6 +74,12 @@ impl Gists {
         self.github.get(&self.path(&format!("/{}/forks", id)))
     }
 
+    /// provides a stream over all pages of a gist's forks
+    pub fn iter_forks(&self, id: &str) -> Paginator<GistFork> {
+        self.github
+            .get_stream(&self.path(&format!("/{}/forks", id)))
+    }
+
     pub fn delete(&self, id: &str) -> Future<()> {
         self.github.delete(&self.path(&format!("/{}", id)))
     }
@@ -85,14 +100,33 @@ impl Gists {
         self.github.get::<Vec<Gist>>(&uri.join("?"))
     }
 
+    /// provides a stream over all pages of the authenticated user's gists
+    pub fn iter(&self, options: &GistListOptions) -> Paginator<Gist> {
+        let mut uri = vec![self.path("")];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
+
     pub fn public(&self) -> Future<Vec<Gist>> {
         self.github.get(&self.path("/public"))
     }
 
+    /// provides a stream over all pages of public gists
+    pub fn iter_public(&self) -> Paginator<Gist> {
+        self.github.get_stream(&self.path("/public"))
+    }
+
     pub fn starred(&self) -> Future<Vec<Gist>> {
         self.github.get(&self.path("/starred"))
     }
 
+    /// provides a stream over all pages of the authenticated user's starred gists
+    pub fn iter_starred(&self) -> Paginator<Gist> {
+        self.github.get_stream(&self.path("/starred"))
+    }
+
     pub fn create(&self, gist: &GistOptions) -> Future<Gist> {
         self.github.post(&self.path(""), json!(gist))
     }
@@ -1,10 +1,16 @@
 //! Git interface
+mod commit_builder;
+
+pub use self::commit_builder::*;
 
 // Third party
-use serde::Deserialize;
+use data_encoding::BASE64;
+use futures::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // Ours
-use crate::{Future, Github};
+use crate::repo_commits::{CommitRef, UserStamp};
+use crate::{Future, Github, MediaType, Stream};
 
 /// reference to git operations associated with a github repo
 pub struct Git {
@@ -38,10 +44,11 @@ impl Git {
     where
         S: Into<String>,
     {
+        // github lists a tree recursively given any value for `recursive`
         self.github.get(&self.path(&format!(
-            "/trees/{}?recursive={}",
+            "/trees/{}{}",
             sha.into(),
-            if recursive { "1" } else { "0" }
+            if recursive { "?recursive=1" } else { "" }
         )))
     }
 
@@ -55,6 +62,22 @@ impl Git {
             .get(&self.path(&format!("/blobs/{}", sha.into())))
     }
 
+    /// stream the raw bytes of the blob of a given sha, without buffering
+    /// them. blobs of up to 100 megabytes can be downloaded this way
+    pub fn download_blob<S>(&self, sha: S) -> Stream<Vec<u8>>
+    where
+        S: Into<String>,
+    {
+        Box::pin(
+            self.github
+                .get_bytes(
+                    &self.path(&format!("/blobs/{}", sha.into())),
+                    MediaType::Raw,
+                )
+                .try_flatten_stream(),
+        )
+    }
+
     /// get the git reference data of a given ref
     /// the specified reference must be formatted as as "heads/branch", not just "branch"
     /// https://developer.github.com/v3/git/refs/#get-a-reference
@@ -66,6 +89,98 @@ impl Git {
             .get(&self.path(&format!("/refs/{}", reference.into())))
     }
 
+    /// get a git commit
+    /// https://docs.github.com/en/rest/git/commits#get-a-commit-object
+    pub fn commit<S>(&self, sha: S) -> Future<GitCommit>
+    where
+        S: Into<String>,
+    {
+        self.github
+            .get(&self.path(&format!("/commits/{}", sha.into())))
+    }
+
+    /// create a blob
+    /// https://docs.github.com/en/rest/git/blobs#create-a-blob
+    pub fn create_blob(&self, blob: &BlobOptions) -> Future<BlobRef> {
+        self.github.post(&self.path("/blobs"), json!(blob))
+    }
+
+    /// create a tree, optionally on top of an existing one
+    /// https://docs.github.com/en/rest/git/trees#create-a-tree
+    pub fn create_tree(&self, tree: &TreeOptions) -> Future<TreeData> {
+        self.github.post(&self.path("/trees"), json!(tree))
+    }
+
+    /// create a commit. the commit isn't reachable from any branch until a
+    /// reference is created or updated to point at it
+    /// https://docs.github.com/en/rest/git/commits#create-a-commit
+    pub fn create_commit(&self, commit: &CommitOptions) -> Future<GitCommit> {
+        self.github.post(&self.path("/commits"), json!(commit))
+    }
+
+    /// create an annotated tag object. a `tags/{tag}` reference to the
+    /// returned tag's sha must be created for the tag to be listed
+    /// https://docs.github.com/en/rest/git/tags#create-a-tag-object
+    pub fn create_tag(&self, tag: &TagOptions) -> Future<Tag> {
+        self.github.post(&self.path("/tags"), json!(tag))
+    }
+
+    /// create a reference pointing at a sha
+    /// branches should be in the format `heads/feature-a`
+    /// tags should be in the format `tags/v1.0`
+    /// https://docs.github.com/en/rest/git/refs#create-a-reference
+    pub fn create_reference<R, S>(&self, reference: R, sha: S) -> Future<Reference>
+    where
+        R: Into<String>,
+        S: Into<String>,
+    {
+        let reference = reference.into();
+        let reference = if reference.starts_with("refs/") {
+            reference
+        } else {
+            format!("refs/{}", reference)
+        };
+        let options = ReferenceOptions {
+            reference: Some(reference),
+            sha: sha.into(),
+            force: None,
+        };
+        self.github.post(&self.path("/refs"), json!(options))
+    }
+
+    /// point a reference at a new sha. unless `force` is true, the update
+    /// fails unless it's a fast forward
+    /// branches should be in the format `heads/feature-a`
+    /// https://docs.github.com/en/rest/git/refs#update-a-reference
+    pub fn update_reference<R, S>(&self, reference: R, sha: S, force: bool) -> Future<Reference>
+    where
+        R: Into<String>,
+        S: Into<String>,
+    {
+        let options = ReferenceOptions {
+            reference: None,
+            sha: sha.into(),
+            force: Some(force),
+        };
+        self.github.patch(
+            &self.path(&format!("/refs/{}", reference.into())),
+            json!(options),
+        )
+    }
+
+    /// stage changes to the files of a branch, to commit them all at once
+    pub fn commit_builder<B>(&self, branch: B) -> CommitBuilder
+    where
+        B: Into<String>,
+    {
+        CommitBuilder::new(
+            self.github.clone(),
+            self.owner.as_str(),
+            self.repo.as_str(),
+            branch,
+        )
+    }
+
     //// deletes a refish
     /// branches should be in the format `heads/feature-a`
     /// tags should be in the format `tags/v1.0`
@@ -142,11 +257,411 @@ pub struct Object {
     pub url: String,
 }
 
+/// The mode of a tree entry
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum FileMode {
+    #[serde(rename = "100644")]
+    File,
+    #[serde(rename = "100755")]
+    Executable,
+    #[serde(rename = "040000")]
+    Directory,
+    #[serde(rename = "160000")]
+    Submodule,
+    #[serde(rename = "120000")]
+    Symlink,
+}
+
+/// The type of object a tree entry refers to
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryType {
+    Blob,
+    Tree,
+    Commit,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlobOptions {
+    content: String,
+    encoding: &'static str,
+}
+
+impl BlobOptions {
+    /// a blob of text
+    pub fn utf8<C>(content: C) -> Self
+    where
+        C: Into<String>,
+    {
+        BlobOptions {
+            content: content.into(),
+            encoding: "utf-8",
+        }
+    }
+
+    /// a blob of arbitrary bytes, sent base64 encoded
+    pub fn base64(content: &[u8]) -> Self {
+        BlobOptions {
+            content: BASE64.encode(content),
+            encoding: "base64",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlobRef {
+    pub url: String,
+    pub sha: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TreeOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_tree: Option<String>,
+    tree: Vec<TreeEntry>,
+}
+
+impl TreeOptions {
+    pub fn builder() -> TreeOptionsBuilder {
+        TreeOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct TreeOptionsBuilder(TreeOptions);
+
+impl TreeOptionsBuilder {
+    /// the sha of a tree to apply the entries to. without one, the new
+    /// tree only contains the given entries
+    pub fn base_tree<S>(&mut self, sha: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.base_tree = Some(sha.into());
+        self
+    }
+
+    pub fn entry(&mut self, entry: TreeEntry) -> &mut Self {
+        self.0.tree.push(entry);
+        self
+    }
+
+    pub fn build(&self) -> TreeOptions {
+        TreeOptions {
+            base_tree: self.0.base_tree.clone(),
+            tree: self.0.tree.clone(),
+        }
+    }
+}
+
+/// An entry of a tree to create
+#[derive(Clone, Debug, Serialize)]
+pub struct TreeEntry {
+    path: String,
+    mode: FileMode,
+    #[serde(rename = "type")]
+    entry_type: EntryType,
+    /// `Some(None)` serializes as null, deleting the path from the base tree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+impl TreeEntry {
+    /// an entry referring to an existing object
+    pub fn object<P, S>(path: P, mode: FileMode, entry_type: EntryType, sha: S) -> Self
+    where
+        P: Into<String>,
+        S: Into<String>,
+    {
+        TreeEntry {
+            path: path.into(),
+            mode,
+            entry_type,
+            sha: Some(Some(sha.into())),
+            content: None,
+        }
+    }
+
+    /// a file referring to an existing blob
+    pub fn blob<P, S>(path: P, sha: S) -> Self
+    where
+        P: Into<String>,
+        S: Into<String>,
+    {
+        TreeEntry::object(path, FileMode::File, EntryType::Blob, sha)
+    }
+
+    /// a file with the given text, stored as a new blob
+    pub fn file<P, C>(path: P, content: C) -> Self
+    where
+        P: Into<String>,
+        C: Into<String>,
+    {
+        TreeEntry {
+            path: path.into(),
+            mode: FileMode::File,
+            entry_type: EntryType::Blob,
+            sha: None,
+            content: Some(content.into()),
+        }
+    }
+
+    /// removes a file from the base tree
+    pub fn delete<P>(path: P) -> Self
+    where
+        P: Into<String>,
+    {
+        TreeEntry {
+            path: path.into(),
+            mode: FileMode::File,
+            entry_type: EntryType::Blob,
+            sha: Some(None),
+            content: None,
+        }
+    }
+
+    /// set the entry's mode, such as `FileMode::Executable`
+    pub fn mode(mut self, mode: FileMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// The name, email and time identifying the author, committer or tagger of
+/// a git object
+#[derive(Clone, Debug, Serialize)]
+pub struct CommitAuthor {
+    pub name: String,
+    pub email: String,
+    /// an ISO 8601 timestamp. defaults to the current time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+impl CommitAuthor {
+    pub fn new<N, E>(name: N, email: E) -> Self
+    where
+        N: Into<String>,
+        E: Into<String>,
+    {
+        CommitAuthor {
+            name: name.into(),
+            email: email.into(),
+            date: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitOptions {
+    message: String,
+    tree: String,
+    parents: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<CommitAuthor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committer: Option<CommitAuthor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+impl CommitOptions {
+    /// options for a commit of the tree with the given sha
+    pub fn builder<M, T>(message: M, tree: T) -> CommitOptionsBuilder
+    where
+        M: Into<String>,
+        T: Into<String>,
+    {
+        CommitOptionsBuilder(CommitOptions {
+            message: message.into(),
+            tree: tree.into(),
+            parents: Vec::new(),
+            author: None,
+            committer: None,
+            signature: None,
+        })
+    }
+}
+
+pub struct CommitOptionsBuilder(CommitOptions);
+
+impl CommitOptionsBuilder {
+    /// add a parent commit. commits without parents are root commits, those
+    /// with several are merge commits
+    pub fn parent<S>(&mut self, sha: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.parents.push(sha.into());
+        self
+    }
+
+    /// defaults to the authenticated user
+    pub fn author(&mut self, author: CommitAuthor) -> &mut Self {
+        self.0.author = Some(author);
+        self
+    }
+
+    /// defaults to the author
+    pub fn committer(&mut self, committer: CommitAuthor) -> &mut Self {
+        self.0.committer = Some(committer);
+        self
+    }
+
+    /// an ASCII-armored detached PGP signature over the commit, which
+    /// GitHub verifies
+    pub fn signature<S>(&mut self, signature: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.signature = Some(signature.into());
+        self
+    }
+
+    pub fn build(&self) -> CommitOptions {
+        CommitOptions {
+            message: self.0.message.clone(),
+            tree: self.0.tree.clone(),
+            parents: self.0.parents.clone(),
+            author: self.0.author.clone(),
+            committer: self.0.committer.clone(),
+            signature: self.0.signature.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitCommit {
+    pub sha: String,
+    pub url: String,
+    pub html_url: String,
+    pub author: UserStamp,
+    pub committer: UserStamp,
+    pub message: String,
+    pub tree: CommitRef,
+    pub parents: Vec<CommitRef>,
+    pub verification: Option<Verification>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Verification {
+    pub verified: bool,
+    /// why the signature was or wasn't verified, like "valid" or "unsigned"
+    pub reason: String,
+    pub signature: Option<String>,
+    pub payload: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagOptions {
+    tag: String,
+    message: String,
+    object: String,
+    #[serde(rename = "type")]
+    object_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tagger: Option<CommitAuthor>,
+}
+
+impl TagOptions {
+    /// options for an annotated tag of the commit with the given sha
+    pub fn new<T, M, S>(tag: T, message: M, commit: S, tagger: Option<CommitAuthor>) -> Self
+    where
+        T: Into<String>,
+        M: Into<String>,
+        S: Into<String>,
+    {
+        TagOptions {
+            tag: tag.into(),
+            message: message.into(),
+            object: commit.into(),
+            object_type: "commit",
+            tagger,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Tag {
+    pub tag: String,
+    pub sha: String,
+    pub url: String,
+    pub message: String,
+    pub tagger: UserStamp,
+    pub object: Object,
+    pub verification: Option<Verification>,
+}
+
+#[derive(Serialize)]
+struct ReferenceOptions {
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    reference: Option<String>,
+    sha: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    force: Option<bool>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transport::Transport;
+    use http::{Request, Response};
     use serde::Deserialize;
     use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Debug, Default)]
+    struct Requested(Arc<Mutex<Vec<String>>>);
+
+    impl Transport for Requested {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Vec<u8>>> {
+            self.0.lock().unwrap().push(request.uri().to_string());
+            Box::pin(future::ok(Response::new(
+                br#"{"sha":"s","url":"u","tree":[],"truncated":false}"#.to_vec(),
+            )))
+        }
+    }
+
+    #[cfg(not(feature = "httpcache"))]
+    fn git(transport: Requested) -> Git {
+        Git::new(
+            Github::custom("https://api.github.com", "test", None, transport),
+            "o",
+            "r",
+        )
+    }
+
+    #[cfg(feature = "httpcache")]
+    fn git(transport: Requested) -> Git {
+        Git::new(
+            Github::custom(
+                "https://api.github.com",
+                "test",
+                None,
+                transport,
+                <dyn crate::HttpCache>::noop(),
+            ),
+            "o",
+            "r",
+        )
+    }
+
+    #[tokio::test]
+    async fn lists_trees() {
+        let transport = Requested::default();
+        let git = git(transport.clone());
+        git.tree("main", false).await.unwrap();
+        git.tree("main", true).await.unwrap();
+        assert_eq!(
+            *transport.0.lock().unwrap(),
+            vec![
+                "https://api.github.com/repos/o/r/git/trees/main",
+                "https://api.github.com/repos/o/r/git/trees/main?recursive=1",
+            ]
+        );
+    }
 
     fn test_deserializing<'de, T>(payload: &'static str, expected: T)
     where
@@ -156,6 +671,74 @@ mod tests {
         assert_eq!(incoming, expected)
     }
 
+    #[test]
+    fn serialize_blobs() {
+        assert_eq!(
+            serde_json::to_string(&BlobOptions::base64(b"hubcaps")).unwrap(),
+            r#"{"content":"aHViY2Fwcw==","encoding":"base64"}"#
+        );
+    }
+
+    #[test]
+    fn serialize_trees() {
+        let tree = TreeOptions::builder()
+            .base_tree("9fb037999f264ba9a7fc6274d15fa3ae2ab98312")
+            .entry(TreeEntry::file("bin/run", "#!/bin/sh\n").mode(FileMode::Executable))
+            .entry(TreeEntry::blob(
+                "README.md",
+                "44b4fc6d56897b048c772eb4087f854f46256132",
+            ))
+            .entry(TreeEntry::delete("CHANGES.md"))
+            .build();
+        assert_eq!(
+            serde_json::to_value(&tree).unwrap(),
+            serde_json::json!({
+                "base_tree": "9fb037999f264ba9a7fc6274d15fa3ae2ab98312",
+                "tree": [
+                    { "path": "bin/run", "mode": "100755", "type": "blob", "content": "#!/bin/sh\n" },
+                    { "path": "README.md", "mode": "100644", "type": "blob", "sha": "44b4fc6d56897b048c772eb4087f854f46256132" },
+                    { "path": "CHANGES.md", "mode": "100644", "type": "blob", "sha": null },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_commits() {
+        let commit = CommitOptions::builder(
+            "my commit message",
+            "827efc6d56897b048c772eb4087f854f46256132",
+        )
+        .parent("7d1b31e74ee336d15cbd21741bc88a537ed063a0")
+        .author(CommitAuthor {
+            date: Some("2008-07-09T16:13:30+12:00".into()),
+            ..CommitAuthor::new("Mona Octocat", "octocat@github.com")
+        })
+        .build();
+        assert_eq!(
+            serde_json::to_value(&commit).unwrap(),
+            serde_json::json!({
+                "message": "my commit message",
+                "tree": "827efc6d56897b048c772eb4087f854f46256132",
+                "parents": ["7d1b31e74ee336d15cbd21741bc88a537ed063a0"],
+                "author": {
+                    "name": "Mona Octocat",
+                    "email": "octocat@github.com",
+                    "date": "2008-07-09T16:13:30+12:00"
+                }
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&ReferenceOptions {
+                reference: None,
+                sha: "7d1b31e74ee336d15cbd21741bc88a537ed063a0".into(),
+                force: Some(true),
+            })
+            .unwrap(),
+            serde_json::json!({ "sha": "7d1b31e74ee336d15cbd21741bc88a537ed063a0", "force": true })
+        );
+    }
+
     #[test]
     fn deserialize_get_ref_exact() {
         let payload = r#"{
@@ -0,0 +1,465 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use http::StatusCode;
+
+use super::{
+    BlobOptions, CommitAuthor, CommitOptions, EntryType, FileMode, GetReferenceResponse, Git,
+    GitCommit, GitFile, TreeEntry, TreeOptions,
+};
+use crate::errors::{ClientError, Error};
+use crate::{Future, Github, Result};
+
+/// Stages changes to the files of a branch in memory, then commits them all
+/// at once with the git data api
+///
+/// ```no_run
+/// # async fn run(github: hubcaps::Github) -> hubcaps::Result<()> {
+/// let commit = github
+///     .repo("owner", "repo")
+///     .git()
+///     .commit_builder("main")
+///     .write("src/version.txt", "1.2.0\n")
+///     .rename("CHANGES.md", "CHANGELOG.md")
+///     .set_executable("scripts/release.sh", true)
+///     .commit("Release 1.2.0")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CommitBuilder {
+    github: Github,
+    owner: String,
+    repo: String,
+    branch: String,
+    changes: Vec<Change>,
+    author: Option<CommitAuthor>,
+    committer: Option<CommitAuthor>,
+    attempts: u32,
+}
+
+#[derive(Clone, Debug)]
+enum Change {
+    Write(String, Vec<u8>),
+    Delete(String),
+    Rename(String, String),
+    SetExecutable(String, bool),
+}
+
+/// what a path of the new tree refers to
+#[derive(Clone, Debug, PartialEq)]
+enum Source {
+    /// an object of the base tree
+    Object(String),
+    /// the blob of the write staged at this index
+    Write(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Staged {
+    mode: FileMode,
+    source: Source,
+}
+
+impl CommitBuilder {
+    #[doc(hidden)]
+    pub fn new<O, R, B>(github: Github, owner: O, repo: R, branch: B) -> Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+        B: Into<String>,
+    {
+        CommitBuilder {
+            github,
+            owner: owner.into(),
+            repo: repo.into(),
+            branch: branch.into(),
+            changes: Vec::new(),
+            author: None,
+            committer: None,
+            attempts: 3,
+        }
+    }
+
+    /// add or replace a file. replaced files keep their mode
+    pub fn write<P, C>(&mut self, path: P, content: C) -> &mut Self
+    where
+        P: Into<String>,
+        C: Into<Vec<u8>>,
+    {
+        self.changes
+            .push(Change::Write(normalize(path), content.into()));
+        self
+    }
+
+    /// remove a file. removing a file which doesn't exist has no effect
+    pub fn delete<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.changes.push(Change::Delete(normalize(path)));
+        self
+    }
+
+    /// move a file, keeping its content and mode
+    pub fn rename<F, T>(&mut self, from: F, to: T) -> &mut Self
+    where
+        F: Into<String>,
+        T: Into<String>,
+    {
+        self.changes
+            .push(Change::Rename(normalize(from), normalize(to)));
+        self
+    }
+
+    /// set or clear a file's executable bit
+    pub fn set_executable<P>(&mut self, path: P, executable: bool) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.changes
+            .push(Change::SetExecutable(normalize(path), executable));
+        self
+    }
+
+    /// defaults to the authenticated user
+    pub fn author(&mut self, author: CommitAuthor) -> &mut Self {
+        self.author = Some(author);
+        self
+    }
+
+    /// defaults to the author
+    pub fn committer(&mut self, committer: CommitAuthor) -> &mut Self {
+        self.committer = Some(committer);
+        self
+    }
+
+    /// how many times to try committing when the branch moves while the
+    /// commit is being made. defaults to 3
+    pub fn attempts(&mut self, attempts: u32) -> &mut Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Create blobs for the written files, a tree and a commit on top of the
+    /// branch's head, then fast forward the branch to the commit. When the
+    /// branch moved in the meantime, the changes are reapplied to its new
+    /// head
+    pub fn commit<M>(&self, message: M) -> Future<GitCommit>
+    where
+        M: Into<String>,
+    {
+        let git = Git::new(self.github.clone(), self.owner.as_str(), self.repo.as_str());
+        let branch = self.branch.clone();
+        let changes = self.changes.clone();
+        let message = message.into();
+        let author = self.author.clone();
+        let committer = self.committer.clone();
+        let attempts = self.attempts;
+        Box::pin(async move {
+            let mut blobs = Vec::new();
+            for change in &changes {
+                if let Change::Write(_, content) = change {
+                    blobs.push(git.create_blob(&BlobOptions::base64(content)).await?.sha);
+                }
+            }
+            let reference = format!("heads/{}", branch);
+            let mut attempt = 1;
+            loop {
+                let head = match git.reference(reference.as_str()).await? {
+                    GetReferenceResponse::Exact(head) => head.object.sha,
+                    GetReferenceResponse::StartWith(_) => {
+                        return Err(not_found(format!("branch {} not found", branch)))
+                    }
+                };
+                let base_tree = git.commit(head.as_str()).await?.tree.sha;
+                let base = git.tree(base_tree.as_str(), true).await?;
+                let base = if base.truncated {
+                    // too many files to list at once, so list only the
+                    // directories of the paths changed
+                    directory_files(&git, &base_tree, &changes).await?
+                } else {
+                    base.tree
+                };
+                let entries = stage(&changes, &base)?;
+
+                let mut tree = TreeOptions::builder();
+                tree.base_tree(base_tree);
+                for (path, staged) in entries {
+                    tree.entry(match staged {
+                        Some(Staged { mode, source }) => {
+                            let sha = match source {
+                                Source::Object(sha) => sha,
+                                Source::Write(index) => blobs[index].clone(),
+                            };
+                            TreeEntry::object(path, mode, entry_type(mode), sha)
+                        }
+                        None => TreeEntry::delete(path),
+                    });
+                }
+                let tree = git.create_tree(&tree.build()).await?;
+
+                let mut options = CommitOptions::builder(message.as_str(), tree.sha);
+                options.parent(head);
+                if let Some(author) = &author {
+                    options.author(author.clone());
+                }
+                if let Some(committer) = &committer {
+                    options.committer(committer.clone());
+                }
+                let commit = git.create_commit(&options.build()).await?;
+
+                match git
+                    .update_reference(reference.as_str(), commit.sha.as_str(), false)
+                    .await
+                {
+                    Ok(_) => return Ok(commit),
+                    // the branch moved, so the update is no longer a fast forward
+                    Err(Error::Fault { code, .. })
+                        if code == StatusCode::UNPROCESSABLE_ENTITY && attempt < attempts =>
+                    {
+                        attempt += 1
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        })
+    }
+}
+
+/// List the files of the directories changes affect
+async fn directory_files(git: &Git, tree: &str, changes: &[Change]) -> Result<Vec<GitFile>> {
+    let mut files = Vec::new();
+    for directory in directories(changes) {
+        let listing = if directory.is_empty() {
+            git.tree(tree, false).await
+        } else {
+            git.tree(format!("{}:{}", tree, directory), false).await
+        };
+        let listing = match listing {
+            Ok(listing) => listing,
+            // a directory the changes create
+            Err(Error::Fault { code, .. }) if code == StatusCode::NOT_FOUND => continue,
+            Err(err) => return Err(err),
+        };
+        if listing.truncated {
+            return Err(Error::Fault {
+                code: StatusCode::UNPROCESSABLE_ENTITY,
+                error: ClientError {
+                    message: format!("directory {} has too many files to list", directory),
+                    errors: None,
+                    documentation_url: None,
+                },
+                request_id: None,
+            });
+        }
+        files.extend(listing.tree.into_iter().map(|mut file| {
+            if !directory.is_empty() {
+                file.path = format!("{}/{}", directory, file.path);
+            }
+            file
+        }));
+    }
+    Ok(files)
+}
+
+/// the directories of the paths changes affect. the root is ""
+fn directories(changes: &[Change]) -> BTreeSet<String> {
+    let directory = |path: &str| match path.rfind('/') {
+        Some(index) => path[..index].to_string(),
+        None => String::new(),
+    };
+    changes
+        .iter()
+        .flat_map(|change| match change {
+            Change::Write(path, _) | Change::Delete(path) | Change::SetExecutable(path, _) => {
+                vec![directory(path)]
+            }
+            Change::Rename(from, to) => vec![directory(from), directory(to)],
+        })
+        .collect()
+}
+
+/// git tree paths don't start with a slash, content api paths do
+fn normalize<P>(path: P) -> String
+where
+    P: Into<String>,
+{
+    path.into().trim_start_matches('/').to_string()
+}
+
+fn not_found(message: String) -> Error {
+    Error::Fault {
+        code: StatusCode::NOT_FOUND,
+        error: ClientError {
+            message,
+            errors: None,
+            documentation_url: None,
+        },
+        request_id: None,
+    }
+}
+
+fn entry_type(mode: FileMode) -> EntryType {
+    match mode {
+        FileMode::Directory => EntryType::Tree,
+        FileMode::Submodule => EntryType::Commit,
+        _ => EntryType::Blob,
+    }
+}
+
+fn file_mode(mode: &str) -> Option<FileMode> {
+    match mode {
+        "100644" => Some(FileMode::File),
+        "100755" => Some(FileMode::Executable),
+        "040000" => Some(FileMode::Directory),
+        "160000" => Some(FileMode::Submodule),
+        "120000" => Some(FileMode::Symlink),
+        _ => None,
+    }
+}
+
+/// Apply changes to a base tree, returning the entries of paths which
+/// differ from it. `None` entries delete their path
+fn stage(changes: &[Change], base: &[GitFile]) -> Result<BTreeMap<String, Option<Staged>>> {
+    let base = base
+        .iter()
+        .filter_map(|file| {
+            file_mode(&file.mode).map(|mode| {
+                (
+                    file.path.as_str(),
+                    Staged {
+                        mode,
+                        source: Source::Object(file.sha.clone()),
+                    },
+                )
+            })
+        })
+        .collect::<HashMap<_, _>>();
+    let mut staged: BTreeMap<String, Option<Staged>> = BTreeMap::new();
+    let current = |staged: &BTreeMap<String, Option<Staged>>, path: &str| match staged.get(path) {
+        Some(entry) => entry.clone(),
+        None => base.get(path).cloned(),
+    };
+    let mut writes = 0;
+    for change in changes {
+        match change {
+            Change::Write(path, _) => {
+                let mode = current(&staged, path).map_or(FileMode::File, |entry| entry.mode);
+                staged.insert(
+                    path.clone(),
+                    Some(Staged {
+                        mode,
+                        source: Source::Write(writes),
+                    }),
+                );
+                writes += 1;
+            }
+            Change::Delete(path) => {
+                staged.insert(path.clone(), None);
+            }
+            Change::Rename(from, to) => {
+                let entry = current(&staged, from)
+                    .ok_or_else(|| not_found(format!("{} not found", from)))?;
+                staged.insert(from.clone(), None);
+                staged.insert(to.clone(), Some(entry));
+            }
+            Change::SetExecutable(path, executable) => {
+                let mut entry = current(&staged, path)
+                    .ok_or_else(|| not_found(format!("{} not found", path)))?;
+                entry.mode = if *executable {
+                    FileMode::Executable
+                } else {
+                    FileMode::File
+                };
+                staged.insert(path.clone(), Some(entry));
+            }
+        }
+    }
+    // paths the base tree doesn't have can't be deleted, so drop their
+    // entries rather than failing the commit
+    staged.retain(|path, entry| entry.is_some() || base.contains_key(path.as_str()));
+    Ok(staged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, mode: &str, sha: &str) -> GitFile {
+        GitFile {
+            path: path.into(),
+            mode: mode.into(),
+            content_type: "blob".into(),
+            size: Some(1),
+            sha: sha.into(),
+            url: None,
+        }
+    }
+
+    fn staged(mode: FileMode, source: Source) -> Option<Staged> {
+        Some(Staged { mode, source })
+    }
+
+    #[test]
+    fn stages_changes() {
+        let base = vec![
+            file("README.md", "100644", "a"),
+            file("build.sh", "100755", "b"),
+            file("CHANGES.md", "100644", "c"),
+        ];
+        let changes = vec![
+            Change::Write("build.sh".into(), b"#!/bin/sh".to_vec()),
+            Change::Write("src/lib.rs".into(), b"".to_vec()),
+            Change::Rename("CHANGES.md".into(), "CHANGELOG.md".into()),
+            Change::SetExecutable("README.md".into(), true),
+            Change::Delete("missing.txt".into()),
+            Change::Write("tmp.txt".into(), b"".to_vec()),
+            Change::Delete("tmp.txt".into()),
+        ];
+        let entries = stage(&changes, &base).unwrap();
+        assert_eq!(
+            entries.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    "CHANGELOG.md".into(),
+                    staged(FileMode::File, Source::Object("c".into()))
+                ),
+                ("CHANGES.md".into(), None),
+                (
+                    "README.md".into(),
+                    staged(FileMode::Executable, Source::Object("a".into()))
+                ),
+                (
+                    "build.sh".into(),
+                    staged(FileMode::Executable, Source::Write(0))
+                ),
+                (
+                    "src/lib.rs".into(),
+                    staged(FileMode::File, Source::Write(1))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_changed_directories() {
+        let changes = vec![
+            Change::Write("README.md".into(), Vec::new()),
+            Change::Rename("src/a.rs".into(), "src/bin/a.rs".into()),
+            Change::Delete("src/b.rs".into()),
+        ];
+        assert_eq!(
+            directories(&changes).into_iter().collect::<Vec<_>>(),
+            vec!["", "src", "src/bin"]
+        );
+    }
+
+    #[test]
+    fn stages_missing_paths() {
+        let changes = vec![Change::Rename("nope".into(), "yep".into())];
+        match stage(&changes, &[]) {
+            Err(Error::Fault { code, .. }) => assert_eq!(code, StatusCode::NOT_FOUND),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
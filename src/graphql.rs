@@ -0,0 +1,336 @@
+//! GraphQL interface
+//!
+//! Queries are posted to GitHub's [GraphQL API](https://docs.github.com/en/graphql)
+//! with the credentials and transport of the `Github` client they're issued
+//! through. For GitHub Enterprise hosts ending in `/api/v3` queries are posted
+//! to the corresponding `/api/graphql` endpoint.
+//!
+//! ```no_run
+//! use futures::prelude::*;
+//! use hubcaps::graphql::Query;
+//! use hubcaps::{Credentials, Github};
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Issue {
+//!     number: u64,
+//!     title: String,
+//! }
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", Credentials::Token("personal-access-token".into()))?;
+//! let query = Query::with_variables(
+//!     r#"query($owner: String!, $name: String!, $cursor: String) {
+//!         repository(owner: $owner, name: $name) {
+//!             issues(first: 100, after: $cursor) {
+//!                 nodes { number title }
+//!                 pageInfo { endCursor hasNextPage }
+//!             }
+//!         }
+//!     }"#,
+//!     serde_json::json!({ "owner": "softprops", "name": "hubcaps" }),
+//! );
+//! let issues = github
+//!     .graphql()
+//!     .iter::<Issue>(&query, &["repository", "issues"])
+//!     .try_collect::<Vec<_>>()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use futures::{prelude::*, stream};
+use http::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::GraphQLError;
+use crate::{AuthenticationConstraint, Error, Future, Github, MediaType, Stream};
+
+/// The variable `GraphQL::iter` sets to the cursor of the page to fetch
+const CURSOR: &str = "cursor";
+
+/// Issues GraphQL queries
+pub struct GraphQL {
+    github: Github,
+}
+
+impl GraphQL {
+    #[doc(hidden)]
+    pub fn new(github: Github) -> Self {
+        Self { github }
+    }
+
+    fn url(&self) -> String {
+        match self.github.host.strip_suffix("/api/v3") {
+            Some(enterprise) => format!("{}/api/graphql", enterprise),
+            None => format!("{}/graphql", self.github.host),
+        }
+    }
+
+    /// Run a query or mutation, deserializing its `data` as a `D`. Errors
+    /// reported in the response fail the operation with `Error::GraphQL`
+    pub fn query<D>(&self, query: &Query) -> Future<D>
+    where
+        D: DeserializeOwned + Send + 'static,
+    {
+        let body = match serde_json::to_vec(query) {
+            Ok(body) => body,
+            Err(err) => return Box::pin(future::err(err.into())),
+        };
+        Box::pin(
+            self.github
+                .request_entity::<Response<D>>(
+                    Method::POST,
+                    &self.url(),
+                    Some(body),
+                    MediaType::Json,
+                    AuthenticationConstraint::Unconstrained,
+                )
+                .and_then(|response| async move {
+                    match (response.data, response.errors) {
+                        (_, Some(errors)) if !errors.is_empty() => Err(Error::GraphQL(errors)),
+                        (Some(data), _) => Ok(data),
+                        (None, _) => Err(Error::GraphQL(Vec::new())),
+                    }
+                }),
+        )
+    }
+
+    /// Provides a stream over all nodes of a paginated connection
+    ///
+    /// `connection` is the path of field names leading from the query's
+    /// `data` to the connection, which must select `nodes` and
+    /// `pageInfo { endCursor hasNextPage }`. The query should accept a
+    /// `$cursor: String` variable which is passed to the connection's `after`
+    /// argument.
+    pub fn iter<N>(&self, query: &Query, connection: &[&str]) -> Stream<N>
+    where
+        N: DeserializeOwned + Send + 'static,
+    {
+        let graphql = GraphQL {
+            github: self.github.clone(),
+        };
+        let query = query.clone();
+        let connection: Vec<String> = connection.iter().map(|field| field.to_string()).collect();
+        Box::pin(
+            stream::try_unfold(Some(None), move |cursor: Option<Option<String>>| {
+                let page = cursor.map(|cursor| {
+                    graphql.query::<Value>(&query.with_variable(CURSOR, cursor.into()))
+                });
+                let connection = connection.clone();
+                async move {
+                    let data = match page {
+                        Some(page) => page.await?,
+                        None => return Ok::<_, Error>(None),
+                    };
+                    let connection = connection
+                        .iter()
+                        .fold(&data, |value, field| &value[field.as_str()]);
+                    let Connection { nodes, page_info } = Connection::deserialize(connection)?;
+                    let next = if page_info.has_next_page {
+                        page_info.end_cursor.map(Some)
+                    } else {
+                        None
+                    };
+                    Ok(Some((stream::iter(nodes.into_iter().map(Ok)), next)))
+                }
+            })
+            .try_flatten(),
+        )
+    }
+}
+
+// representations
+
+/// A GraphQL document along with the values of its variables
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct Query {
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Value>,
+}
+
+impl Query {
+    pub fn new<Q>(query: Q) -> Self
+    where
+        Q: Into<String>,
+    {
+        Query {
+            query: query.into(),
+            variables: None,
+        }
+    }
+
+    /// `variables` should be a json object keyed by variable name
+    pub fn with_variables<Q>(query: Q, variables: Value) -> Self
+    where
+        Q: Into<String>,
+    {
+        Query {
+            query: query.into(),
+            variables: Some(variables),
+        }
+    }
+
+    fn with_variable(&self, name: &str, value: Value) -> Self {
+        let mut variables = match self.variables {
+            Some(Value::Object(ref variables)) => variables.clone(),
+            _ => serde_json::Map::new(),
+        };
+        variables.insert(name.into(), value);
+        Query {
+            query: self.query.clone(),
+            variables: Some(Value::Object(variables)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Response<D> {
+    data: Option<D>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Connection<N> {
+    nodes: Vec<N>,
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    end_cursor: Option<String>,
+    has_next_page: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Transport;
+    use http::{Request, Response as HttpResponse, StatusCode};
+    use std::sync::{Arc, Mutex};
+
+    /// Answers each request with the next of a sequence of bodies, recording
+    /// the urls and bodies requested
+    #[derive(Clone, Debug)]
+    struct Scripted {
+        bodies: Arc<Mutex<Vec<&'static str>>>,
+        requests: Arc<Mutex<Vec<(String, Value)>>>,
+    }
+
+    impl Scripted {
+        fn new(bodies: Vec<&'static str>) -> Self {
+            Scripted {
+                bodies: Arc::new(Mutex::new(bodies)),
+                requests: Arc::default(),
+            }
+        }
+    }
+
+    impl Transport for Scripted {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<HttpResponse<Vec<u8>>> {
+            self.requests.lock().unwrap().push((
+                request.uri().to_string(),
+                serde_json::from_slice(request.body()).unwrap(),
+            ));
+            let body = self.bodies.lock().unwrap().remove(0);
+            let mut response = HttpResponse::new(body.as_bytes().to_vec());
+            *response.status_mut() = StatusCode::OK;
+            Box::pin(future::ok(response))
+        }
+    }
+
+    #[cfg(not(feature = "httpcache"))]
+    fn github(host: &str, transport: Scripted) -> Github {
+        Github::custom(host, "test", None, transport)
+    }
+
+    #[cfg(feature = "httpcache")]
+    fn github(host: &str, transport: Scripted) -> Github {
+        Github::custom(
+            host,
+            "test",
+            None,
+            transport,
+            <dyn crate::HttpCache>::noop(),
+        )
+    }
+
+    #[test]
+    fn enterprise_url() {
+        let transport = Scripted::new(vec![]);
+        assert_eq!(
+            github("https://api.github.com", transport.clone())
+                .graphql()
+                .url(),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            github("https://github.example.com/api/v3", transport)
+                .graphql()
+                .url(),
+            "https://github.example.com/api/graphql"
+        );
+    }
+
+    #[tokio::test]
+    async fn query_maps_errors() {
+        let transport = Scripted::new(vec![
+            r#"{"data":{"viewer":{"login":"octocat"}}}"#,
+            r#"{"data":null,"errors":[{"type":"NOT_FOUND","path":["repository"],"message":"Could not resolve to a Repository"}]}"#,
+        ]);
+        let graphql = github("https://api.github.com", transport.clone()).graphql();
+        let data = graphql
+            .query::<Value>(&Query::new("{ viewer { login } }"))
+            .await
+            .unwrap();
+        assert_eq!(data["viewer"]["login"], "octocat");
+        match graphql.query::<Value>(&Query::new("{ repository }")).await {
+            Err(Error::GraphQL(errors)) => {
+                assert_eq!(errors[0].kind.as_deref(), Some("NOT_FOUND"));
+                assert_eq!(errors[0].path, vec![Value::from("repository")]);
+            }
+            otherwise => panic!("expected graphql errors, got {:?}", otherwise),
+        }
+        assert_eq!(
+            transport.requests.lock().unwrap()[0],
+            (
+                "https://api.github.com/graphql".to_string(),
+                serde_json::json!({ "query": "{ viewer { login } }" })
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn iter_follows_cursors() {
+        let transport = Scripted::new(vec![
+            r#"{"data":{"repository":{"issues":{"nodes":[1,2],"pageInfo":{"endCursor":"Y3Vyc29yOjI=","hasNextPage":true}}}}}"#,
+            r#"{"data":{"repository":{"issues":{"nodes":[3],"pageInfo":{"endCursor":"Y3Vyc29yOjM=","hasNextPage":false}}}}}"#,
+        ]);
+        let query = Query::with_variables("query", serde_json::json!({ "owner": "o" }));
+        let numbers = github("https://api.github.com", transport.clone())
+            .graphql()
+            .iter::<u64>(&query, &["repository", "issues"])
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(numbers, vec![1, 2, 3]);
+        let variables = transport
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| body["variables"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variables,
+            vec![
+                serde_json::json!({ "owner": "o", "cursor": null }),
+                serde_json::json!({ "owner": "o", "cursor": "Y3Vyc29yOjI=" }),
+            ]
+        );
+    }
+}
@@ -1,31 +1,29 @@
 //! Hooks interface
 //!
 //! See the [github docs](https://developer.github.com/v3/repos/hooks/) for more information
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::marker::PhantomData;
 
 use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
 
-use crate::{Future, Github};
+use crate::scope::{OrgScope, RepoScope, Scope};
+use crate::{Future, Github, Paginator};
 
 /// Content-Type web hooks will receive
 /// deliveries in
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
 pub enum WebHookContentType {
     /// application/json
     #[serde(rename = "json")]
     Json,
     /// application/x-form-url-encoded
     #[serde(rename = "form")]
+    #[default]
     Form,
 }
 
-impl Default for WebHookContentType {
-    fn default() -> WebHookContentType {
-        WebHookContentType::Form
-    }
-}
-
 impl fmt::Display for WebHookContentType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -37,13 +35,20 @@ impl fmt::Display for WebHookContentType {
 }
 
 /// Interface for managing repository hooks
-pub struct Hooks {
+pub type RepoHooks = Hooks<RepoScope>;
+
+/// Interface for managing organization hooks
+pub type OrgHooks = Hooks<OrgScope>;
+
+/// Interface for managing the hooks of a repository or organization
+pub struct Hooks<S: Scope = RepoScope> {
     github: Github,
-    owner: String,
-    repo: String,
+    /// path of the hooks collection, like `/repos/{owner}/{repo}/hooks`
+    path: String,
+    scope: PhantomData<S>,
 }
 
-impl Hooks {
+impl Hooks<RepoScope> {
     #[doc(hidden)]
     pub fn new<O, R>(github: Github, owner: O, repo: R) -> Self
     where
@@ -52,41 +57,118 @@ impl Hooks {
     {
         Hooks {
             github,
-            owner: owner.into(),
-            repo: repo.into(),
+            path: format!("/repos/{}/{}/hooks", owner.into(), repo.into()),
+            scope: PhantomData,
         }
     }
 
-    /// lists hook associated with a repository
-    pub fn list(&self) -> Future<Vec<Hook>> {
+    /// triggers a repository hook with the latest push to the repository,
+    /// if the hook is subscribed to push events
+    pub fn test(&self, id: u64) -> Future<()> {
         self.github
-            .get(&format!("/repos/{}/{}/hooks", self.owner, self.repo))
+            .post(&format!("{}/{}/tests", self.path, id), Vec::new())
+    }
+}
+
+impl Hooks<OrgScope> {
+    #[doc(hidden)]
+    pub fn new<O>(github: Github, org: O) -> Self
+    where
+        O: Into<String>,
+    {
+        Hooks {
+            github,
+            path: format!("/orgs/{}/hooks", org.into()),
+            scope: PhantomData,
+        }
+    }
+}
+
+impl<S: Scope> Hooks<S> {
+    /// lists hooks
+    pub fn list(&self) -> Future<Vec<Hook>> {
+        self.github.get(&self.path)
+    }
+
+    /// provides a stream over all pages of hooks
+    pub fn iter(&self) -> Paginator<Hook> {
+        self.github.get_stream(&self.path)
     }
 
-    /// creates a new repository hook
+    /// creates a new hook
     /// Repository service hooks (like email or Campfire) can have at most one configured at a time.
     /// Creating hooks for a service that already has one configured will update the existing hook.
     /// see [github docs](https://developer.github.com/v3/repos/hooks/)
     /// for more information
     pub fn create(&self, options: &HookCreateOptions) -> Future<Hook> {
-        self.github.post(
-            &format!("/repos/{}/{}/hooks", self.owner, self.repo),
-            json!(options),
-        )
+        self.github.post(&self.path, json!(options))
     }
 
-    /// edits an existing repository hook
+    /// edits an existing hook
     pub fn edit(&self, id: u64, options: &HookEditOptions) -> Future<Hook> {
-        self.github.patch(
-            &format!("/repos/{}/{}/hooks/{}", self.owner, self.repo, id),
-            json!(options),
-        )
+        self.github
+            .patch(&format!("{}/{}", self.path, id), json!(options))
     }
 
-    /// deletes a repository hook by id
+    /// gets a hook by id
+    pub fn get(&self, id: u64) -> Future<Hook> {
+        self.github.get(&format!("{}/{}", self.path, id))
+    }
+
+    /// deletes a hook by id
     pub fn delete(&self, id: u64) -> Future<()> {
+        self.github.delete(&format!("{}/{}", self.path, id))
+    }
+
+    /// triggers a ping event to be sent to a hook
+    pub fn ping(&self, id: u64) -> Future<()> {
         self.github
-            .delete(&format!("/repos/{}/{}/hooks/{}", self.owner, self.repo, id))
+            .post(&format!("{}/{}/pings", self.path, id), Vec::new())
+    }
+
+    /// lists the deliveries of a hook, most recent first
+    pub fn list_deliveries(
+        &self,
+        id: u64,
+        options: &HookDeliveryListOptions,
+    ) -> Future<Vec<HookDelivery>> {
+        let mut uri = vec![self.deliveries_path(id)];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get(&uri.join("?"))
+    }
+
+    /// provides a stream over all pages of a hook's deliveries
+    pub fn iter_deliveries(
+        &self,
+        id: u64,
+        options: &HookDeliveryListOptions,
+    ) -> Paginator<HookDelivery> {
+        let mut uri = vec![self.deliveries_path(id)];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
+
+    /// gets a delivery of a hook, including the request sent and the
+    /// response received
+    pub fn get_delivery(&self, id: u64, delivery_id: u64) -> Future<HookDelivery> {
+        self.github
+            .get(&format!("{}/{}", self.deliveries_path(id), delivery_id))
+    }
+
+    /// requests that a delivery of a hook be sent again
+    pub fn redeliver(&self, id: u64, delivery_id: u64) -> Future<()> {
+        self.github.post_no_response(
+            &format!("{}/{}/attempts", self.deliveries_path(id), delivery_id),
+            Vec::new(),
+        )
+    }
+
+    fn deliveries_path(&self, id: u64) -> String {
+        format!("{}/{}/deliveries", self.path, id)
     }
 }
 
@@ -208,9 +290,13 @@ impl HookCreateOptionsBuilder {
 /// for githubs official documentation
 #[derive(Debug, Default, Serialize)]
 pub struct HookEditOptions {
+    /// replaces the whole config, including any secret, when set
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     config: BTreeMap<String, ::serde_json::Value>,
     events: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     add_events: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     remove_events: Vec<String>,
     active: bool,
 }
@@ -295,11 +381,61 @@ impl HookEditOptionsBuilder {
     }
 }
 
+#[derive(Default)]
+pub struct HookDeliveryListOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl HookDeliveryListOptions {
+    pub fn builder() -> HookDeliveryListOptionsBuilder {
+        HookDeliveryListOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            let encoded: String = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&self.params)
+                .finish();
+            Some(encoded)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HookDeliveryListOptionsBuilder(HookDeliveryListOptions);
+
+impl HookDeliveryListOptionsBuilder {
+    pub fn per_page(&mut self, n: usize) -> &mut Self {
+        self.0.params.insert("per_page", n.to_string());
+        self
+    }
+
+    /// start listing from a cursor taken from a previous listing's
+    /// `rel="next"` link
+    pub fn cursor<C>(&mut self, cursor: C) -> &mut Self
+    where
+        C: Into<String>,
+    {
+        self.0.params.insert("cursor", cursor.into());
+        self
+    }
+
+    pub fn build(&self) -> HookDeliveryListOptions {
+        HookDeliveryListOptions {
+            params: self.0.params.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Hook {
     pub id: u64,
     pub url: String,
-    pub test_url: String,
+    /// only repository hooks may be tested
+    pub test_url: Option<String>,
     pub ping_url: String,
     pub name: String,
     pub events: Vec<String>,
@@ -330,9 +466,54 @@ impl Hook {
     }
 }
 
+/// An attempt to deliver an event to a hook. Listings omit the `request`
+/// and `response`
+#[derive(Debug, Deserialize)]
+pub struct HookDelivery {
+    pub id: u64,
+    /// the `X-GitHub-Delivery` id shared by all attempts to deliver an event
+    pub guid: String,
+    pub delivered_at: String,
+    pub redelivery: bool,
+    /// seconds taken to deliver the event
+    pub duration: f64,
+    /// a description of the delivery's outcome, like "OK"
+    pub status: String,
+    /// the http status code of the hook's response, or 0 if it didn't
+    /// respond
+    pub status_code: u16,
+    pub event: String,
+    pub action: Option<String>,
+    pub installation_id: Option<u64>,
+    pub repository_id: Option<u64>,
+    pub url: Option<String>,
+    pub request: Option<HookDeliveryRequest>,
+    pub response: Option<HookDeliveryResponse>,
+}
+
+impl HookDelivery {
+    /// Return true if the hook's response indicated success
+    pub fn succeeded(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HookDeliveryRequest {
+    pub headers: Option<BTreeMap<String, String>>,
+    pub payload: Option<::serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HookDeliveryResponse {
+    pub headers: Option<BTreeMap<String, String>>,
+    pub payload: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::WebHookContentType;
+    use super::{HookDelivery, HookDeliveryListOptions, OrgHooks, RepoHooks, WebHookContentType};
+    use crate::Github;
 
     #[test]
     fn webhook_content_type_display() {
@@ -349,4 +530,67 @@ mod tests {
         let default: WebHookContentType = Default::default();
         assert_eq!(default, WebHookContentType::Form)
     }
+
+    #[test]
+    fn hook_delivery_list_options() {
+        assert_eq!(HookDeliveryListOptions::builder().build().serialize(), None);
+        assert_eq!(
+            HookDeliveryListOptions::builder()
+                .cursor("v1_12077215967")
+                .build()
+                .serialize(),
+            Some("cursor=v1_12077215967".into())
+        );
+    }
+
+    #[test]
+    fn hook_delivery_deserialize() {
+        let delivery: HookDelivery = serde_json::from_str(
+            r#"{
+              "id": 12345678,
+              "guid": "0b989ba4-242f-11e5-81e1-c7b6966d2516",
+              "delivered_at": "2019-06-03T00:57:16Z",
+              "redelivery": false,
+              "duration": 0.27,
+              "status": "Invalid HTTP Response: 502",
+              "status_code": 502,
+              "event": "issues",
+              "action": "opened",
+              "installation_id": null,
+              "repository_id": 123456,
+              "url": "https://www.example.com",
+              "request": {
+                "headers": { "X-GitHub-Event": "issues" },
+                "payload": { "action": "opened" }
+              },
+              "response": {
+                "headers": { "Content-Type": "text/html;charset=utf-8" },
+                "payload": "bad gateway"
+              }
+            }"#,
+        )
+        .unwrap();
+        assert!(!delivery.succeeded());
+        assert_eq!(
+            delivery.request.unwrap().payload.unwrap()["action"],
+            "opened"
+        );
+        assert_eq!(
+            delivery.response.unwrap().payload.as_deref(),
+            Some("bad gateway")
+        );
+    }
+
+    #[test]
+    fn hook_scope_paths() {
+        let github = Github::new("test", None).unwrap();
+        assert_eq!(
+            RepoHooks::new(github.clone(), "octocat", "hello-world").deliveries_path(1),
+            "/repos/octocat/hello-world/hooks/1/deliveries"
+        );
+        assert_eq!(
+            OrgHooks::new(github, "octo-org").deliveries_path(1),
+            "/orgs/octo-org/hooks/1/deliveries"
+        );
+    }
 }
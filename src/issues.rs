@@ -9,12 +9,13 @@ use crate::comments::Comments;
 use crate::labels::Label;
 use crate::users::User;
 use crate::utils::{percent_encode, PATH_SEGMENT};
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Future, Github, Paginator, SortDirection};
 
 /// enum representation of github pull and issue state
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum State {
     /// Only open issues
+    #[default]
     Open,
     /// Only closed issues
     Closed,
@@ -33,16 +34,11 @@ impl fmt::Display for State {
     }
 }
 
-impl Default for State {
-    fn default() -> State {
-        State::Open
-    }
-}
-
 /// Sort options available for github issues
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum Sort {
     /// sort by creation time of issue
+    #[default]
     Created,
     /// sort by the last time issue was updated
     Updated,
@@ -61,12 +57,6 @@ impl fmt::Display for Sort {
     }
 }
 
-impl Default for Sort {
-    fn default() -> Sort {
-        Sort::Created
-    }
-}
-
 /// Provides access to assignee operations available for an individual issue
 pub struct IssueAssignees {
     github: Github,
@@ -221,6 +211,18 @@ impl IssueRef {
         self.github.patch(&self.path(""), json!(is))
     }
 
+    /// short hand for editing state = open
+    pub fn open(&self) -> Future<Issue> {
+        self.github
+            .patch(&self.path(""), json_lit!({ "state": "open" }))
+    }
+
+    /// short hand for editing state = closed
+    pub fn close(&self) -> Future<Issue> {
+        self.github
+            .patch(&self.path(""), json_lit!({ "state": "closed" }))
+    }
+
     /// Return a reference to comment operations available for this issue
     pub fn comments(&self) -> Comments {
         Comments::new(
@@ -289,7 +291,7 @@ impl Issues {
     ///
     /// Note: You'll typically want to use a `IssueListOptions` with a `per_page`
     /// of 100 for maximum api credential rate limit efficency
-    pub fn iter(&self, options: &IssueListOptions) -> Stream<Issue> {
+    pub fn iter(&self, options: &IssueListOptions) -> Paginator<Issue> {
         let mut uri = vec![self.path("")];
         if let Some(query) = options.serialize() {
             uri.push(query);
@@ -4,7 +4,7 @@
 //! for motivation and use
 use serde::{Deserialize, Serialize};
 
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator};
 
 pub struct Keys {
     github: Github,
@@ -38,6 +38,11 @@ impl Keys {
         self.github.get(&self.path(""))
     }
 
+    /// provides a stream over all pages of this repo's deploy keys
+    pub fn iter(&self) -> Paginator<Key> {
+        self.github.get_stream(&self.path(""))
+    }
+
     pub fn get(&self, id: u64) -> Future<Key> {
         self.github.get(&self.path(&format!("/{}", id)))
     }
@@ -1,7 +1,7 @@
 //! Labels interface
 use serde::{Deserialize, Serialize};
 
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Paginator};
 
 pub struct Labels {
     github: Github,
@@ -45,7 +45,7 @@ impl Labels {
     }
 
     /// provides a stream over all pages of this repo's labels
-    pub fn iter(&self) -> Stream<Label> {
+    pub fn iter(&self) -> Paginator<Label> {
         self.github.get_stream(&self.path(""))
     }
 }
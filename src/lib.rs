@@ -35,7 +35,8 @@
 //! Many of GitHub's APIs return a collection of entities with a common interface for supporting pagination
 //! Hubcaps supports two types of interfaces for working with listings. `list(...)` interfaces return the first
 //! ( often enough ) list of entities. Alternatively for listings that require > 30 items you may wish to
-//! use the `iter(..)` variant which returns a `futures::Stream` over all entities in a paginated set.
+//! use the `iter(..)` variant which returns a [Paginator](pagination/struct.Paginator.html), a
+//! `futures::Stream` over all entities in a paginated set which also provides access to its pages.
 //!
 //! # Errors
 //!
@@ -58,6 +59,10 @@
 //! GitHub's rate limits, enable debug logging and look for "x-rate-limit"
 //! log patterns sourced from this crate
 //!
+//! Rather than surfacing these errors, a client may be configured to wait out
+//! rate limits and retry transient server errors with
+//! `Github::set_retry_policy`. See the [retry](retry/index.html) module for details.
+//!
 //! # Features
 //!
 //! ## httpcache
@@ -78,26 +83,38 @@
 //! Then use the `Github::custom` constructor to provide a cache implementation. See
 //! the conditional_requests example in this crates github repository for an example usage
 //!
+//! ## mock
+//!
+//! The `mock` feature flag enables the [hubcaps::mock](mock/index.html) module, an in-process
+//! server implementing a stateful subset of the GitHub api which clients created with
+//! `Github::host` may be pointed at in integration tests
+//!
+//! ## webhook-receiver
+//!
+//! The `webhook-receiver` feature flag enables the
+//! [hubcaps::webhooks::receiver](webhooks/receiver/index.html) module, an embedded server which
+//! verifies webhook deliveries and routes their events to async handlers
+//!
 #![allow(missing_docs)] // todo: make this a deny eventually
 
 use std::fmt;
 use std::pin::Pin;
-use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use futures::{future, prelude::*, stream, Future as StdFuture, Stream as StdStream};
+use futures::{future, prelude::*, Future as StdFuture, Stream as StdStream};
 #[cfg(feature = "httpcache")]
 use http::header::IF_NONE_MATCH;
 use http::header::{HeaderMap, HeaderValue};
 use http::header::{ACCEPT, AUTHORIZATION, ETAG, LINK, USER_AGENT};
-use http::{Method, StatusCode};
+use http::{Method, Request, Response, StatusCode};
+use hyperx::header::{qitem, Link};
 #[cfg(feature = "httpcache")]
-use hyperx::header::LinkValue;
-use hyperx::header::{qitem, Link, RelationType};
+use hyperx::header::{LinkValue, RelationType};
 use log::{debug, trace};
 use mime::Mime;
+use reqwest::Client;
 use reqwest::Url;
-use reqwest::{Body, Client};
 use serde::de::DeserializeOwned;
 
 #[doc(hidden)] // public for doc testing and integration testing only
@@ -112,11 +129,14 @@ pub mod branches;
 pub mod checks;
 pub mod collaborators;
 pub mod comments;
+pub mod compare;
 pub mod content;
 pub mod deployments;
+pub mod diff;
 pub mod errors;
 pub mod gists;
 pub mod git;
+pub mod graphql;
 pub mod hooks;
 pub mod issues;
 #[cfg(feature = "jwt")]
@@ -124,27 +144,42 @@ mod jwt;
 pub mod keys;
 pub mod labels;
 pub mod membership;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod notifications;
 pub mod organizations;
+pub mod pagination;
 pub mod pull_commits;
 pub mod pulls;
 pub mod rate_limit;
 pub mod releases;
 pub mod repo_commits;
 pub mod repositories;
+pub mod response;
+pub mod retry;
 pub mod review_comments;
 pub mod review_requests;
+pub mod reviews;
+pub mod rulesets;
+pub mod scope;
 pub mod search;
 pub mod stars;
 pub mod statuses;
+pub mod sync;
 pub mod teams;
+pub mod testing;
 pub mod traffic;
+pub mod transport;
 pub mod users;
 pub mod watching;
+pub mod webhooks;
 
 pub use crate::errors::{Error, Result};
 #[cfg(feature = "httpcache")]
 pub use crate::http_cache::{BoxedHttpCache, HttpCache};
+pub use crate::pagination::Paginator;
+pub use crate::retry::RetryPolicy;
+pub use crate::transport::{BoxedTransport, Transport};
 
 use crate::activity::Activity;
 #[cfg(feature = "app")]
@@ -152,11 +187,15 @@ use crate::app::App;
 #[cfg(feature = "app")]
 pub use crate::app::InstallationTokenGenerator;
 use crate::gists::{Gists, UserGists};
+use crate::graphql::GraphQL;
 #[cfg(feature = "jwt")]
 pub use crate::jwt::JWTCredentials;
 use crate::organizations::{Organization, Organizations, UserOrganizations};
-use crate::rate_limit::RateLimit;
+use crate::rate_limit::{Governor, RateLimit, RateLimitResourceStatus, Resource, Throttle};
 use crate::repositories::{OrganizationRepositories, Repositories, Repository, UserRepositories};
+#[cfg(feature = "httpcache")]
+use crate::response::PageLinks;
+use crate::response::Response as ResponseMetadata;
 use crate::search::Search;
 use crate::users::Users;
 
@@ -188,24 +227,28 @@ pub(crate) mod utils {
 
 /// GitHub defined Media types
 /// See [this doc](https://developer.github.com/v3/media/) for more for more information
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub enum MediaType {
     /// Return json (the default)
+    #[default]
     Json,
     /// Return json in preview form
     Preview(&'static str),
-}
-
-impl Default for MediaType {
-    fn default() -> MediaType {
-        MediaType::Json
-    }
+    /// Return the raw contents of a resource, like a file
+    Raw,
+    /// Return a unified diff, like `git diff`
+    Diff,
+    /// Return a patch series, like `git format-patch`
+    Patch,
 }
 
 impl From<MediaType> for Mime {
     fn from(media: MediaType) -> Mime {
         match media {
             MediaType::Json => "application/vnd.github.v3+json".parse().unwrap(),
+            MediaType::Raw => "application/vnd.github.v3.raw".parse().unwrap(),
+            MediaType::Diff => "application/vnd.github.v3.diff".parse().unwrap(),
+            MediaType::Patch => "application/vnd.github.v3.patch".parse().unwrap(),
             MediaType::Preview(codename) => {
                 format!("application/vnd.github.{}-preview+json", codename)
                     .parse()
@@ -228,9 +271,10 @@ pub enum AuthenticationConstraint {
 }
 
 /// enum representation of Github list sorting options
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum SortDirection {
     /// Sort in ascending order (the default)
+    #[default]
     Asc,
     /// Sort in descending order
     Desc,
@@ -246,12 +290,6 @@ impl fmt::Display for SortDirection {
     }
 }
 
-impl Default for SortDirection {
-    fn default() -> SortDirection {
-        SortDirection::Asc
-    }
-}
-
 /// Various forms of authentication credentials supported by GitHub
 #[derive(PartialEq, Clone)]
 pub enum Credentials {
@@ -305,12 +343,19 @@ impl fmt::Debug for Credentials {
 pub struct Github {
     host: String,
     agent: String,
-    client: Client,
+    transport: BoxedTransport,
     credentials: Option<Credentials>,
     #[cfg(feature = "httpcache")]
     http_cache: BoxedHttpCache,
+    retry_policy: RetryPolicy,
+    governor: Governor,
+    throttle: Throttle,
+    response_sink: Option<ResponseSink>,
 }
 
+/// Receives the metadata of responses on behalf of `Github::with_response`
+type ResponseSink = Arc<Mutex<Option<ResponseMetadata<()>>>>;
+
 impl Github {
     pub fn new<A, C>(agent: A, credentials: C) -> Result<Self>
     where
@@ -343,40 +388,54 @@ impl Github {
         }
     }
 
+    /// Create a client which issues its requests through the provided
+    /// `Transport`, such as a configured `reqwest::Client` or an offline backend
     #[cfg(feature = "httpcache")]
-    pub fn custom<H, A, CR>(
+    pub fn custom<H, A, CR, T>(
         host: H,
         agent: A,
         credentials: CR,
-        http: Client,
+        http: T,
         http_cache: BoxedHttpCache,
     ) -> Self
     where
         H: Into<String>,
         A: Into<String>,
         CR: Into<Option<Credentials>>,
+        T: Transport + Clone + Send + Sync + 'static,
     {
         Self {
             host: host.into(),
             agent: agent.into(),
-            client: http,
+            transport: Box::new(http),
             credentials: credentials.into(),
             http_cache,
+            retry_policy: RetryPolicy::none(),
+            governor: Governor::default(),
+            throttle: Throttle::default(),
+            response_sink: None,
         }
     }
 
+    /// Create a client which issues its requests through the provided
+    /// `Transport`, such as a configured `reqwest::Client` or an offline backend
     #[cfg(not(feature = "httpcache"))]
-    pub fn custom<H, A, CR>(host: H, agent: A, credentials: CR, http: Client) -> Self
+    pub fn custom<H, A, CR, T>(host: H, agent: A, credentials: CR, http: T) -> Self
     where
         H: Into<String>,
         A: Into<String>,
         CR: Into<Option<Credentials>>,
+        T: Transport + Clone + Send + Sync + 'static,
     {
         Self {
             host: host.into(),
             agent: agent.into(),
-            client: http,
+            transport: Box::new(http),
             credentials: credentials.into(),
+            retry_policy: RetryPolicy::none(),
+            governor: Governor::default(),
+            throttle: Throttle::default(),
+            response_sink: None,
         }
     }
 
@@ -387,6 +446,45 @@ impl Github {
         self.credentials = credentials.into();
     }
 
+    /// Configure how failed requests are retried. By default they are not
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Configure how outgoing requests are paced against the rate limits.
+    /// By default they are not
+    pub fn set_throttle(&mut self, throttle: Throttle) {
+        self.throttle = throttle;
+    }
+
+    /// Return the latest known rate limit figures for a resource, as
+    /// reported by responses to this client or any of its clones
+    pub fn rate_limit_status(&self, resource: Resource) -> Option<RateLimitResourceStatus> {
+        self.governor.status(resource)
+    }
+
+    /// Run an operation, returning its result along with the metadata of the
+    /// http response it was read from
+    pub fn with_response<T, F>(&self, operation: F) -> Future<ResponseMetadata<T>>
+    where
+        F: FnOnce(&Github) -> Future<T>,
+        T: Send + 'static,
+    {
+        let sink = ResponseSink::default();
+        let github = Github {
+            response_sink: Some(sink.clone()),
+            ..self.clone()
+        };
+        Box::pin(operation(&github).and_then(move |body| async move {
+            let metadata = sink.lock().unwrap().take().ok_or_else(|| {
+                Error::from(std::io::Error::other(
+                    "operation completed without receiving a response",
+                ))
+            })?;
+            Ok(metadata.map(|_| body))
+        }))
+    }
+
     pub fn rate_limit(&self) -> RateLimit {
         RateLimit::new(self.clone())
     }
@@ -462,6 +560,11 @@ impl Github {
         Gists::new(self.clone())
     }
 
+    /// Return a reference to GitHub's GraphQL api
+    pub fn graphql(&self) -> GraphQL {
+        GraphQL::new(self.clone())
+    }
+
     /// Return a reference to an interface that provides access to search operations
     pub fn search(&self) -> Search {
         Search::new(self.clone())
@@ -488,10 +591,9 @@ impl Github {
             #[cfg(feature = "jwt")]
             (AuthenticationConstraint::JWT, creds @ Some(&Credentials::JWT(_))) => creds,
             #[cfg(feature = "app")]
-            (
-                AuthenticationConstraint::JWT,
-                Some(&Credentials::InstallationToken(ref apptoken)),
-            ) => Some(apptoken.jwt()),
+            (AuthenticationConstraint::JWT, Some(Credentials::InstallationToken(apptoken))) => {
+                Some(apptoken.jwt())
+            }
             #[cfg(feature = "jwt")]
             (AuthenticationConstraint::JWT, creds) => {
                 log::error!(
@@ -511,7 +613,7 @@ impl Github {
         let parsed_url = uri.parse::<Url>();
 
         match self.credentials(authentication) {
-            Some(&Credentials::Client(ref id, ref secret)) => Box::pin(future::ready(
+            Some(Credentials::Client(id, secret)) => Box::pin(future::ready(
                 parsed_url
                     .map(|mut u| {
                         u.query_pairs_mut()
@@ -521,21 +623,21 @@ impl Github {
                     })
                     .map_err(Error::from),
             )),
-            Some(&Credentials::Token(ref token)) => {
+            Some(Credentials::Token(token)) => {
                 let auth = format!("token {}", token);
                 Box::pin(future::ready(
                     parsed_url.map(|u| (u, Some(auth))).map_err(Error::from),
                 ))
             }
             #[cfg(feature = "jwt")]
-            Some(&Credentials::JWT(ref jwt)) => {
+            Some(Credentials::JWT(jwt)) => {
                 let auth = format!("Bearer {}", jwt.token());
                 Box::pin(future::ready(
                     parsed_url.map(|u| (u, Some(auth))).map_err(Error::from),
                 ))
             }
             #[cfg(feature = "app")]
-            Some(&Credentials::InstallationToken(ref apptoken)) => {
+            Some(Credentials::InstallationToken(apptoken)) => {
                 if let Some(token) = apptoken.token() {
                     let auth = format!("token {}", token);
                     Box::pin(future::ready(
@@ -563,63 +665,216 @@ impl Github {
         }
     }
 
-    fn request<Out>(
+    /// Send a request, reattempting it as the client's `RetryPolicy` allows
+    fn send(
         &self,
         method: Method,
         uri: &str,
         body: Option<Vec<u8>>,
         media_type: MediaType,
         authentication: AuthenticationConstraint,
-    ) -> Future<(Option<Link>, Out)>
+    ) -> Future<Response<Vec<u8>>> {
+        let instance = self.clone();
+        let uri = uri.to_string();
+        let attempt = {
+            let method = method.clone();
+            let uri = uri.clone();
+            move || -> Future<Attempt<Response<Vec<u8>>>> {
+                Box::pin(
+                    instance
+                        .send_once(
+                            method.clone(),
+                            &uri,
+                            body.clone(),
+                            media_type,
+                            authentication,
+                        )
+                        .map_ok(|response| {
+                            if response.status().is_success() {
+                                Attempt::Done(response)
+                            } else {
+                                Attempt::Failed(response)
+                            }
+                        }),
+                )
+            }
+        };
+        Box::pin(
+            self.retrying(method, uri, attempt)
+                .map_ok(|attempt| match attempt {
+                    Attempt::Done(response) | Attempt::Failed(response) => response,
+                }),
+        )
+    }
+
+    /// Make attempts at a request until one succeeds or the client's
+    /// `RetryPolicy` gives up, sleeping in between as it advises. `attempt`
+    /// resolves to a successful result or to an unsuccessful response, which
+    /// is handed back once the policy gives up
+    fn retrying<T, F>(&self, method: Method, uri: String, mut attempt: F) -> Future<Attempt<T>>
     where
-        Out: DeserializeOwned + 'static + Send,
+        T: Send + 'static,
+        F: FnMut() -> Future<Attempt<T>> + Send + 'static,
     {
+        let policy = self.retry_policy.clone();
+        Box::pin(async move {
+            let mut attempts = 1;
+            loop {
+                let outcome = match attempt().await {
+                    Ok(Attempt::Done(done)) => return Ok(Attempt::Done(done)),
+                    Ok(Attempt::Failed(response)) => Ok(response),
+                    Err(err) => Err(err),
+                };
+                match policy.delay(attempts, &method, &outcome) {
+                    Some(delay) => {
+                        debug!(
+                            "Retrying {} {} in {:?} after attempt {}",
+                            method, uri, delay, attempts
+                        );
+                        tokio::time::sleep(delay).await;
+                        attempts += 1;
+                    }
+                    None => return outcome.map(Attempt::Failed),
+                }
+            }
+        })
+    }
+
+    fn send_once(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Vec<u8>>,
+        media_type: MediaType,
+        authentication: AuthenticationConstraint,
+    ) -> Future<Response<Vec<u8>>> {
+        let instance = self.clone();
+        Box::pin(
+            self.prepare(method, uri, body, media_type, authentication)
+                .and_then(move |request| {
+                    instance.dispatch(request, |transport, request| transport.send(request))
+                }),
+        )
+    }
+
+    /// Build an authenticated request
+    fn prepare(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Vec<u8>>,
+        media_type: MediaType,
+        authentication: AuthenticationConstraint,
+    ) -> Future<Request<Vec<u8>>> {
         let url_and_auth = self.url_and_auth(uri, authentication);
 
         let instance = self.clone();
         #[cfg(feature = "httpcache")]
         let uri2 = uri.to_string();
-        let response = url_and_auth
-            .map_err(Error::from)
-            .and_then(move |(url, auth)| {
-                #[cfg(not(feature = "httpcache"))]
-                let mut req = instance.client.request(method, url);
+        Box::pin(url_and_auth.and_then(move |(url, auth)| {
+            let mut req = Request::builder().method(method.clone()).uri(url.as_str());
 
-                #[cfg(feature = "httpcache")]
-                let mut req = {
-                    let mut req = instance.client.request(method.clone(), url);
-                    if method == Method::GET {
-                        if let Ok(etag) = instance.http_cache.lookup_etag(&uri2) {
-                            req = req.header(IF_NONE_MATCH, etag);
-                        }
+            #[cfg(feature = "httpcache")]
+            {
+                if method == Method::GET {
+                    if let Ok(etag) = instance.http_cache.lookup_etag(&uri2) {
+                        req = req.header(IF_NONE_MATCH, etag);
                     }
-                    req
-                };
+                }
+            }
 
-                req = req.header(USER_AGENT, &*instance.agent);
-                req = req.header(
-                    ACCEPT,
-                    &*format!("{}", qitem::<Mime>(From::from(media_type))),
-                );
+            req = req.header(USER_AGENT, &*instance.agent);
+            req = req.header(
+                ACCEPT,
+                &*format!("{}", qitem::<Mime>(From::from(media_type))),
+            );
 
-                if let Some(auth_str) = auth {
-                    req = req.header(AUTHORIZATION, &*auth_str);
-                }
+            if let Some(auth_str) = auth {
+                req = req.header(AUTHORIZATION, &*auth_str);
+            }
 
-                trace!("Body: {:?}", &body);
-                if let Some(body) = body {
-                    req = req.body(Body::from(body));
-                }
-                debug!("Request: {:?}", &req);
-                req.send().map_err(Error::from)
-            });
+            trace!("Body: {:?}", &body);
+            let request = req.body(body.unwrap_or_default());
+            if let Ok(request) = &request {
+                debug!("Request: {:?}", request);
+            }
+            future::ready(request.map_err(Error::from))
+        }))
+    }
+
+    /// The rate limit bucket a request for a path draws from
+    fn resource(&self, path: &str) -> Resource {
+        let base = self
+            .host
+            .parse::<Url>()
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+        Resource::for_path(&base, path)
+    }
+
+    /// Hand a request to the transport with `send` once the throttle allows
+    /// and track the rate limits its response reports
+    fn dispatch<T, F>(&self, request: Request<Vec<u8>>, send: F) -> Future<Response<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&BoxedTransport, Request<Vec<u8>>) -> Future<Response<T>> + Send + 'static,
+    {
+        let instance = self.clone();
+        let resource = self.resource(request.uri().path());
+        Box::pin(async move {
+            if let Some(delay) = instance.governor.acquire(resource, instance.throttle) {
+                debug!("Throttling {:?} request for {:?}", resource, delay);
+                tokio::time::sleep(delay).await;
+            }
+            let response = send(&instance.transport, request).await?;
+            instance.governor.observe(resource, response.headers());
+            Ok(response)
+        })
+    }
+
+    fn request<Out>(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Vec<u8>>,
+        media_type: MediaType,
+        authentication: AuthenticationConstraint,
+    ) -> Future<(Option<Link>, Out)>
+    where
+        Out: DeserializeOwned + 'static + Send,
+    {
+        Box::pin(
+            self.request_raw(method, uri, body, media_type, authentication)
+                .and_then(|(link, status, body)| async move {
+                    let parsed_response = if status == StatusCode::NO_CONTENT {
+                        serde_json::from_str("null")
+                    } else {
+                        serde_json::from_slice::<Out>(&body)
+                    };
+                    parsed_response.map(|out| (link, out)).map_err(Error::Codec)
+                }),
+        )
+    }
+
+    /// Send a request, resolving to the successful response's body without
+    /// interpreting it
+    fn request_raw(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Vec<u8>>,
+        media_type: MediaType,
+        authentication: AuthenticationConstraint,
+    ) -> Future<(Option<Link>, StatusCode, Vec<u8>)> {
+        let response = self.send(method, uri, body, media_type, authentication);
 
         #[cfg(feature = "httpcache")]
         let instance2 = self.clone();
 
         #[cfg(feature = "httpcache")]
         let uri3 = uri.to_string();
-        Box::pin(response.and_then(move |response| {
+        let sink = self.response_sink.clone();
+        Box::pin(response.and_then(move |response| async move {
             #[cfg(not(feature = "httpcache"))]
             let (remaining, reset) = get_header_values(response.headers());
             #[cfg(feature = "httpcache")]
@@ -631,88 +886,158 @@ impl Github {
                 .get(LINK)
                 .and_then(|l| l.to_str().ok())
                 .and_then(|l| l.parse().ok());
+            let request_id = request_id(response.headers());
+            let (parts, response_body) = response.into_parts();
+            if let Some(sink) = sink {
+                *sink.lock().unwrap() =
+                    Some(ResponseMetadata::new(status, parts.headers, link.as_ref()));
+            }
 
-            Box::pin(
-                response
-                    .bytes()
-                    .map_err(Error::from)
-                    .and_then(move |response_body| async move {
-                        if status.is_success() {
-                            debug!(
-                                "response payload {}",
-                                String::from_utf8_lossy(&response_body)
-                            );
-                            #[cfg(feature = "httpcache")]
-                            {
-                                if let Some(etag) = etag {
-                                    let next_link = link.as_ref().and_then(|l| next_link(&l));
-                                    if let Err(e) = instance2.http_cache.cache_response(
-                                        &uri3,
-                                        &response_body,
-                                        &etag,
-                                        &next_link,
-                                    ) {
-                                        // failing to cache isn't fatal, so just log & swallow the error
-                                        debug!("Failed to cache body & etag: {}", e);
-                                    }
-                                }
-                            }
-                            let parsed_response = if status == StatusCode::NO_CONTENT { serde_json::from_str("null") } else { serde_json::from_slice::<Out>(&response_body) };
-                            parsed_response
-                                .map(|out| (link, out))
-                                .map_err(Error::Codec)
-                        } else if status == StatusCode::NOT_MODIFIED {
-                            // only supported case is when client provides if-none-match
-                            // header when cargo builds with --cfg feature="httpcache"
-                            #[cfg(feature = "httpcache")]
-                            {
-                                instance2
-                                    .http_cache
-                                    .lookup_body(&uri3)
-                                    .map_err(Error::from)
-                                    .and_then(|body| {
-                                        serde_json::from_str::<Out>(&body)
-                                            .map_err(Error::from)
-                                            .and_then(|out| {
-                                                let link = match link {
-                                                    Some(link) => Ok(Some(link)),
-                                                    None => instance2
-                                                        .http_cache
-                                                        .lookup_next_link(&uri3)
-                                                        .map(|next_link| next_link.map(|next| {
-                                                            let next = LinkValue::new(next).push_rel(RelationType::Next);
-                                                            Link::new(vec![next])
-                                                        }))
-                                                };
-                                                link.map(|link| (link, out))
+            if status.is_success() {
+                debug!(
+                    "response payload {}",
+                    String::from_utf8_lossy(&response_body)
+                );
+                #[cfg(feature = "httpcache")]
+                {
+                    if let Some(etag) = etag {
+                        let next_link = link.as_ref().and_then(|l| PageLinks::from_link(l).next);
+                        if let Err(e) = instance2.http_cache.cache_response(
+                            &uri3,
+                            &response_body,
+                            &etag,
+                            &next_link,
+                        ) {
+                            // failing to cache isn't fatal, so just log & swallow the error
+                            debug!("Failed to cache body & etag: {}", e);
+                        }
+                    }
+                }
+                Ok((link, status, response_body))
+            } else if status == StatusCode::NOT_MODIFIED {
+                // only supported case is when client provides if-none-match
+                // header when cargo builds with --cfg feature="httpcache"
+                #[cfg(feature = "httpcache")]
+                {
+                    instance2
+                        .http_cache
+                        .lookup_body(&uri3)
+                        .map_err(Error::from)
+                        .and_then(|body| {
+                            let link =
+                                match link {
+                                    Some(link) => Ok(Some(link)),
+                                    None => instance2.http_cache.lookup_next_link(&uri3).map(
+                                        |next_link| {
+                                            next_link.map(|next| {
+                                                let next = LinkValue::new(next)
+                                                    .push_rel(RelationType::Next);
+                                                Link::new(vec![next])
                                             })
-                                    })
-                            }
+                                        },
+                                    ),
+                                };
+                            link.map(|link| (link, StatusCode::OK, body.into_bytes()))
+                        })
+                }
+                #[cfg(not(feature = "httpcache"))]
+                {
+                    unreachable!(
+                        "this should not be reachable without the httpcache feature enabled"
+                    )
+                }
+            } else {
+                Err(failure(
+                    status,
+                    request_id,
+                    remaining,
+                    reset,
+                    &response_body,
+                ))
+            }
+        }))
+    }
+
+    /// Send a GET request, resolving once the response's headers arrive to
+    /// a stream over its body. Unsuccessful responses are retried and
+    /// reported like those of buffered requests, but successful ones bypass
+    /// the http cache
+    fn get_bytes(&self, uri: &str, media: MediaType) -> Future<Stream<Vec<u8>>> {
+        let instance = self.clone();
+        let uri = self.host.clone() + uri;
+        let attempt = {
+            let uri = uri.clone();
+            move || -> Future<Attempt<Stream<Vec<u8>>>> {
+                let instance = instance.clone();
+                let uri = uri.clone();
+                Box::pin(async move {
+                    let request = instance
+                        .prepare(
+                            Method::GET,
+                            &uri,
+                            None,
+                            media,
+                            AuthenticationConstraint::Unconstrained,
+                        )
+                        .await?;
+                    // a not modified response would leave nothing to stream
+                    #[cfg(feature = "httpcache")]
+                    let request = {
+                        let mut request = request;
+                        request.headers_mut().remove(IF_NONE_MATCH);
+                        request
+                    };
+                    let response = instance
+                        .dispatch(request, |transport, request| {
+                            transport.send_streaming(request)
+                        })
+                        .await?;
+                    let status = response.status();
+                    let link = response
+                        .headers()
+                        .get(LINK)
+                        .and_then(|l| l.to_str().ok())
+                        .and_then(|l| l.parse().ok());
+                    let (parts, body) = response.into_parts();
+                    if let Some(sink) = &instance.response_sink {
+                        *sink.lock().unwrap() = Some(ResponseMetadata::new(
+                            status,
+                            parts.headers.clone(),
+                            link.as_ref(),
+                        ));
+                    }
+                    if status.is_success() {
+                        return Ok(Attempt::Done(body));
+                    }
+                    // buffer failures, which are small, to interpret them
+                    Ok(Attempt::Failed(Response::from_parts(
+                        parts,
+                        body.try_concat().await?,
+                    )))
+                })
+            }
+        };
+        Box::pin(
+            self.retrying(Method::GET, uri, attempt)
+                .and_then(|attempt| async move {
+                    match attempt {
+                        Attempt::Done(body) => Ok(body),
+                        Attempt::Failed(response) => {
                             #[cfg(not(feature = "httpcache"))]
-                            {
-                                unreachable!("this should not be reachable without the httpcache feature enabled")
-                            }
-                        } else {
-                            let error = match (remaining, reset) {
-                                (Some(remaining), Some(reset)) if remaining == 0 => {
-                                    let now = SystemTime::now()
-                                        .duration_since(UNIX_EPOCH)
-                                        .unwrap()
-                                        .as_secs();
-                                    Error::RateLimit {
-                                        reset: Duration::from_secs(u64::from(reset) - now),
-                                    }
-                                }
-                                _ => Error::Fault {
-                                    code: status,
-                                    error: serde_json::from_slice(&response_body)?,
-                                },
-                            };
-                            Err(error)
+                            let (remaining, reset) = get_header_values(response.headers());
+                            #[cfg(feature = "httpcache")]
+                            let (remaining, reset, _) = get_header_values(response.headers());
+                            Err(failure(
+                                response.status(),
+                                request_id(response.headers()),
+                                remaining,
+                                reset,
+                                response.body(),
+                            ))
                         }
-                    }),
-            )
-        }))
+                    }
+                }),
+        )
     }
 
     fn request_entity<D>(
@@ -752,27 +1077,53 @@ impl Github {
         )
     }
 
-    fn get_stream<D>(&self, uri: &str) -> Stream<D>
+    /// Request a textual representation of a resource, like a diff
+    fn get_text(&self, uri: &str, media: MediaType) -> Future<String> {
+        Box::pin(
+            self.request_raw(
+                Method::GET,
+                &(self.host.clone() + uri),
+                None,
+                media,
+                AuthenticationConstraint::Unconstrained,
+            )
+            .map_ok(|(_, _, body)| String::from_utf8_lossy(&body).into_owned()),
+        )
+    }
+
+    fn get_stream<D>(&self, uri: &str) -> Paginator<D>
     where
         D: DeserializeOwned + 'static + Send,
     {
-        unfold(self.clone(), self.get_pages(uri), |x| x)
+        self.get_paginator(uri, |items| (items, None))
     }
 
-    fn get_pages<D>(&self, uri: &str) -> Future<(Option<Link>, D)>
+    fn get_paginator<D, T>(
+        &self,
+        uri: &str,
+        into_contents: fn(D) -> (Vec<T>, Option<u64>),
+    ) -> Paginator<T>
     where
         D: DeserializeOwned + 'static + Send,
+        T: 'static + Send,
     {
-        self.request(
-            Method::GET,
-            &(self.host.clone() + uri),
-            None,
-            MediaType::Json,
-            AuthenticationConstraint::Unconstrained,
-        )
+        self.get_paginator_media(uri, MediaType::Json, into_contents)
+    }
+
+    fn get_paginator_media<D, T>(
+        &self,
+        uri: &str,
+        media: MediaType,
+        into_contents: fn(D) -> (Vec<T>, Option<u64>),
+    ) -> Paginator<T>
+    where
+        D: DeserializeOwned + 'static + Send,
+        T: 'static + Send,
+    {
+        Paginator::new(self.clone(), self.host.clone() + uri, media, into_contents)
     }
 
-    fn get_pages_url<D>(&self, url: &Url) -> Future<(Option<Link>, D)>
+    fn get_pages_url<D>(&self, url: &Url, media: MediaType) -> Future<(Option<Link>, D)>
     where
         D: DeserializeOwned + 'static + Send,
     {
@@ -780,7 +1131,7 @@ impl Github {
             Method::GET,
             url.as_str(),
             None,
-            MediaType::Json,
+            media,
             AuthenticationConstraint::Unconstrained,
         )
     }
@@ -833,6 +1184,15 @@ impl Github {
         )
     }
 
+    fn post_no_response(&self, uri: &str, message: Vec<u8>) -> Future<()> {
+        Box::pin(self.post(uri, message).or_else(|err| async move {
+            match err {
+                Error::Codec(_) => Ok(()),
+                err => Err(err),
+            }
+        }))
+    }
+
     fn post_media<D>(
         &self,
         uri: &str,
@@ -916,6 +1276,50 @@ type HeaderValues = (Option<u32>, Option<u32>);
 #[cfg(feature = "httpcache")]
 type HeaderValues = (Option<u32>, Option<u32>, Option<Vec<u8>>);
 
+/// The outcome of an attempt at a request
+enum Attempt<T> {
+    /// a successful result, which is never retried
+    Done(T),
+    /// an unsuccessful response for the client's `RetryPolicy` to judge
+    Failed(Response<Vec<u8>>),
+}
+
+fn request_id(headers: &HeaderMap<HeaderValue>) -> Option<String> {
+    headers
+        .get(X_GITHUB_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+/// Interpret an unsuccessful response
+fn failure(
+    status: StatusCode,
+    request_id: Option<String>,
+    remaining: Option<u32>,
+    reset: Option<u32>,
+    body: &[u8],
+) -> Error {
+    match (remaining, reset) {
+        (Some(0), Some(reset)) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            Error::RateLimit {
+                reset: Duration::from_secs(u64::from(reset).saturating_sub(now)),
+            }
+        }
+        _ => match serde_json::from_slice(body) {
+            Ok(error) => Error::Fault {
+                code: status,
+                error,
+                request_id,
+            },
+            Err(err) => Error::Codec(err),
+        },
+    }
+}
+
 fn get_header_values(headers: &HeaderMap<HeaderValue>) -> HeaderValues {
     if let Some(value) = headers.get(X_GITHUB_REQUEST_ID) {
         debug!("x-github-request-id: {:?}", value)
@@ -951,57 +1355,6 @@ fn get_header_values(headers: &HeaderMap<HeaderValue>) -> HeaderValues {
     (remaining, reset)
 }
 
-fn next_link(l: &Link) -> Option<String> {
-    l.values().iter().find_map(|value| {
-        value.rel().and_then(|rels| {
-            if rels.iter().any(|rel| rel == &RelationType::Next) {
-                Some(value.link().into())
-            } else {
-                None
-            }
-        })
-    })
-}
-
-/// "unfold" paginated results of a list of github entities
-fn unfold<D, I>(
-    github: Github,
-    first: Future<(Option<Link>, D)>,
-    into_items: fn(D) -> Vec<I>,
-) -> Stream<I>
-where
-    D: DeserializeOwned + 'static + Send,
-    I: 'static + Send,
-{
-    Box::pin(
-        first
-            .map_ok(move |(link, payload)| {
-                let mut items = into_items(payload);
-                items.reverse();
-                stream::try_unfold(
-                    (github, link, items),
-                    move |(github, link, mut items)| async move {
-                        match items.pop() {
-                            Some(item) => Ok(Some((item, (github, link, items)))),
-                            None => match link.and_then(|l| next_link(&l)) {
-                                Some(url) => {
-                                    let url = Url::from_str(&url).unwrap();
-                                    let (link, payload) = github.get_pages_url(&url).await?;
-                                    let mut items = into_items(payload);
-                                    let item = items.remove(0);
-                                    items.reverse();
-                                    Ok(Some((item, (github, link, items))))
-                                }
-                                None => Ok(None),
-                            },
-                        }
-                    },
-                )
-            })
-            .try_flatten_stream(),
-    )
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1027,6 +1380,25 @@ mod tests {
         assert_eq!(default, SortDirection::Asc)
     }
 
+    #[test]
+    fn failures_report_request_ids() {
+        let error = failure(
+            StatusCode::NOT_FOUND,
+            Some("CAFE:1234".into()),
+            None,
+            None,
+            br#"{"message":"Not Found"}"#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "404 Not Found: Not Found (request id CAFE:1234)"
+        );
+        match error {
+            Error::Fault { request_id, .. } => assert_eq!(request_id.as_deref(), Some("CAFE:1234")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
     #[test]
     #[cfg(not(feature = "httpcache"))]
     fn header_values() {
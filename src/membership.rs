@@ -2,7 +2,7 @@
 use serde::Deserialize;
 
 use crate::users::User;
-use crate::{Github, Stream};
+use crate::{Github, Paginator};
 
 /// Provides access to membership operations available for an individual organization
 pub struct OrgMembership {
@@ -26,7 +26,7 @@ impl OrgMembership {
     ///
     /// See the [github docs](https://developer.github.com/v3/orgs/members/)
     /// for more information
-    pub fn invitations(&self) -> Stream<Invitation> {
+    pub fn invitations(&self) -> Paginator<Invitation> {
         self.github
             .get_stream(&format!("/orgs/{}/invitations", self.org))
     }
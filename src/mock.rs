@@ -0,0 +1,1259 @@
+//! An in-process stand-in for the GitHub api
+//!
+//! `MockGithub` serves a stateful subset of the REST api (repositories,
+//! issues, labels, comments, pulls and releases) from in-memory data over a
+//! local http server, so `Github` clients pointed at it via `Github::host`
+//! exercise the same code paths they would against github.com without
+//! requiring network access.
+//!
+//! This module is only available with the `mock` cargo feature enabled and
+//! requires a running tokio runtime.
+//!
+//! ```no_run
+//! use hubcaps::issues::IssueOptions;
+//! use hubcaps::mock::MockGithub;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let mock = MockGithub::start()?;
+//! mock.add_repo("octocat", "hello-world");
+//! let github = mock.client()?;
+//! let issue = github
+//!     .repo("octocat", "hello-world")
+//!     .issues()
+//!     .create(&IssueOptions::new("title", None::<String>, None::<String>, None, vec!["bug"]))
+//!     .await?;
+//! github.repo("octocat", "hello-world").issue(issue.number).close().await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http::header::{CONTENT_TYPE, LINK};
+use http::{Method, StatusCode};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use log::debug;
+use percent_encoding::percent_decode_str;
+use serde_json::{Map, Value};
+use tokio::sync::oneshot;
+use url::form_urlencoded;
+
+use crate::{Error, Github, Result};
+
+/// login of the user owning repositories created through `/user/repos`
+const MOCK_LOGIN: &str = "hubcaps-mock";
+const DEFAULT_PER_PAGE: usize = 30;
+
+/// A running mock GitHub api server. The server shuts down when dropped
+pub struct MockGithub {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockGithub {
+    /// Start a server bound to an ephemeral port on the loopback interface
+    pub fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::new(format!("http://{}", addr))));
+        let service_state = state.clone();
+        let service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(&state, req).await) }
+                }))
+            }
+        });
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)
+            .map_err(|e| Error::from(io::Error::other(e)))?
+            .serve(service)
+            .with_graceful_shutdown(async {
+                rx.await.ok();
+            });
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                debug!("mock server error: {}", e);
+            }
+        });
+        Ok(MockGithub {
+            addr,
+            state,
+            shutdown: Some(tx),
+        })
+    }
+
+    /// The address the server is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The value to provide to `Github::host` to target this server
+    pub fn host(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Return a new client targeting this server
+    pub fn client(&self) -> Result<Github> {
+        Github::host(self.host(), MOCK_LOGIN, None)
+    }
+
+    /// Seed a repository owned by `owner`, returning its representation.
+    /// Seeding an existing repository leaves it untouched
+    pub fn add_repo<O, R>(&self, owner: O, repo: R) -> Value
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        let mut state = self.state.lock().unwrap();
+        let key = (owner.into(), repo.into());
+        if !state.repos.contains_key(&key) {
+            let doc = state.repo_doc(&key.0, &key.1, &Map::new());
+            state.repos.insert(key.clone(), RepoState::new(doc));
+        }
+        state.repos[&key].repo.clone()
+    }
+}
+
+impl Drop for MockGithub {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+struct State {
+    host: String,
+    next_id: u64,
+    repos: BTreeMap<(String, String), RepoState>,
+}
+
+struct RepoState {
+    repo: Value,
+    next_number: u64,
+    labels: Vec<Value>,
+    issues: Vec<Value>,
+    pulls: Vec<Value>,
+    comments: BTreeMap<u64, Vec<Value>>,
+    releases: Vec<Value>,
+}
+
+impl RepoState {
+    fn new(repo: Value) -> Self {
+        RepoState {
+            repo,
+            next_number: 1,
+            labels: Vec::new(),
+            issues: Vec::new(),
+            pulls: Vec::new(),
+            comments: BTreeMap::new(),
+            releases: Vec::new(),
+        }
+    }
+
+    fn issue_mut(&mut self, number: u64) -> Option<&mut Value> {
+        self.issues.iter_mut().find(|i| i["number"] == number)
+    }
+
+    fn label(&self, name: &str) -> Option<&Value> {
+        self.labels
+            .iter()
+            .find(|l| l["name"].as_str().map(|n| n.eq_ignore_ascii_case(name)) == Some(true))
+    }
+}
+
+/// A response in the making
+struct Reply {
+    status: StatusCode,
+    body: Value,
+    link: Option<String>,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Reply::with_status(StatusCode::OK, body)
+    }
+
+    fn created(body: Value) -> Self {
+        Reply::with_status(StatusCode::CREATED, body)
+    }
+
+    fn no_content() -> Self {
+        Reply::with_status(StatusCode::NO_CONTENT, Value::Null)
+    }
+
+    fn with_status(status: StatusCode, body: Value) -> Self {
+        Reply {
+            status,
+            body,
+            link: None,
+        }
+    }
+
+    fn not_found() -> Self {
+        Reply::with_status(
+            StatusCode::NOT_FOUND,
+            serde_json::json!({
+                "message": "Not Found",
+                "documentation_url": "https://docs.github.com/rest"
+            }),
+        )
+    }
+
+    fn invalid(resource: &str, field: &str, code: &str) -> Self {
+        Reply::with_status(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            serde_json::json!({
+                "message": "Validation Failed",
+                "errors": [{ "resource": resource, "field": field, "code": code }],
+                "documentation_url": "https://docs.github.com/rest"
+            }),
+        )
+    }
+
+    fn bad_json() -> Self {
+        Reply::with_status(
+            StatusCode::BAD_REQUEST,
+            serde_json::json!({ "message": "Problems parsing JSON" }),
+        )
+    }
+}
+
+async fn handle(state: &Mutex<State>, req: Request<Body>) -> Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let query: BTreeMap<String, String> = req
+        .uri()
+        .query()
+        .map(|q| form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+        .unwrap_or_default();
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => body,
+        Err(_) => return respond(Reply::bad_json()),
+    };
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice::<Value>(&body) {
+            Ok(body) => body,
+            Err(_) => return respond(Reply::bad_json()),
+        }
+    };
+    let segments = path
+        .trim_matches('/')
+        .split('/')
+        .map(|s| percent_decode_str(s).decode_utf8_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+    debug!("mock {} {}", method, path);
+    let mut reply = route(
+        &mut state.lock().unwrap(),
+        &method,
+        &segments,
+        &query,
+        &body,
+    );
+    if reply.status == StatusCode::OK && reply.body.is_array() {
+        let host = state.lock().unwrap().host.clone();
+        reply = paginate(reply.body, &host, &path, &query);
+    }
+    respond(reply)
+}
+
+fn respond(reply: Reply) -> Response<Body> {
+    let mut response = Response::builder().status(reply.status);
+    if let Some(link) = reply.link {
+        response = response.header(LINK, link);
+    }
+    let body = if reply.status == StatusCode::NO_CONTENT {
+        Body::empty()
+    } else {
+        response = response.header(CONTENT_TYPE, "application/json; charset=utf-8");
+        Body::from(reply.body.to_string())
+    };
+    response.body(body).unwrap()
+}
+
+/// Serve the page of items requested via `page` and `per_page`,
+/// linking to the next and last pages
+fn paginate(items: Value, host: &str, path: &str, query: &BTreeMap<String, String>) -> Reply {
+    let items = match items {
+        Value::Array(items) => items,
+        other => return Reply::ok(other),
+    };
+    let per_page = query
+        .get("per_page")
+        .and_then(|p| p.parse::<usize>().ok())
+        .filter(|p| *p > 0)
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .min(100);
+    let page = query
+        .get("page")
+        .and_then(|p| p.parse::<usize>().ok())
+        .filter(|p| *p > 0)
+        .unwrap_or(1);
+    let last = items.len().div_ceil(per_page).max(1);
+    let page_url = |page: usize| {
+        let mut params = query.clone();
+        params.insert("page".into(), page.to_string());
+        let query: String = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&params)
+            .finish();
+        format!("{}{}?{}", host, path, query)
+    };
+    let mut links = Vec::new();
+    if page < last {
+        links.push(format!("<{}>; rel=\"next\"", page_url(page + 1)));
+        links.push(format!("<{}>; rel=\"last\"", page_url(last)));
+    }
+    if page > 1 {
+        links.push(format!("<{}>; rel=\"first\"", page_url(1)));
+        links.push(format!("<{}>; rel=\"prev\"", page_url(page - 1)));
+    }
+    let items = items
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .collect();
+    Reply {
+        status: StatusCode::OK,
+        body: Value::Array(items),
+        link: Some(links.join(", ")).filter(|l| !l.is_empty()),
+    }
+}
+
+fn route(
+    state: &mut State,
+    method: &Method,
+    segments: &[&str],
+    query: &BTreeMap<String, String>,
+    body: &Value,
+) -> Reply {
+    let fields = body.as_object().cloned().unwrap_or_default();
+    match (method, segments) {
+        (&Method::GET, ["user", "repos"]) => Reply::ok(Value::Array(
+            state
+                .repos
+                .iter()
+                .filter(|((owner, _), _)| owner == MOCK_LOGIN)
+                .map(|(_, repo)| repo.repo.clone())
+                .collect(),
+        )),
+        (&Method::POST, ["user", "repos"]) => match fields.get("name").and_then(Value::as_str) {
+            Some(name) => {
+                let key = (MOCK_LOGIN.to_owned(), name.to_owned());
+                if state.repos.contains_key(&key) {
+                    return Reply::invalid("Repository", "name", "already_exists");
+                }
+                let doc = state.repo_doc(MOCK_LOGIN, name, &fields);
+                state.repos.insert(key, RepoState::new(doc.clone()));
+                Reply::created(doc)
+            }
+            None => Reply::invalid("Repository", "name", "missing_field"),
+        },
+        (_, ["repos", owner, repo, rest @ ..]) => {
+            let key = (owner.to_string(), repo.to_string());
+            if !state.repos.contains_key(&key) {
+                return Reply::not_found();
+            }
+            match (method, rest) {
+                (&Method::GET, []) => Reply::ok(state.repos[&key].repo.clone()),
+                (&Method::PATCH, []) | (&Method::POST, []) => {
+                    let repo = state.repos.get_mut(&key).unwrap();
+                    merge(
+                        &mut repo.repo,
+                        &fields,
+                        &[
+                            "description",
+                            "homepage",
+                            "private",
+                            "has_issues",
+                            "has_wiki",
+                            "default_branch",
+                        ],
+                    );
+                    Reply::ok(repo.repo.clone())
+                }
+                (&Method::DELETE, []) => {
+                    state.repos.remove(&key);
+                    Reply::no_content()
+                }
+                _ => {
+                    let id = state.next_id();
+                    let host = state.host.clone();
+                    let repo = state.repos.get_mut(&key).unwrap();
+                    let ctx = Context {
+                        host: &host,
+                        owner,
+                        repo: repo_name(repo),
+                        id,
+                    };
+                    route_repo(repo, &ctx, method, rest, query, body)
+                }
+            }
+        }
+        _ => Reply::not_found(),
+    }
+}
+
+fn repo_name(repo: &RepoState) -> String {
+    repo.repo["name"].as_str().unwrap_or_default().to_owned()
+}
+
+/// Identifiers available while handling a repository scoped request
+struct Context<'a> {
+    host: &'a str,
+    owner: &'a str,
+    repo: String,
+    /// a fresh id for any entity created by this request
+    id: u64,
+}
+
+impl<'a> Context<'a> {
+    fn url(&self, more: &str) -> String {
+        format!("{}/repos/{}/{}{}", self.host, self.owner, self.repo, more)
+    }
+
+    fn html_url(&self, more: &str) -> String {
+        format!("{}/{}/{}{}", self.host, self.owner, self.repo, more)
+    }
+}
+
+fn route_repo(
+    repo: &mut RepoState,
+    ctx: &Context<'_>,
+    method: &Method,
+    segments: &[&str],
+    query: &BTreeMap<String, String>,
+    body: &Value,
+) -> Reply {
+    let fields = &body.as_object().cloned().unwrap_or_default();
+    // issue and pull listings only include open entries unless asked otherwise
+    let state_filter = |entry: &&Value| match query.get("state").map(String::as_str) {
+        Some("all") => true,
+        Some(state) => entry["state"] == state,
+        None => entry["state"] == "open",
+    };
+    match (method, segments) {
+        // labels
+        (&Method::GET, ["labels"]) => Reply::ok(Value::Array(repo.labels.clone())),
+        (&Method::POST, ["labels"]) => match fields.get("name").and_then(Value::as_str) {
+            Some(name) if repo.label(name).is_some() => {
+                Reply::invalid("Label", "name", "already_exists")
+            }
+            Some(name) => {
+                let label = label_doc(ctx, name, fields);
+                repo.labels.push(label.clone());
+                Reply::created(label)
+            }
+            None => Reply::invalid("Label", "name", "missing_field"),
+        },
+        (&Method::GET, ["labels", name]) => match repo.label(name) {
+            Some(label) => Reply::ok(label.clone()),
+            None => Reply::not_found(),
+        },
+        (&Method::PATCH, ["labels", name]) => {
+            let current = match repo.label(name) {
+                Some(label) => label.clone(),
+                None => return Reply::not_found(),
+            };
+            let mut updated = current.clone();
+            merge(&mut updated, fields, &["color", "description"]);
+            if let Some(new_name) = fields.get("name").and_then(Value::as_str) {
+                updated = label_doc(ctx, new_name, updated.as_object().unwrap());
+            }
+            for label in repo
+                .labels
+                .iter_mut()
+                .chain(repo.issues.iter_mut().flat_map(|issue| {
+                    issue["labels"]
+                        .as_array_mut()
+                        .map(|labels| labels.iter_mut())
+                        .into_iter()
+                        .flatten()
+                }))
+            {
+                if label["name"] == current["name"] {
+                    *label = updated.clone();
+                }
+            }
+            Reply::ok(updated)
+        }
+        (&Method::DELETE, ["labels", name]) => {
+            let current = match repo.label(name) {
+                Some(label) => label["name"].clone(),
+                None => return Reply::not_found(),
+            };
+            repo.labels.retain(|l| l["name"] != current);
+            for issue in &mut repo.issues {
+                if let Some(labels) = issue["labels"].as_array_mut() {
+                    labels.retain(|l| l["name"] != current);
+                }
+            }
+            Reply::no_content()
+        }
+
+        // issues
+        (&Method::GET, ["issues"]) => Reply::ok(Value::Array(
+            repo.issues.iter().filter(state_filter).cloned().collect(),
+        )),
+        (&Method::POST, ["issues"]) => match fields.get("title").and_then(Value::as_str) {
+            Some(title) => {
+                let number = repo.next_number;
+                repo.next_number += 1;
+                let labels = resolve_labels(repo, ctx, fields.get("labels"));
+                let issue = issue_doc(ctx, number, title, fields, labels, None);
+                repo.issues.push(issue.clone());
+                Reply::created(issue)
+            }
+            None => Reply::invalid("Issue", "title", "missing_field"),
+        },
+        (&Method::GET, ["issues", number]) => match parse(number).and_then(|n| repo.issue_mut(n)) {
+            Some(issue) => Reply::ok(issue.clone()),
+            None => Reply::not_found(),
+        },
+        (&Method::PATCH, ["issues", number]) => {
+            let number = match parse(number) {
+                Some(n) if repo.issue_mut(n).is_some() => n,
+                _ => return Reply::not_found(),
+            };
+            let labels = fields
+                .get("labels")
+                .map(|labels| resolve_labels(repo, ctx, Some(labels)));
+            let issue = repo.issue_mut(number).unwrap();
+            merge(issue, fields, &["title", "body", "state"]);
+            if let Some(labels) = labels {
+                issue["labels"] = Value::Array(labels);
+            }
+            touch(issue);
+            let issue = issue.clone();
+            sync_pull(repo, number);
+            Reply::ok(issue)
+        }
+
+        // issue labels
+        (&Method::GET, ["issues", number, "labels"]) => {
+            match parse(number).and_then(|n| repo.issue_mut(n)) {
+                Some(issue) => Reply::ok(issue["labels"].clone()),
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::POST, ["issues", number, "labels"])
+        | (&Method::PUT, ["issues", number, "labels"]) => {
+            let number = match parse(number) {
+                Some(n) if repo.issue_mut(n).is_some() => n,
+                _ => return Reply::not_found(),
+            };
+            // labels may be provided as a bare array or under a "labels" key
+            let requested = fields.get("labels").unwrap_or(body);
+            let labels = resolve_labels(repo, ctx, Some(requested));
+            let issue = repo.issue_mut(number).unwrap();
+            if method == Method::PUT {
+                issue["labels"] = Value::Array(Vec::new());
+            }
+            let current = issue["labels"].as_array_mut().unwrap();
+            for label in labels {
+                if !current.iter().any(|l| l["name"] == label["name"]) {
+                    current.push(label);
+                }
+            }
+            let labels = issue["labels"].clone();
+            sync_pull(repo, number);
+            Reply::ok(labels)
+        }
+        (&Method::DELETE, ["issues", number, "labels"]) => {
+            match parse(number).and_then(|n| repo.issue_mut(n)) {
+                Some(issue) => {
+                    issue["labels"] = Value::Array(Vec::new());
+                    let number = issue["number"].as_u64().unwrap();
+                    sync_pull(repo, number);
+                    Reply::no_content()
+                }
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::DELETE, ["issues", number, "labels", name]) => {
+            match parse(number).and_then(|n| repo.issue_mut(n)) {
+                Some(issue) => {
+                    let labels = issue["labels"].as_array_mut().unwrap();
+                    let before = labels.len();
+                    labels.retain(|l| l["name"].as_str() != Some(*name));
+                    if labels.len() == before {
+                        return Reply::not_found();
+                    }
+                    let labels = Value::Array(labels.clone());
+                    let number = issue["number"].as_u64().unwrap();
+                    sync_pull(repo, number);
+                    Reply::ok(labels)
+                }
+                None => Reply::not_found(),
+            }
+        }
+
+        // issue comments
+        (&Method::GET, ["issues", number, "comments"]) => {
+            match parse(number).filter(|n| repo.issue_mut(*n).is_some()) {
+                Some(n) => Reply::ok(Value::Array(
+                    repo.comments.get(&n).cloned().unwrap_or_default(),
+                )),
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::POST, ["issues", number, "comments"]) => {
+            let number = match parse(number) {
+                Some(n) if repo.issue_mut(n).is_some() => n,
+                _ => return Reply::not_found(),
+            };
+            match fields.get("body").and_then(Value::as_str) {
+                Some(body) => {
+                    let now = now();
+                    let comment = serde_json::json!({
+                        "id": ctx.id,
+                        "url": ctx.url(&format!("/issues/comments/{}", ctx.id)),
+                        "html_url": ctx.html_url(&format!("/issues/{}#issuecomment-{}", number, ctx.id)),
+                        "body": body,
+                        "user": user_doc(ctx.host, MOCK_LOGIN),
+                        "created_at": now,
+                        "updated_at": now,
+                    });
+                    repo.comments
+                        .entry(number)
+                        .or_default()
+                        .push(comment.clone());
+                    let issue = repo.issue_mut(number).unwrap();
+                    issue["comments"] =
+                        serde_json::json!(issue["comments"].as_u64().unwrap_or(0) + 1);
+                    Reply::created(comment)
+                }
+                None => Reply::invalid("IssueComment", "body", "missing_field"),
+            }
+        }
+
+        // pulls
+        (&Method::GET, ["pulls"]) => Reply::ok(Value::Array(
+            repo.pulls.iter().filter(state_filter).cloned().collect(),
+        )),
+        (&Method::POST, ["pulls"]) => {
+            let field = |name| fields.get(name).and_then(Value::as_str);
+            match (field("title"), field("head"), field("base")) {
+                (Some(title), Some(head), Some(base)) => {
+                    let number = repo.next_number;
+                    repo.next_number += 1;
+                    let pull_ref = serde_json::json!({
+                        "url": ctx.url(&format!("/pulls/{}", number)),
+                        "html_url": ctx.html_url(&format!("/pull/{}", number)),
+                        "diff_url": ctx.html_url(&format!("/pull/{}.diff", number)),
+                        "patch_url": ctx.html_url(&format!("/pull/{}.patch", number)),
+                    });
+                    let issue = issue_doc(ctx, number, title, fields, Vec::new(), Some(pull_ref));
+                    let pull = pull_doc(ctx, &issue, head, base);
+                    repo.issues.push(issue);
+                    repo.pulls.push(pull.clone());
+                    Reply::created(pull)
+                }
+                (None, _, _) => Reply::invalid("PullRequest", "title", "missing_field"),
+                (_, None, _) => Reply::invalid("PullRequest", "head", "missing_field"),
+                (_, _, None) => Reply::invalid("PullRequest", "base", "missing_field"),
+            }
+        }
+        (&Method::GET, ["pulls", number]) => {
+            match parse(number).and_then(|n| repo.pulls.iter().find(|p| p["number"] == n)) {
+                Some(pull) => Reply::ok(pull.clone()),
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::PATCH, ["pulls", number]) => {
+            let number = match parse(number) {
+                Some(n) if repo.pulls.iter().any(|p| p["number"] == n) => n,
+                _ => return Reply::not_found(),
+            };
+            let issue = repo.issue_mut(number).unwrap();
+            merge(issue, fields, &["title", "body", "state"]);
+            touch(issue);
+            sync_pull(repo, number);
+            Reply::ok(
+                repo.pulls
+                    .iter()
+                    .find(|p| p["number"] == number)
+                    .unwrap()
+                    .clone(),
+            )
+        }
+
+        (&Method::GET, ["pulls", number, "merge"]) => {
+            match parse(number).and_then(|n| repo.pulls.iter().find(|p| p["number"] == n)) {
+                Some(pull) if pull["merged"] == true => Reply::no_content(),
+                _ => Reply::not_found(),
+            }
+        }
+        (&Method::PUT, ["pulls", number, "merge"]) => {
+            let number = match parse(number) {
+                Some(n) if repo.pulls.iter().any(|p| p["number"] == n) => n,
+                _ => return Reply::not_found(),
+            };
+            let pull = repo.pulls.iter().find(|p| p["number"] == number).unwrap();
+            if pull["state"] != "open" {
+                return Reply::with_status(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    serde_json::json!({ "message": "Pull Request is not mergeable" }),
+                );
+            }
+            if fields
+                .get("sha")
+                .is_some_and(|sha| *sha != pull["head"]["sha"])
+            {
+                return Reply::with_status(
+                    StatusCode::CONFLICT,
+                    serde_json::json!({
+                        "message": "Head branch was modified. Review and try the merge again."
+                    }),
+                );
+            }
+            let sha = fake_sha(&format!("{}/{}#{}", ctx.owner, ctx.repo, number));
+            let issue = repo.issue_mut(number).unwrap();
+            issue["state"] = serde_json::json!("closed");
+            touch(issue);
+            sync_pull(repo, number);
+            let pull = repo
+                .pulls
+                .iter_mut()
+                .find(|p| p["number"] == number)
+                .unwrap();
+            pull["merged"] = serde_json::json!(true);
+            pull["merged_at"] = pull["closed_at"].clone();
+            pull["merge_commit_sha"] = serde_json::json!(sha);
+            pull["mergeable"] = Value::Null;
+            pull["mergeable_state"] = serde_json::json!("unknown");
+            Reply::ok(serde_json::json!({
+                "sha": sha,
+                "merged": true,
+                "message": "Pull Request successfully merged",
+            }))
+        }
+        (&Method::PUT, ["pulls", number, "update-branch"]) => {
+            let pull =
+                match parse(number).and_then(|n| repo.pulls.iter().find(|p| p["number"] == n)) {
+                    Some(pull) => pull,
+                    None => return Reply::not_found(),
+                };
+            match fields.get("expected_head_sha") {
+                Some(sha) if *sha != pull["head"]["sha"] => Reply::with_status(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    serde_json::json!({
+                        "message": "expected head sha didn't match current head ref."
+                    }),
+                ),
+                _ => Reply::with_status(
+                    StatusCode::ACCEPTED,
+                    serde_json::json!({
+                        "message": "Updating pull request branch.",
+                        "url": pull["html_url"],
+                    }),
+                ),
+            }
+        }
+
+        // releases
+        (&Method::GET, ["releases"]) => Reply::ok(Value::Array(repo.releases.clone())),
+        (&Method::POST, ["releases"]) => match fields.get("tag_name").and_then(Value::as_str) {
+            Some(tag) if repo.releases.iter().any(|r| r["tag_name"] == tag) => {
+                Reply::invalid("Release", "tag_name", "already_exists")
+            }
+            Some(tag) => {
+                let release = release_doc(ctx, tag, fields, repo);
+                repo.releases.insert(0, release.clone());
+                Reply::created(release)
+            }
+            None => Reply::invalid("Release", "tag_name", "missing_field"),
+        },
+        (&Method::GET, ["releases", "latest"]) => match repo
+            .releases
+            .iter()
+            .find(|r| r["draft"] == false && r["prerelease"] == false)
+        {
+            Some(release) => Reply::ok(release.clone()),
+            None => Reply::not_found(),
+        },
+        (&Method::GET, ["releases", "tags", tag]) => {
+            match repo.releases.iter().find(|r| r["tag_name"] == *tag) {
+                Some(release) => Reply::ok(release.clone()),
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::GET, ["releases", id]) => {
+            match parse(id).and_then(|id| repo.releases.iter().find(|r| r["id"] == id)) {
+                Some(release) => Reply::ok(release.clone()),
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::GET, ["releases", id, "assets"]) => {
+            match parse(id).and_then(|id| repo.releases.iter().find(|r| r["id"] == id)) {
+                Some(release) => Reply::ok(release["assets"].clone()),
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::PATCH, ["releases", id]) => {
+            match parse(id).and_then(|id| repo.releases.iter_mut().find(|r| r["id"] == id)) {
+                Some(release) => {
+                    merge(
+                        release,
+                        fields,
+                        &[
+                            "tag_name",
+                            "target_commitish",
+                            "name",
+                            "body",
+                            "draft",
+                            "prerelease",
+                        ],
+                    );
+                    Reply::ok(release.clone())
+                }
+                None => Reply::not_found(),
+            }
+        }
+        (&Method::DELETE, ["releases", id]) => match parse(id) {
+            Some(id) if repo.releases.iter().any(|r| r["id"] == id) => {
+                repo.releases.retain(|r| r["id"] != id);
+                Reply::no_content()
+            }
+            _ => Reply::not_found(),
+        },
+        _ => Reply::not_found(),
+    }
+}
+
+fn parse(value: &str) -> Option<u64> {
+    value.parse().ok()
+}
+
+/// Copy the provided non-null fields onto a document
+fn merge(doc: &mut Value, fields: &Map<String, Value>, allowed: &[&str]) {
+    for name in allowed {
+        if let Some(value) = fields.get(*name).filter(|v| !v.is_null()) {
+            doc[*name] = value.clone();
+        }
+    }
+}
+
+fn touch(issue: &mut Value) {
+    let now = now();
+    issue["closed_at"] = if issue["state"] == "closed" {
+        if issue["closed_at"].is_null() {
+            serde_json::json!(now)
+        } else {
+            issue["closed_at"].clone()
+        }
+    } else {
+        Value::Null
+    };
+    issue["updated_at"] = serde_json::json!(now);
+}
+
+/// Reflect changes made to the issue backing a pull onto the pull itself
+fn sync_pull(repo: &mut RepoState, number: u64) {
+    let issue = match repo.issues.iter().find(|i| i["number"] == number) {
+        Some(issue) => issue.clone(),
+        None => return,
+    };
+    if let Some(pull) = repo.pulls.iter_mut().find(|p| p["number"] == number) {
+        for field in &[
+            "title",
+            "body",
+            "state",
+            "labels",
+            "updated_at",
+            "closed_at",
+        ] {
+            pull[*field] = issue[*field].clone();
+        }
+    }
+}
+
+/// Resolve label names to label documents, creating any which do not exist yet
+fn resolve_labels(repo: &mut RepoState, ctx: &Context<'_>, names: Option<&Value>) -> Vec<Value> {
+    let names = names
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| match name {
+                    Value::String(name) => Some(name.clone()),
+                    Value::Object(label) => {
+                        label.get("name").and_then(Value::as_str).map(str::to_owned)
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    names
+        .iter()
+        .map(|name| match repo.label(name) {
+            Some(label) => label.clone(),
+            None => {
+                let label = label_doc(ctx, name, &Map::new());
+                repo.labels.push(label.clone());
+                label
+            }
+        })
+        .collect()
+}
+
+impl State {
+    fn new(host: String) -> Self {
+        State {
+            host,
+            next_id: 1,
+            repos: BTreeMap::new(),
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn repo_doc(&mut self, owner: &str, name: &str, fields: &Map<String, Value>) -> Value {
+        let id = self.next_id();
+        let now = now();
+        let api = format!("{}/repos/{}/{}", self.host, owner, name);
+        let html = format!("{}/{}/{}", self.host, owner, name);
+        let mut doc = serde_json::json!({
+            "id": id,
+            "owner": user_doc(&self.host, owner),
+            "name": name,
+            "full_name": format!("{}/{}", owner, name),
+            "description": fields.get("description").cloned().unwrap_or(Value::Null),
+            "private": fields.get("private").cloned().unwrap_or(serde_json::json!(false)),
+            "fork": false,
+            "url": api,
+            "html_url": html,
+            "clone_url": format!("{}.git", html),
+            "git_url": format!("{}.git", html),
+            "ssh_url": format!("git@localhost:{}/{}.git", owner, name),
+            "svn_url": html,
+            "mirror_url": null,
+            "homepage": fields.get("homepage").cloned().unwrap_or(Value::Null),
+            "language": null,
+            "forks_count": 0,
+            "stargazers_count": 0,
+            "watchers_count": 0,
+            "size": 0,
+            "default_branch": "main",
+            "open_issues_count": 0,
+            "has_issues": fields.get("has_issues").cloned().unwrap_or(serde_json::json!(true)),
+            "has_wiki": fields.get("has_wiki").cloned().unwrap_or(serde_json::json!(true)),
+            "has_pages": false,
+            "has_downloads": fields.get("has_downloads").cloned().unwrap_or(serde_json::json!(true)),
+            "archived": false,
+            "pushed_at": now,
+            "created_at": now,
+            "updated_at": now,
+        });
+        for resource in &[
+            "archive",
+            "assignees",
+            "blobs",
+            "branches",
+            "collaborators",
+            "comments",
+            "commits",
+            "compare",
+            "contents",
+            "contributors",
+            "deployments",
+            "downloads",
+            "events",
+            "forks",
+            "git_commits",
+            "git_refs",
+            "git_tags",
+            "hooks",
+            "issue_comment",
+            "issue_events",
+            "issues",
+            "keys",
+            "labels",
+            "languages",
+            "merges",
+            "milestones",
+            "notifications",
+            "pulls",
+            "releases",
+            "stargazers",
+            "statuses",
+            "subscribers",
+            "subscription",
+            "tags",
+            "teams",
+            "trees",
+        ] {
+            doc[format!("{}_url", resource)] = serde_json::json!(format!(
+                "{}/{}",
+                api,
+                resource
+                    .replace("git_", "git/")
+                    .replace("issue_", "issues/")
+            ));
+        }
+        doc
+    }
+}
+
+fn user_doc(host: &str, login: &str) -> Value {
+    let url = format!("{}/users/{}", host, login);
+    serde_json::json!({
+        "login": login,
+        "id": fake_id(login),
+        "avatar_url": format!("{}/avatars/{}", host, login),
+        "gravatar_id": "",
+        "url": url,
+        "html_url": format!("{}/{}", host, login),
+        "followers_url": format!("{}/followers", url),
+        "following_url": format!("{}/following{{/other_user}}", url),
+        "gists_url": format!("{}/gists{{/gist_id}}", url),
+        "starred_url": format!("{}/starred{{/owner}}{{/repo}}", url),
+        "subscriptions_url": format!("{}/subscriptions", url),
+        "organizations_url": format!("{}/orgs", url),
+        "repos_url": format!("{}/repos", url),
+        "events_url": format!("{}/events{{/privacy}}", url),
+        "received_events_url": format!("{}/received_events", url),
+        "type": "User",
+        "site_admin": false,
+    })
+}
+
+fn label_doc(ctx: &Context<'_>, name: &str, fields: &Map<String, Value>) -> Value {
+    serde_json::json!({
+        "url": ctx.url(&format!("/labels/{}", name)),
+        "name": name,
+        "color": fields.get("color").cloned().unwrap_or_else(|| serde_json::json!("ededed")),
+        "description": fields.get("description").cloned().unwrap_or(Value::Null),
+    })
+}
+
+fn issue_doc(
+    ctx: &Context<'_>,
+    number: u64,
+    title: &str,
+    fields: &Map<String, Value>,
+    labels: Vec<Value>,
+    pull_request: Option<Value>,
+) -> Value {
+    let now = now();
+    let url = ctx.url(&format!("/issues/{}", number));
+    serde_json::json!({
+        "id": ctx.id,
+        "url": url,
+        "labels_url": format!("{}/labels{{/name}}", url),
+        "comments_url": format!("{}/comments", url),
+        "events_url": format!("{}/events", url),
+        "html_url": ctx.html_url(&format!("/issues/{}", number)),
+        "number": number,
+        "state": "open",
+        "title": title,
+        "body": fields.get("body").cloned().unwrap_or(Value::Null),
+        "user": user_doc(ctx.host, MOCK_LOGIN),
+        "labels": labels,
+        "assignee": null,
+        "assignees": [],
+        "locked": false,
+        "comments": 0,
+        "pull_request": pull_request,
+        "closed_at": null,
+        "created_at": now,
+        "updated_at": now,
+    })
+}
+
+fn pull_doc(ctx: &Context<'_>, issue: &Value, head: &str, base: &str) -> Value {
+    let number = issue["number"].as_u64().unwrap_or_default();
+    let url = ctx.url(&format!("/pulls/{}", number));
+    let commit = |name: &str| {
+        serde_json::json!({
+            "label": format!("{}:{}", ctx.owner, name),
+            "ref": name,
+            "sha": fake_sha(&format!("{}/{}:{}", ctx.owner, ctx.repo, name)),
+            "user": user_doc(ctx.host, ctx.owner),
+        })
+    };
+    serde_json::json!({
+        "id": ctx.id,
+        "url": url,
+        "html_url": ctx.html_url(&format!("/pull/{}", number)),
+        "diff_url": ctx.html_url(&format!("/pull/{}.diff", number)),
+        "patch_url": ctx.html_url(&format!("/pull/{}.patch", number)),
+        "issue_url": issue["url"],
+        "commits_url": format!("{}/commits", url),
+        "review_comments_url": format!("{}/comments", url),
+        "review_comment_url": ctx.url("/pulls/comments{/number}"),
+        "comments_url": issue["comments_url"],
+        "statuses_url": ctx.url(&format!("/statuses/{}", fake_sha(head))),
+        "number": number,
+        "state": issue["state"],
+        "title": issue["title"],
+        "body": issue["body"],
+        "created_at": issue["created_at"],
+        "updated_at": issue["updated_at"],
+        "closed_at": null,
+        "merged_at": null,
+        "head": commit(head),
+        "base": commit(base),
+        "user": issue["user"],
+        "assignee": null,
+        "assignees": [],
+        "merge_commit_sha": null,
+        "mergeable": true,
+        "mergeable_state": "clean",
+        "rebaseable": true,
+        "merged": false,
+        "merged_by": null,
+        "comments": 0,
+        "commits": 1,
+        "additions": 0,
+        "deletions": 0,
+        "changed_files": 0,
+        "labels": issue["labels"],
+    })
+}
+
+fn release_doc(
+    ctx: &Context<'_>,
+    tag: &str,
+    fields: &Map<String, Value>,
+    repo: &RepoState,
+) -> Value {
+    let now = now();
+    let url = ctx.url(&format!("/releases/{}", ctx.id));
+    let string = |name: &str, default: &str| {
+        fields
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or(default)
+            .to_owned()
+    };
+    let flag = |name: &str| fields.get(name).and_then(Value::as_bool).unwrap_or(false);
+    serde_json::json!({
+        "url": url,
+        "html_url": ctx.html_url(&format!("/releases/tag/{}", tag)),
+        "assets_url": format!("{}/assets", url),
+        "upload_url": format!("{}/assets{{?name,label}}", url),
+        "tarball_url": ctx.url(&format!("/tarball/{}", tag)),
+        "zipball_url": ctx.url(&format!("/zipball/{}", tag)),
+        "id": ctx.id,
+        "tag_name": tag,
+        "target_commitish": string(
+            "target_commitish",
+            repo.repo["default_branch"].as_str().unwrap_or("main"),
+        ),
+        "name": string("name", tag),
+        "body": string("body", ""),
+        "draft": flag("draft"),
+        "prerelease": flag("prerelease"),
+        "created_at": now,
+        "published_at": now,
+        "author": user_doc(ctx.host, MOCK_LOGIN),
+        "assets": [],
+    })
+}
+
+fn fake_id(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish() >> 40
+}
+
+/// A stable, 40 character hex digest standing in for a git sha
+fn fake_sha(value: &str) -> String {
+    (0..3u8)
+        .map(|salt| {
+            let mut hasher = DefaultHasher::new();
+            (salt, value).hash(&mut hasher);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect::<String>()[..40]
+        .to_owned()
+}
+
+/// The current time formatted as an ISO 8601 UTC timestamp
+fn now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    timestamp(secs)
+}
+
+fn timestamp(secs: u64) -> String {
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn fake_shas() {
+        assert_eq!(fake_sha("main").len(), 40);
+        assert_eq!(fake_sha("main"), fake_sha("main"));
+        assert_ne!(fake_sha("main"), fake_sha("develop"));
+    }
+
+    #[test]
+    fn pagination_links() {
+        let items = Value::Array((0..5).map(|i| serde_json::json!(i)).collect());
+        let mut query = BTreeMap::new();
+        query.insert("per_page".to_owned(), "2".to_owned());
+        let first = paginate(items.clone(), "http://host", "/things", &query);
+        assert_eq!(first.body, serde_json::json!([0, 1]));
+        assert_eq!(
+            first.link.unwrap(),
+            "<http://host/things?page=2&per_page=2>; rel=\"next\", \
+             <http://host/things?page=3&per_page=2>; rel=\"last\""
+        );
+        query.insert("page".to_owned(), "3".to_owned());
+        let last = paginate(items, "http://host", "/things", &query);
+        assert_eq!(last.body, serde_json::json!([4]));
+        assert!(!last.link.unwrap().contains("rel=\"next\""));
+    }
+}
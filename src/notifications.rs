@@ -7,6 +7,7 @@ use url::form_urlencoded;
 use crate::users::User;
 use crate::Future;
 use crate::Github;
+use crate::Paginator;
 
 /// Provides access to notifications.
 /// See the [github docs](https://developer.github.com/v3/activity/notifications/)
@@ -33,6 +34,15 @@ impl Notifications {
         self.github.get(&uri.join("?"))
     }
 
+    /// Provides a stream over all pages of the authenticated user's notifications.
+    pub fn iter(&self, options: &ThreadListOptions) -> Paginator<Thread> {
+        let mut uri = vec!["/notifications".into()];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
+
     /// List the authenticated user's notifications for a repository.
     ///
     /// See the [github docs](https://developer.github.com/v3/activity/notifications/#list-your-notifications-in-a-repository)
@@ -58,6 +68,29 @@ impl Notifications {
         self.github.get(&uri.join("?"))
     }
 
+    /// Provides a stream over all pages of the authenticated user's
+    /// notifications for a repository.
+    pub fn iter_for_repo<O, R>(
+        &self,
+        owner: O,
+        repo: R,
+        options: &ThreadListOptions,
+    ) -> Paginator<Thread>
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        let mut uri = vec![format!(
+            "/repos/{}/{}/notifications",
+            owner.into(),
+            repo.into()
+        )];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
+
     /// Mark notifications as read. Default: `now`
     ///
     /// See the [github docs](https://developer.github.com/v3/activity/notifications/#mark-as-read)
@@ -266,11 +299,11 @@ impl ThreadListOptionsBuilder {
 
 #[derive(Debug, Deserialize)]
 pub struct Subject {
-    title: String,
-    url: String,
-    latest_comment_url: String,
+    pub title: String,
+    pub url: String,
+    pub latest_comment_url: String,
     #[serde(rename = "type")]
-    kind: String,
+    pub kind: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,10 +1,12 @@
 //! Organizations interface
 use serde::Deserialize;
 
+use crate::hooks::OrgHooks;
 use crate::membership::OrgMembership;
 use crate::repositories::OrgRepositories;
+use crate::rulesets::OrgRulesets;
 use crate::teams::OrgTeams;
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator};
 
 /// Provides access to label operations available for an individual organization
 pub struct Organization {
@@ -38,6 +40,16 @@ impl Organization {
     pub fn repos(&self) -> OrgRepositories {
         OrgRepositories::new(self.github.clone(), self.org.clone())
     }
+
+    /// returns a reference to an interface for this organization's hooks
+    pub fn hooks(&self) -> OrgHooks {
+        OrgHooks::new(self.github.clone(), self.org.clone())
+    }
+
+    /// returns a reference to an interface for this organization's rulesets
+    pub fn rulesets(&self) -> OrgRulesets {
+        OrgRulesets::new(self.github.clone(), self.org.clone())
+    }
 }
 
 pub struct Organizations {
@@ -59,6 +71,11 @@ impl Organizations {
     pub fn list(&self) -> Future<Vec<Org>> {
         self.github.get(&self.path(""))
     }
+
+    /// provides a stream over all pages of the authenticated user's organizations
+    pub fn iter(&self) -> Paginator<Org> {
+        self.github.get_stream(&self.path(""))
+    }
 }
 
 pub struct UserOrganizations {
@@ -86,6 +103,12 @@ impl UserOrganizations {
     pub fn list(&self) -> Future<Vec<Org>> {
         self.github.get(&self.path(""))
     }
+
+    /// provides a stream over all pages of the organizations this user is
+    /// publicly associated with
+    pub fn iter(&self) -> Paginator<Org> {
+        self.github.get_stream(&self.path(""))
+    }
 }
 
 // representations
@@ -0,0 +1,300 @@
+//! Paginated listings
+//!
+//! Every `iter(..)` style method returns a `Paginator`. Used as a
+//! `futures::Stream` it yields each item of every page, following `rel="next"`
+//! links as it goes. It also offers control over the underlying pages.
+//!
+//! ```no_run
+//! use futures::prelude::*;
+//! use hubcaps::issues::IssueListOptions;
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", None)?;
+//! let issues = github
+//!     .repo("softprops", "hubcaps")
+//!     .issues()
+//!     .iter(&IssueListOptions::default());
+//!
+//! // fetch up to four pages at a time once the number of pages is known
+//! let mut pages = issues.concurrent_pages(4);
+//! while let Some(page) = pages.try_next().await? {
+//!     println!("page {} of {:?}", page.number, page.last_page());
+//!     // persist this to resume the crawl later with `issues.resume(cursor)`
+//!     let _cursor = page.next_cursor();
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::{prelude::*, stream, Stream as StdStream};
+use hyperx::header::Link;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::response::PageLinks;
+use crate::{Error, Future, Github, MediaType, Result, Stream};
+
+type Fetch<T> = Arc<dyn Fn(&Github, &Url) -> Future<(Option<Link>, Contents<T>)> + Send + Sync>;
+
+/// The items of a page and the total number of items, if reported
+type Contents<T> = (Vec<T>, Option<u64>);
+
+/// A paginated listing of `T`s
+pub struct Paginator<T> {
+    github: Github,
+    url: String,
+    fetch: Fetch<T>,
+    items: Option<Stream<T>>,
+}
+
+impl<T> Paginator<T>
+where
+    T: Send + 'static,
+{
+    /// Create a paginator starting at `url`, extracting the items of each
+    /// page's `D` payload with `into_contents`
+    pub(crate) fn new<D>(
+        github: Github,
+        url: String,
+        media: MediaType,
+        into_contents: fn(D) -> Contents<T>,
+    ) -> Self
+    where
+        D: DeserializeOwned + Send + 'static,
+    {
+        Paginator {
+            github,
+            url,
+            fetch: Arc::new(move |github, url| {
+                Box::pin(
+                    github
+                        .get_pages_url::<D>(url, media)
+                        .map_ok(move |(link, payload)| (link, into_contents(payload))),
+                )
+            }),
+            items: None,
+        }
+    }
+
+    /// Return a cursor marking where this paginator starts
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            url: self.url.clone(),
+        }
+    }
+
+    /// Return a paginator over the same listing starting at `cursor`
+    pub fn resume(&self, cursor: Cursor) -> Self {
+        Paginator {
+            github: self.github.clone(),
+            url: cursor.url,
+            fetch: self.fetch.clone(),
+            items: None,
+        }
+    }
+
+    /// Fetch the page with the given (1-based) number
+    pub fn page(&self, number: u32) -> Future<Page<T>> {
+        let url = Url::from_str(&self.url).map(|url| with_page(&url, number));
+        match url {
+            Ok(url) => self.fetch_page(url),
+            Err(err) => Box::pin(future::err(Error::from(err))),
+        }
+    }
+
+    /// Return a stream of whole pages, following `rel="next"` links
+    pub fn pages(&self) -> Stream<Page<T>> {
+        let paginator = self.clone();
+        Box::pin(stream::try_unfold(Some(self.url.clone()), move |next| {
+            let paginator = paginator.clone();
+            async move {
+                match next {
+                    Some(url) => {
+                        let page = paginator.fetch_page(Url::from_str(&url)?).await?;
+                        let next = page.links.next.clone();
+                        Ok(Some((page, next)))
+                    }
+                    None => Ok(None),
+                }
+            }
+        }))
+    }
+
+    /// Return a stream of whole pages, fetching up to `limit` at a time once
+    /// the first page has reported the number of the last. Pages are
+    /// yielded in order. Listings which don't report their last page are
+    /// fetched one page at a time
+    pub fn concurrent_pages(&self, limit: usize) -> Stream<Page<T>> {
+        let paginator = self.clone();
+        Box::pin(
+            async move {
+                let first = paginator.fetch_page(Url::from_str(&paginator.url)?).await?;
+                let rest: Stream<Page<T>> = match (first.last_page(), first.links.next.clone()) {
+                    (Some(last), Some(_)) if last > first.number => Box::pin(
+                        stream::iter((first.number + 1)..=last)
+                            .map(move |number| paginator.page(number))
+                            .buffered(limit.max(1)),
+                    ),
+                    (_, Some(next)) => paginator.resume(Cursor { url: next }).pages(),
+                    _ => Box::pin(stream::empty()),
+                };
+                Ok(stream::once(future::ok(first)).chain(rest))
+            }
+            .try_flatten_stream(),
+        )
+    }
+
+    fn fetch_page(&self, url: Url) -> Future<Page<T>> {
+        let number = page_number(&url);
+        Box::pin(
+            (self.fetch)(&self.github, &url).map_ok(move |(link, (items, total_count))| Page {
+                items,
+                number,
+                total_count,
+                links: link.as_ref().map(PageLinks::from_link).unwrap_or_default(),
+            }),
+        )
+    }
+}
+
+impl<T> Clone for Paginator<T> {
+    fn clone(&self) -> Self {
+        Paginator {
+            github: self.github.clone(),
+            url: self.url.clone(),
+            fetch: self.fetch.clone(),
+            items: None,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Paginator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Paginator").field("url", &self.url).finish()
+    }
+}
+
+impl<T> StdStream for Paginator<T>
+where
+    T: Send + 'static,
+{
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.items.is_none() {
+            let items = self
+                .pages()
+                .map_ok(|page| stream::iter(page.items.into_iter().map(Ok)))
+                .try_flatten();
+            self.items = Some(Box::pin(items));
+        }
+        match self.items {
+            Some(ref mut items) => items.as_mut().poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A single page of a listing
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// this page's (1-based) number
+    pub number: u32,
+    /// the total number of items in the listing, for listings which report it
+    pub total_count: Option<u64>,
+    pub links: PageLinks,
+}
+
+impl<T> Page<T> {
+    /// Return the number of the listing's last page, if known
+    pub fn last_page(&self) -> Option<u32> {
+        match (&self.links.last, &self.links.next) {
+            (Some(last), _) => Url::from_str(last).ok().map(|url| page_number(&url)),
+            (None, None) => Some(self.number),
+            (None, Some(_)) => None,
+        }
+    }
+
+    /// Return a cursor for resuming the listing after this page
+    pub fn next_cursor(&self) -> Option<Cursor> {
+        self.links.next.clone().map(|url| Cursor { url })
+    }
+}
+
+/// A serializable position within a listing
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Cursor {
+    pub url: String,
+}
+
+fn page_number(url: &Url) -> u32 {
+    url.query_pairs()
+        .find(|(key, _)| key == "page")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(1)
+}
+
+fn with_page(url: &Url, number: u32) -> Url {
+    let params: Vec<(String, String)> = url
+        .query_pairs()
+        .into_owned()
+        .filter(|(key, _)| key != "page")
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(params)
+        .append_pair("page", &number.to_string());
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_numbers() {
+        let url = Url::from_str("https://api.github.com/user/repos?per_page=5").unwrap();
+        assert_eq!(page_number(&url), 1);
+        let url = with_page(&url, 3);
+        assert_eq!(
+            url.as_str(),
+            "https://api.github.com/user/repos?per_page=5&page=3"
+        );
+        assert_eq!(page_number(&url), 3);
+        assert_eq!(
+            with_page(&url, 4).as_str(),
+            "https://api.github.com/user/repos?per_page=5&page=4"
+        );
+    }
+
+    #[test]
+    fn last_page() {
+        let page = |next: Option<&str>, last: Option<&str>| Page::<()> {
+            items: vec![],
+            number: 2,
+            total_count: None,
+            links: PageLinks {
+                next: next.map(String::from),
+                last: last.map(String::from),
+                ..PageLinks::default()
+            },
+        };
+        let last = "https://api.github.com/user/repos?page=7";
+        assert_eq!(page(Some(last), Some(last)).last_page(), Some(7));
+        assert_eq!(page(None, None).last_page(), Some(2));
+        assert_eq!(page(Some(last), None).last_page(), None);
+        assert_eq!(
+            page(Some(last), None).next_cursor(),
+            Some(Cursor { url: last.into() })
+        );
+    }
+}
@@ -2,7 +2,7 @@
 use serde::Deserialize;
 
 use crate::users::User;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Paginator};
 
 /// A structure for interfacing with a pull commits
 pub struct PullCommits {
@@ -37,7 +37,7 @@ impl PullCommits {
     }
 
     /// provides a stream over all pages of pull commits
-    pub fn iter(&self) -> Stream<PullCommit> {
+    pub fn iter(&self) -> Paginator<PullCommit> {
         self.github.get_stream(&format!(
             "/repos/{}/{}/pulls/{}/commits",
             self.owner, self.repo, self.number
@@ -1,7 +1,10 @@
 //! Pull requests interface
 use std::collections::HashMap;
 use std::fmt;
+use std::time::Duration;
 
+use futures::prelude::*;
+use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use url::form_urlencoded;
 
@@ -9,15 +12,17 @@ use crate::comments::Comments;
 use crate::issues::{IssueAssignees, IssueLabels, Sort as IssueSort, State};
 use crate::labels::Label;
 use crate::pull_commits::PullCommits;
-use crate::review_comments::ReviewComments;
+use crate::review_comments::{RepoReviewComments, ReviewComments};
 use crate::review_requests::ReviewRequests;
+use crate::reviews::Reviews;
 use crate::users::User;
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Error, Future, Github, MediaType, Paginator, SortDirection};
 
 /// Sort directions for pull requests
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum Sort {
     /// Sort by time created
+    #[default]
     Created,
     /// Sort by last updated
     Updated,
@@ -39,12 +44,53 @@ impl fmt::Display for Sort {
     }
 }
 
-impl Default for Sort {
-    fn default() -> Sort {
-        Sort::Created
+/// Methods of merging a pull request
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMethod {
+    /// Add all commits to the base branch with a merge commit
+    #[default]
+    Merge,
+    /// Combine all commits into a single commit on the base branch
+    Squash,
+    /// Add all commits to the base branch individually, without a merge commit
+    Rebase,
+}
+
+impl fmt::Display for MergeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MergeMethod::Merge => "merge",
+            MergeMethod::Squash => "squash",
+            MergeMethod::Rebase => "rebase",
+        }
+        .fmt(f)
     }
 }
 
+/// The state of a pull request's mergeability, as computed by GitHub
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeableState {
+    /// mergeable and passing commit statuses
+    Clean,
+    /// conflicts with the base branch
+    Dirty,
+    /// blocked by branch protection
+    Blocked,
+    /// the head branch is out of date with the base branch
+    Behind,
+    /// mergeable with failing or pending commit statuses
+    Unstable,
+    /// mergeable with passing commit statuses and pre-receive hooks
+    HasHooks,
+    /// the pull request is a draft
+    Draft,
+    /// not yet computed, or a state not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
 /// A structure for accessing interfacing with a specific pull request
 pub struct PullRequest {
     github: Github,
@@ -115,6 +161,76 @@ impl PullRequest {
         self.github.patch::<Pull>(&self.path(""), json!(pr))
     }
 
+    /// Merge this pull request
+    pub fn merge(&self, options: &MergeOptions) -> Future<MergeResult> {
+        self.github.put(&self.path("/merge"), json!(options))
+    }
+
+    /// Return true if this pull request has been merged
+    pub fn is_merged(&self) -> Future<bool> {
+        Box::pin(
+            self.github
+                .get::<()>(&self.path("/merge"))
+                .map_ok(|_| true)
+                .or_else(|err| async move {
+                    match err {
+                        Error::Fault {
+                            code: StatusCode::NOT_FOUND,
+                            ..
+                        } => Ok(false),
+                        Error::Codec(_) => Ok(true),
+                        otherwise => Err(otherwise),
+                    }
+                }),
+        )
+    }
+
+    /// Update this pull request's head branch with the latest changes of
+    /// its base branch. When provided, the update fails unless
+    /// `expected_head_sha` is the sha of the head branch. The update is
+    /// performed asynchronously by GitHub
+    pub fn update_branch(&self, expected_head_sha: Option<&str>) -> Future<UpdateBranch> {
+        let mut options = HashMap::new();
+        if let Some(sha) = expected_head_sha {
+            options.insert("expected_head_sha", sha);
+        }
+        self.github
+            .put(&self.path("/update-branch"), json!(options))
+    }
+
+    /// Request this pull request's information once GitHub has computed its
+    /// mergeability. GitHub computes mergeability in the background after
+    /// changes to a pull request or its base branch, reporting `mergeable`
+    /// as null in the meantime. While it does, the pull request is
+    /// requested again after `interval`, up to `attempts` times in total,
+    /// after which the last response is returned as is
+    pub fn wait_for_mergeability(&self, interval: Duration, attempts: u32) -> Future<Pull> {
+        let github = self.github.clone();
+        let path = self.path("");
+        Box::pin(async move {
+            let mut attempt = 1;
+            loop {
+                let pull = github.get::<Pull>(&path).await?;
+                if pull.is_mergeability_known() || attempt >= attempts {
+                    return Ok(pull);
+                }
+                attempt += 1;
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Request this pull request's changes as a unified diff, which may be
+    /// parsed with `hubcaps::diff::Diff::parse`
+    pub fn diff(&self) -> Future<String> {
+        self.github.get_text(&self.path(""), MediaType::Diff)
+    }
+
+    /// Request this pull request's commits as a patch series
+    pub fn patch(&self) -> Future<String> {
+        self.github.get_text(&self.path(""), MediaType::Patch)
+    }
+
     /// Returns a vector of file diffs associated with this pull request
     #[deprecated(
         note = "This only returns the first page of results; up to 30 entries. Use `iter_files` instead"
@@ -124,7 +240,7 @@ impl PullRequest {
     }
 
     /// Returns a stream of file diffs associated with this pull request
-    pub fn iter_files(&self) -> Stream<FileDiff> {
+    pub fn iter_files(&self) -> Paginator<FileDiff> {
         self.github.get_stream(&self.path("/files"))
     }
 
@@ -157,6 +273,16 @@ impl PullRequest {
         )
     }
 
+    /// returns reviews interface
+    pub fn reviews(&self) -> Reviews {
+        Reviews::new(
+            self.github.clone(),
+            self.owner.clone(),
+            self.repo.clone(),
+            self.number,
+        )
+    }
+
     /// returns pull commits interface
     pub fn commits(&self) -> PullCommits {
         PullCommits::new(
@@ -203,6 +329,12 @@ impl PullRequests {
         )
     }
 
+    /// returns an interface for the review comments of all of the
+    /// repository's pull requests
+    pub fn review_comments(&self) -> RepoReviewComments {
+        RepoReviewComments::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
+    }
+
     /// Create a new pull request
     pub fn create(&self, pr: &PullOptions) -> Future<Pull> {
         self.github.post(&self.path(""), json!(pr))
@@ -218,7 +350,7 @@ impl PullRequests {
     }
 
     /// provides a stream over all pages of pull requests
-    pub fn iter(&self, options: &PullListOptions) -> Stream<Pull> {
+    pub fn iter(&self, options: &PullListOptions) -> Paginator<Pull> {
         let mut uri = vec![self.path("")];
         if let Some(query) = options.serialize() {
             uri.push(query);
@@ -266,6 +398,20 @@ pub struct Pull {
     pub deletions: Option<u64>,
     pub changed_files: Option<u64>,
     pub labels: Vec<Label>,
+    /// only provided when requesting a single pull request
+    pub merged: Option<bool>,
+    /// only provided when requesting a single pull request
+    pub mergeable_state: Option<MergeableState>,
+    /// only provided when requesting a single pull request
+    pub rebaseable: Option<bool>,
+}
+
+impl Pull {
+    /// Return true once GitHub has computed whether this pull request can
+    /// be merged
+    pub fn is_mergeability_known(&self) -> bool {
+        self.mergeable.is_some() && self.mergeable_state != Some(MergeableState::Unknown)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -373,6 +519,88 @@ impl PullOptions {
     }
 }
 
+#[derive(Default)]
+pub struct MergeOptionsBuilder(MergeOptions);
+
+impl MergeOptionsBuilder {
+    /// set the title of the merge commit
+    pub fn commit_title<T>(&mut self, title: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0.commit_title = Some(title.into());
+        self
+    }
+
+    /// set the message of the merge commit
+    pub fn commit_message<M>(&mut self, message: M) -> &mut Self
+    where
+        M: Into<String>,
+    {
+        self.0.commit_message = Some(message.into());
+        self
+    }
+
+    /// only merge if the pull request's head is at this sha
+    pub fn sha<S>(&mut self, sha: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.sha = Some(sha.into());
+        self
+    }
+
+    /// set how the pull request is merged
+    pub fn merge_method(&mut self, method: MergeMethod) -> &mut Self {
+        self.0.merge_method = Some(method);
+        self
+    }
+
+    /// create a new set of merge options
+    pub fn build(&self) -> MergeOptions {
+        MergeOptions {
+            commit_title: self.0.commit_title.clone(),
+            commit_message: self.0.commit_message.clone(),
+            sha: self.0.sha.clone(),
+            merge_method: self.0.merge_method,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MergeOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merge_method: Option<MergeMethod>,
+}
+
+impl MergeOptions {
+    pub fn builder() -> MergeOptionsBuilder {
+        MergeOptionsBuilder::default()
+    }
+}
+
+/// The outcome of merging a pull request
+#[derive(Debug, Deserialize)]
+pub struct MergeResult {
+    /// the sha of the merge commit
+    pub sha: String,
+    pub merged: bool,
+    pub message: String,
+}
+
+/// The acknowledgement of a request to update a pull request's branch
+#[derive(Debug, Deserialize)]
+pub struct UpdateBranch {
+    pub message: String,
+    pub url: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FileDiff {
     /// sha from GitHub may be null when file mode changed without contents changing
@@ -489,6 +717,37 @@ mod tests {
         test_encoding(tests)
     }
 
+    #[test]
+    fn merge_options() {
+        let tests = vec![
+            (MergeOptions::builder().build(), r#"{}"#),
+            (
+                MergeOptions::builder()
+                    .commit_title("Ship it (#1)")
+                    .sha("6dcb09b5b57875f334f61aebed695e2e4193db5e")
+                    .merge_method(MergeMethod::Squash)
+                    .build(),
+                r#"{"commit_title":"Ship it (#1)","sha":"6dcb09b5b57875f334f61aebed695e2e4193db5e","merge_method":"squash"}"#,
+            ),
+        ];
+        test_encoding(tests)
+    }
+
+    #[test]
+    fn mergeable_states() {
+        for (json, state) in &[
+            (r#""clean""#, MergeableState::Clean),
+            (r#""has_hooks""#, MergeableState::HasHooks),
+            (r#""unknown""#, MergeableState::Unknown),
+            (r#""something_new""#, MergeableState::Unknown),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<MergeableState>(json).unwrap(),
+                *state
+            );
+        }
+    }
+
     #[test]
     fn default_sort() {
         let default: Sort = Default::default();
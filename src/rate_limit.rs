@@ -1,7 +1,38 @@
 //! Rate Limit interface
-use crate::{Future, Github};
+//!
+//! Besides querying GitHub for the current rate limit status, every `Github`
+//! client tracks the `x-ratelimit-*` headers of the responses it receives.
+//! These figures are shared between a client and all of its clones and are
+//! available via `Github::rate_limit_status`. Clients may optionally be
+//! configured with a `Throttle` which delays outgoing requests so that they
+//! stay within the remaining budget.
+//!
+//! ```no_run
+//! use hubcaps::rate_limit::{Resource, Throttle};
+//! use hubcaps::Github;
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let mut github = Github::new("user-agent-name", None)?;
+//! github.set_throttle(Throttle::Spread);
+//! if let Some(status) = github.rate_limit_status(Resource::Core) {
+//!     println!("{} of {} requests remaining", status.remaining, status.limit);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::prelude::*;
+use http::header::HeaderMap;
+use log::debug;
 use serde::Deserialize;
 
+use crate::{Future, Github, X_RATELIMIT_LIMIT, X_RATELIMIT_REMAINING, X_RATELIMIT_RESET};
+
+const X_RATELIMIT_RESOURCE: &str = "x-ratelimit-resource";
+
 pub struct RateLimit {
     github: Github,
 }
@@ -13,8 +44,164 @@ impl RateLimit {
     }
 
     /// https://developer.github.com/v3/rate_limit/#get-your-current-rate-limit-status
+    ///
+    /// The returned figures also refresh those tracked by the client
     pub fn get(&self) -> Future<RateLimitStatus> {
-        self.github.get("/rate_limit")
+        let governor = self.github.governor.clone();
+        Box::pin(
+            self.github
+                .get::<RateLimitStatus>("/rate_limit")
+                .map_ok(move |status| {
+                    governor.update(Resource::Core, status.resources.core);
+                    governor.update(Resource::Search, status.resources.search);
+                    governor.update(Resource::Graphql, status.resources.graphql);
+                    status
+                }),
+        )
+    }
+}
+
+/// A rate limit bucket. GitHub budgets requests to each separately
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Core,
+    Search,
+    Graphql,
+}
+
+impl Resource {
+    /// The bucket a request for the given path is expected to draw from.
+    /// `base` is the path of the api's host, like `/api/v3` for GitHub
+    /// Enterprise, whose graphql endpoint sits beside it at `/api/graphql`
+    pub(crate) fn for_path(base: &str, path: &str) -> Self {
+        let base = base.trim_end_matches('/');
+        let enterprise_graphql = base
+            .strip_suffix("/v3")
+            .is_some_and(|api| path.strip_prefix(api) == Some("/graphql"));
+        let path = path.strip_prefix(base).unwrap_or(path);
+        if path.starts_with("/search/") {
+            Resource::Search
+        } else if path == "/graphql" || enterprise_graphql {
+            Resource::Graphql
+        } else {
+            Resource::Core
+        }
+    }
+
+    fn from_header(value: &str) -> Option<Self> {
+        match value {
+            "core" => Some(Resource::Core),
+            "search" => Some(Resource::Search),
+            "graphql" => Some(Resource::Graphql),
+            _ => None,
+        }
+    }
+}
+
+/// How a client paces outgoing requests against the tracked rate limits
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Throttle {
+    /// Send requests immediately. This is the default
+    #[default]
+    Off,
+    /// Hold requests once a bucket's budget is exhausted until it resets
+    Queue,
+    /// Space requests evenly so that a bucket's remaining budget lasts
+    /// until it resets
+    Spread,
+}
+
+/// Rate limit figures shared by a `Github` client and its clones
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Governor {
+    buckets: Arc<Mutex<HashMap<Resource, Bucket>>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    status: RateLimitResourceStatus,
+    next_slot: Option<Instant>,
+}
+
+impl Governor {
+    pub(crate) fn status(&self, resource: Resource) -> Option<RateLimitResourceStatus> {
+        self.buckets
+            .lock()
+            .unwrap()
+            .get(&resource)
+            .map(|bucket| bucket.status)
+    }
+
+    pub(crate) fn update(&self, resource: Resource, status: RateLimitResourceStatus) {
+        let mut buckets = self.buckets.lock().unwrap();
+        match buckets.get_mut(&resource) {
+            // responses may arrive out of order, so within the same window
+            // the lowest remaining count is the most recent
+            Some(bucket) if bucket.status.reset == status.reset => {
+                bucket.status.limit = status.limit;
+                bucket.status.remaining = bucket.status.remaining.min(status.remaining);
+            }
+            Some(bucket) => {
+                bucket.status = status;
+                bucket.next_slot = None;
+            }
+            None => {
+                buckets.insert(
+                    resource,
+                    Bucket {
+                        status,
+                        next_slot: None,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Record the rate limit headers of a response to a request expected to
+    /// draw from `resource`
+    pub(crate) fn observe(&self, resource: Resource, headers: &HeaderMap) {
+        let resource = headers
+            .get(X_RATELIMIT_RESOURCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Resource::from_header)
+            .unwrap_or(resource);
+        if let Some(status) = RateLimitResourceStatus::from_headers(headers) {
+            self.update(resource, status)
+        }
+    }
+
+    /// Reserve budget for a request drawing from `resource`, returning how
+    /// long it should be delayed to honor the `throttle`
+    pub(crate) fn acquire(&self, resource: Resource, throttle: Throttle) -> Option<Duration> {
+        if throttle == Throttle::Off {
+            return None;
+        }
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.get_mut(&resource)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let until_reset = Duration::from_secs(u64::from(bucket.status.reset).saturating_sub(now));
+        if until_reset == Duration::default() {
+            // the window has passed, so the tracked figures are stale
+            return None;
+        }
+        if bucket.status.remaining == 0 {
+            debug!("{:?} rate limit exhausted for {:?}", resource, until_reset);
+            return Some(until_reset);
+        }
+        let delay = match throttle {
+            Throttle::Spread => {
+                let now = Instant::now();
+                let slot = bucket.next_slot.map_or(now, |next| next.max(now));
+                bucket.next_slot = Some(slot + until_reset / bucket.status.remaining);
+                Some(slot - now).filter(|delay| *delay > Duration::default())
+            }
+            _ => None,
+        };
+        bucket.status.remaining -= 1;
+        delay
     }
 }
 
@@ -32,9 +219,148 @@ pub struct RateLimitResourcesStatus {
     pub graphql: RateLimitResourceStatus,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
 pub struct RateLimitResourceStatus {
     pub limit: u32,
     pub remaining: u32,
     pub reset: u32, // ideally something like std::time::Duration
 }
+
+impl RateLimitResourceStatus {
+    /// Read the `x-ratelimit-*` headers of a response
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u32>().ok())
+        };
+        Some(RateLimitResourceStatus {
+            limit: header(X_RATELIMIT_LIMIT)?,
+            remaining: header(X_RATELIMIT_REMAINING)?,
+            reset: header(X_RATELIMIT_RESET)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn now() -> u32 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32
+    }
+
+    fn status(remaining: u32, reset: u32) -> RateLimitResourceStatus {
+        RateLimitResourceStatus {
+            limit: 5000,
+            remaining,
+            reset,
+        }
+    }
+
+    #[test]
+    fn resource_for_path() {
+        assert_eq!(Resource::for_path("/", "/repos/o/r"), Resource::Core);
+        assert_eq!(Resource::for_path("/", "/search/issues"), Resource::Search);
+        assert_eq!(Resource::for_path("/", "/graphql"), Resource::Graphql);
+        // repositories, users and files named graphql
+        for path in &[
+            "/repos/facebook/graphql",
+            "/users/graphql",
+            "/repos/o/r/contents/x/graphql",
+        ] {
+            assert_eq!(Resource::for_path("/", path), Resource::Core);
+        }
+        // github enterprise
+        assert_eq!(
+            Resource::for_path("/api/v3", "/api/v3/repos/o/r"),
+            Resource::Core
+        );
+        assert_eq!(
+            Resource::for_path("/api/v3/", "/api/v3/search/issues"),
+            Resource::Search
+        );
+        assert_eq!(
+            Resource::for_path("/api/v3", "/api/graphql"),
+            Resource::Graphql
+        );
+        assert_eq!(
+            Resource::for_path("/api/v3", "/api/v3/repos/facebook/graphql"),
+            Resource::Core
+        );
+    }
+
+    #[test]
+    fn observes_headers() {
+        let governor = Governor::default();
+        let mut headers = HeaderMap::new();
+        headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from_static("30"));
+        headers.insert(X_RATELIMIT_REMAINING, HeaderValue::from_static("12"));
+        headers.insert(X_RATELIMIT_RESET, HeaderValue::from_static("5678"));
+        headers.insert(X_RATELIMIT_RESOURCE, HeaderValue::from_static("search"));
+        governor.observe(Resource::Core, &headers);
+        assert_eq!(governor.status(Resource::Core), None);
+        assert_eq!(
+            governor.status(Resource::Search),
+            Some(RateLimitResourceStatus {
+                limit: 30,
+                remaining: 12,
+                reset: 5678
+            })
+        );
+        // stale responses within the same window don't raise the count
+        headers.insert(X_RATELIMIT_REMAINING, HeaderValue::from_static("14"));
+        governor.observe(Resource::Search, &headers);
+        assert_eq!(governor.status(Resource::Search).unwrap().remaining, 12);
+        // but a new window does
+        headers.insert(X_RATELIMIT_RESET, HeaderValue::from_static("9999"));
+        governor.observe(Resource::Search, &headers);
+        assert_eq!(governor.status(Resource::Search).unwrap().remaining, 14);
+    }
+
+    #[test]
+    fn shared_across_clones() {
+        let governor = Governor::default();
+        governor
+            .clone()
+            .update(Resource::Core, status(10, now() + 60));
+        assert_eq!(governor.status(Resource::Core).unwrap().remaining, 10);
+    }
+
+    #[test]
+    fn off_never_delays() {
+        let governor = Governor::default();
+        governor.update(Resource::Core, status(0, now() + 60));
+        assert_eq!(governor.acquire(Resource::Core, Throttle::Off), None);
+    }
+
+    #[test]
+    fn queue_holds_requests_until_reset() {
+        let governor = Governor::default();
+        governor.update(Resource::Core, status(1, now() + 60));
+        assert_eq!(governor.acquire(Resource::Core, Throttle::Queue), None);
+        assert_eq!(governor.status(Resource::Core).unwrap().remaining, 0);
+        let delay = governor.acquire(Resource::Core, Throttle::Queue).unwrap();
+        assert!(delay > Duration::from_secs(58) && delay <= Duration::from_secs(60));
+        // untracked and stale buckets are not delayed
+        assert_eq!(governor.acquire(Resource::Search, Throttle::Queue), None);
+        governor.update(Resource::Search, status(0, now() - 1));
+        assert_eq!(governor.acquire(Resource::Search, Throttle::Queue), None);
+    }
+
+    #[test]
+    fn spread_spaces_requests() {
+        let governor = Governor::default();
+        governor.update(Resource::Core, status(10, now() + 100));
+        assert_eq!(governor.acquire(Resource::Core, Throttle::Spread), None);
+        let delay = governor.acquire(Resource::Core, Throttle::Spread).unwrap();
+        assert!(delay > Duration::from_secs(8) && delay <= Duration::from_secs(10));
+        let delay = governor.acquire(Resource::Core, Throttle::Spread).unwrap();
+        assert!(delay > Duration::from_secs(19) && delay <= Duration::from_secs(22));
+    }
+}
@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator};
 
 /// Provides access to assets for a release.
 /// See the [github docs](https://developer.github.com/v3/repos/releases/)
@@ -64,6 +64,11 @@ impl Assets {
     pub fn list(&self) -> Future<Vec<Asset>> {
         self.github.get(&self.path(""))
     }
+
+    /// provides a stream over all pages of this release's assets
+    pub fn iter(&self) -> Paginator<Asset> {
+        self.github.get_stream(&self.path(""))
+    }
 }
 
 pub struct ReleaseRef {
@@ -174,6 +179,11 @@ impl Releases {
         self.github.get(&self.path(""))
     }
 
+    /// provides a stream over all pages of this repo's releases
+    pub fn iter(&self) -> Paginator<Release> {
+        self.github.get_stream(&self.path(""))
+    }
+
     /// Return the latest full release. Draft releases and prereleases are not returned.
     ///
     /// See the [github docs](https://developer.github.com/v3/repos/releases/#get-the-latest-release)
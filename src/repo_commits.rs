@@ -3,7 +3,7 @@
 use serde::Deserialize;
 
 use crate::users::User;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, MediaType, Paginator};
 
 /// A structure for interfacing with a repository commits
 pub struct RepoCommits {
@@ -38,7 +38,7 @@ impl RepoCommits {
 
     /// provides a stream over all pages of pull commits
     /// !!! make optional parameters
-    pub fn iter(&self) -> Stream<RepoCommit> {
+    pub fn iter(&self) -> Paginator<RepoCommit> {
         self.github
             .get_stream(&format!("/repos/{}/{}/commits", self.owner, self.repo))
     }
@@ -48,6 +48,19 @@ impl RepoCommits {
         let uri = format!("/repos/{}/{}/commits/{}", self.owner, self.repo, commit_ref);
         self.github.get::<RepoCommit>(&uri)
     }
+
+    /// get a repo commit's changes as a unified diff, which may be parsed
+    /// with `hubcaps::diff::Diff::parse`
+    pub fn diff(&self, commit_ref: &str) -> Future<String> {
+        let uri = format!("/repos/{}/{}/commits/{}", self.owner, self.repo, commit_ref);
+        self.github.get_text(&uri, MediaType::Diff)
+    }
+
+    /// get a repo commit as a patch
+    pub fn patch(&self, commit_ref: &str) -> Future<String> {
+        let uri = format!("/repos/{}/{}/commits/{}", self.owner, self.repo, commit_ref);
+        self.github.get_text(&uri, MediaType::Patch)
+    }
 }
 
 // representations
@@ -63,8 +76,10 @@ pub struct RepoCommit {
     pub html_url: String,
     pub comments_url: String,
     pub commit: CommitDetails,
-    pub author: User,
-    pub committer: User,
+    /// null when the commit's author email isn't associated with an account
+    pub author: Option<User>,
+    /// null when the commit's committer email isn't associated with an account
+    pub committer: Option<User>,
     pub parents: Vec<CommitRef>,
 }
 
@@ -8,22 +8,24 @@ use url::{form_urlencoded, Url};
 use crate::branches::Branches;
 use crate::checks::CheckRuns;
 use crate::collaborators::Collaborators;
+use crate::compare::Compare;
 use crate::content::Content;
 use crate::deployments::Deployments;
 use crate::git::Git;
-use crate::hooks::Hooks;
+use crate::hooks::RepoHooks;
 use crate::issues::{IssueRef, Issues};
 use crate::keys::Keys;
 use crate::labels::Labels;
 use crate::pulls::PullRequests;
 use crate::releases::Releases;
 use crate::repo_commits::RepoCommits;
+use crate::rulesets::RepoRulesets;
 use crate::statuses::Statuses;
 use crate::teams::RepoTeams;
 use crate::traffic::Traffic;
 use crate::users::Contributors;
 use crate::users::User;
-use crate::{Future, Github, SortDirection, Stream};
+use crate::{Future, Github, Paginator, SortDirection};
 
 /// describes repository visibilities
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -165,7 +167,7 @@ impl Repositories {
 
     /// provides a stream over all pages of the authenticated users repositories
     /// https://developer.github.com/v3/repos/#list-your-repositories
-    pub fn iter(&self, options: &RepoListOptions) -> Stream<Repo> {
+    pub fn iter(&self, options: &RepoListOptions) -> Paginator<Repo> {
         let mut uri = vec![self.path("")];
         if let Some(query) = options.serialize() {
             uri.push(query);
@@ -216,7 +218,7 @@ impl Forks {
 
     /// provides a stream over all pages of the forks
     /// https://developer.github.com/v3/repos/forks/#list-forks
-    pub fn iter(&self, options: &ForkListOptions) -> Stream<Repo> {
+    pub fn iter(&self, options: &ForkListOptions) -> Paginator<Repo> {
         let mut uri = vec![self.path()];
         if let Some(query) = options.serialize() {
             uri.push(query);
@@ -258,7 +260,7 @@ impl OrgRepositories {
 
     /// provides a stream over all pages of an orgs's repositories
     /// https://developer.github.com/v3/repos/#list-organization-repositories
-    pub fn iter(&self, options: &OrgRepoListOptions) -> Stream<Repo> {
+    pub fn iter(&self, options: &OrgRepoListOptions) -> Paginator<Repo> {
         let mut uri = vec![self.path("")];
         if let Some(query) = options.serialize() {
             uri.push(query);
@@ -307,7 +309,7 @@ impl UserRepositories {
 
     /// provides a stream over all pages of a user's repositories
     /// https://developer.github.com/v3/repos/#list-your-repositories
-    pub fn iter(&self, options: &UserRepoListOptions) -> Stream<Repo> {
+    pub fn iter(&self, options: &UserRepoListOptions) -> Paginator<Repo> {
         self.github.get_stream(&self.uri(options))
     }
 }
@@ -346,7 +348,7 @@ impl OrganizationRepositories {
 
     /// Provides a stream over all pages of an organization's repositories
     /// https://developer.github.com/v3/repos/#list-organization-repositories
-    pub fn iter(&self, options: &OrganizationRepoListOptions) -> Stream<Repo> {
+    pub fn iter(&self, options: &OrganizationRepoListOptions) -> Paginator<Repo> {
         let mut uri = vec![self.path("")];
         if let Some(query) = options.serialize() {
             uri.push(query);
@@ -407,6 +409,21 @@ impl Repository {
         RepoCommits::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
     }
 
+    /// get a reference to the comparison of two commits, branches or tags
+    pub fn compare<B, H>(&self, base: B, head: H) -> Compare
+    where
+        B: Into<String>,
+        H: Into<String>,
+    {
+        Compare::new(
+            self.github.clone(),
+            self.owner.as_str(),
+            self.repo.as_str(),
+            base,
+            head,
+        )
+    }
+
     /// get a reference to content operations
     pub fn content(&self) -> Content {
         Content::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
@@ -418,8 +435,13 @@ impl Repository {
     }
 
     /// get a reference to repo hook operations
-    pub fn hooks(&self) -> Hooks {
-        Hooks::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
+    pub fn hooks(&self) -> RepoHooks {
+        RepoHooks::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
+    }
+
+    /// get a reference to repo ruleset operations
+    pub fn rulesets(&self) -> RepoRulesets {
+        RepoRulesets::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
     }
 
     /// get a reference to [deployments](https://developer.github.com/v3/repos/deployments/)
@@ -577,10 +599,15 @@ pub struct Repo {
     pub default_branch: String,
     pub open_issues_count: u64,
     pub has_issues: bool,
+    pub has_projects: Option<bool>,
     pub has_wiki: bool,
     pub has_pages: bool,
     pub has_downloads: bool,
     pub archived: bool,
+    /// merge settings are only provided to users with admin access
+    pub allow_squash_merge: Option<bool>,
+    pub allow_merge_commit: Option<bool>,
+    pub allow_rebase_merge: Option<bool>,
     pub pushed_at: String,
     pub created_at: String,
     pub updated_at: String, // permissions: Permissions
@@ -879,7 +906,7 @@ impl ContributorStatistics {
             self.owner, self.repo
         ))
     }
-    pub fn iter(&self) -> Stream<ContributorStatistic> {
+    pub fn iter(&self) -> Paginator<ContributorStatistic> {
         self.github.get_stream(&format!(
             "/repos/{}/{}/stats/contributors",
             self.owner, self.repo
@@ -0,0 +1,158 @@
+//! Response metadata
+//!
+//! Operations return only their deserialized results. When the details of the
+//! http response behind a result are needed, such as GitHub's request id for a
+//! support ticket or an etag for conditional requests, wrap the operation
+//! with `Github::with_response`. Failed requests report their request id in
+//! `Error::Fault` instead.
+//!
+//! ```no_run
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let github = Github::new("user-agent-name", None)?;
+//! let response = github
+//!     .with_response(|github| github.repo("softprops", "hubcaps").get())
+//!     .await?;
+//! println!(
+//!     "fetched {} (request id {:?})",
+//!     response.body.full_name, response.request_id
+//! );
+//! # Ok(())
+//! # }
+//! ```
+use http::header::{HeaderMap, ETAG};
+use http::StatusCode;
+use hyperx::header::{Link, RelationType};
+
+use crate::rate_limit::RateLimitResourceStatus;
+use crate::X_GITHUB_REQUEST_ID;
+
+/// A result along with the metadata of the http response it was read from
+///
+/// For operations which issue several requests, such as those following
+/// pagination links, the metadata describes the last response received.
+#[derive(Clone, Debug)]
+pub struct Response<T> {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// the `x-github-request-id` GitHub assigned the request
+    pub request_id: Option<String>,
+    /// the rate limit figures reported for the request's resource
+    pub rate_limit: Option<RateLimitResourceStatus>,
+    pub etag: Option<String>,
+    pub links: PageLinks,
+    pub body: T,
+}
+
+impl Response<()> {
+    pub(crate) fn new(status: StatusCode, headers: HeaderMap, link: Option<&Link>) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        Response {
+            status,
+            request_id: header(X_GITHUB_REQUEST_ID),
+            rate_limit: RateLimitResourceStatus::from_headers(&headers),
+            etag: header(ETAG.as_str()),
+            links: link.map(PageLinks::from_link).unwrap_or_default(),
+            headers,
+            body: (),
+        }
+    }
+}
+
+impl<T> Response<T> {
+    /// Transform the body, retaining the metadata
+    pub fn map<U, F>(self, f: F) -> Response<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Response {
+            status: self.status,
+            headers: self.headers,
+            request_id: self.request_id,
+            rate_limit: self.rate_limit,
+            etag: self.etag,
+            links: self.links,
+            body: f(self.body),
+        }
+    }
+
+    pub fn into_body(self) -> T {
+        self.body
+    }
+}
+
+/// The pagination urls advertised by a response's `Link` header
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PageLinks {
+    pub first: Option<String>,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    pub last: Option<String>,
+}
+
+impl PageLinks {
+    pub(crate) fn from_link(link: &Link) -> Self {
+        let find = |relation: RelationType| {
+            link.values().iter().find_map(|value| {
+                value.rel().and_then(|rels| {
+                    if rels.iter().any(|rel| rel == &relation) {
+                        Some(value.link().to_owned())
+                    } else {
+                        None
+                    }
+                })
+            })
+        };
+        PageLinks {
+            first: find(RelationType::First),
+            prev: find(RelationType::Prev),
+            next: find(RelationType::Next),
+            last: find(RelationType::Last),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    #[test]
+    fn reads_metadata_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(X_GITHUB_REQUEST_ID, HeaderValue::from_static("AB:CD"));
+        headers.insert(ETAG, HeaderValue::from_static("W/\"abc\""));
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("5000"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("4999"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1372700873"));
+        let link: Link = r#"<https://api.github.com/user/repos?page=3>; rel="next", <https://api.github.com/user/repos?page=50>; rel="last""#
+            .parse()
+            .unwrap();
+        let response = Response::new(StatusCode::OK, headers, Some(&link)).map(|_| 42);
+        assert_eq!(response.request_id, Some("AB:CD".into()));
+        assert_eq!(response.etag, Some("W/\"abc\"".into()));
+        assert_eq!(
+            response.rate_limit,
+            Some(RateLimitResourceStatus {
+                limit: 5000,
+                remaining: 4999,
+                reset: 1_372_700_873,
+            })
+        );
+        assert_eq!(
+            response.links,
+            PageLinks {
+                next: Some("https://api.github.com/user/repos?page=3".into()),
+                last: Some("https://api.github.com/user/repos?page=50".into()),
+                ..PageLinks::default()
+            }
+        );
+        assert_eq!(response.into_body(), 42);
+    }
+}
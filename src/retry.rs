@@ -0,0 +1,513 @@
+//! Automatic retries for rate limited and transiently failing requests
+//!
+//! A `RetryPolicy` configured via `Github::set_retry_policy` applies to every
+//! request issued by a client, including those fetching subsequent pages of
+//! an `iter(..)` stream. By default no requests are retried.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use hubcaps::{Github, RetryPolicy};
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! let mut github = Github::new("user-agent-name", None)?;
+//! github.set_retry_policy(
+//!     RetryPolicy::builder()
+//!         .max_attempts(5)
+//!         .max_rate_limit_wait(Duration::from_secs(10 * 60))
+//!         .build(),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::{HeaderMap, RETRY_AFTER};
+use http::{Method, Response, StatusCode};
+
+use crate::errors::ClientError;
+use crate::{Error, Result, X_RATELIMIT_REMAINING, X_RATELIMIT_RESET};
+
+/// GitHub recommends waiting at least a minute after hitting a secondary
+/// rate limit which does not specify a `Retry-After` header
+const SECONDARY_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Governs whether and when failed requests are reattempted
+///
+/// Requests are retried when
+///
+/// * the primary rate limit is exhausted, after waiting for its reset
+/// * a secondary (abuse) rate limit is hit, after waiting for the
+///   `Retry-After` duration or at least a minute
+/// * a `500`, `502`, `503` or `504` response or a connection or timeout error
+///   is encountered, after an exponential backoff with jitter. Only
+///   idempotent requests are retried in these cases unless
+///   `retry_non_idempotent` is enabled
+///
+/// Rate limit waits longer than `max_rate_limit_wait` are not attempted and
+/// the error is returned to the caller instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_rate_limit_wait: Duration,
+    retry_non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    /// A policy which never retries. This is the policy of `Github` clients
+    /// until one is set
+    pub fn none() -> Self {
+        RetryPolicy::builder().max_attempts(1).build()
+    }
+
+    pub fn builder() -> RetryPolicyBuilder {
+        RetryPolicyBuilder::default()
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Return the duration to wait before reattempting a request, if it should
+    /// be reattempted, given the number of attempts made so far and the
+    /// outcome of the latest
+    pub(crate) fn delay(
+        &self,
+        attempt: u32,
+        method: &Method,
+        outcome: &Result<Response<Vec<u8>>>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let idempotent = self.retry_non_idempotent || is_idempotent(method);
+        match outcome {
+            Err(Error::Reqwest(err)) if idempotent && (err.is_timeout() || err.is_connect()) => {
+                Some(self.backoff(attempt))
+            }
+            Err(_) => None,
+            Ok(response) => match response.status() {
+                StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                    let wait = rate_limit_wait(response.headers())
+                        .or_else(|| {
+                            if is_secondary_rate_limit(response) {
+                                Some(self.backoff(attempt).max(SECONDARY_RATE_LIMIT_WAIT))
+                            } else {
+                                None
+                            }
+                        })
+                        .or_else(|| {
+                            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                                Some(self.backoff(attempt))
+                            } else {
+                                None
+                            }
+                        })?;
+                    if wait <= self.max_rate_limit_wait {
+                        Some(wait)
+                    } else {
+                        None
+                    }
+                }
+                StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+                    if idempotent =>
+                {
+                    Some(self.backoff(attempt))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Exponential backoff with "equal jitter": half of the exponential delay
+    /// plus a random portion of the other half
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        let half = exponential / 2;
+        half + jitter(half)
+    }
+}
+
+/// a mutable retry policy builder
+pub struct RetryPolicyBuilder(RetryPolicy);
+
+impl Default for RetryPolicyBuilder {
+    fn default() -> Self {
+        RetryPolicyBuilder(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_rate_limit_wait: Duration::from_secs(60),
+            retry_non_idempotent: false,
+        })
+    }
+}
+
+impl RetryPolicyBuilder {
+    /// total number of attempts made for a request, including the first.
+    /// defaults to 3
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.0.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// delay before the first reattempt of a failed request. subsequent
+    /// delays double up to `max_backoff`. defaults to 500 milliseconds
+    pub fn initial_backoff(&mut self, initial_backoff: Duration) -> &mut Self {
+        self.0.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// upper bound on backoff delays. defaults to 30 seconds
+    pub fn max_backoff(&mut self, max_backoff: Duration) -> &mut Self {
+        self.0.max_backoff = max_backoff;
+        self
+    }
+
+    /// the longest a request will wait for a rate limit to reset before
+    /// reattempting. defaults to 60 seconds
+    pub fn max_rate_limit_wait(&mut self, max_rate_limit_wait: Duration) -> &mut Self {
+        self.0.max_rate_limit_wait = max_rate_limit_wait;
+        self
+    }
+
+    /// also retry requests which are not idempotent, such as `POST`s,
+    /// after server errors. defaults to false
+    pub fn retry_non_idempotent(&mut self, retry_non_idempotent: bool) -> &mut Self {
+        self.0.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    pub fn build(&self) -> RetryPolicy {
+        self.0.clone()
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+/// How long GitHub asked us to wait, either explicitly via `Retry-After` or
+/// implicitly via an exhausted rate limit's reset time
+fn rate_limit_wait(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+    if let Some(secs) = header(RETRY_AFTER.as_str()) {
+        return Some(Duration::from_secs(secs));
+    }
+    match (header(X_RATELIMIT_REMAINING), header(X_RATELIMIT_RESET)) {
+        (Some(0), Some(reset)) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            // allow a second of slack for clock skew
+            Some(Duration::from_secs(reset.saturating_sub(now) + 1))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a response reports a secondary rate limit without saying how long
+/// to wait. GitHub documents these as leaving the primary rate limit's
+/// remaining requests alone and carrying a message about secondary rate limits
+fn is_secondary_rate_limit(response: &Response<Vec<u8>>) -> bool {
+    let exhausted = response
+        .headers()
+        .get(X_RATELIMIT_REMAINING)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value == "0");
+    !exhausted
+        && serde_json::from_slice::<ClientError>(response.body())
+            .is_ok_and(|error| error.message.contains("secondary rate limit"))
+}
+
+fn jitter(max: Duration) -> Duration {
+    let millis = max.as_millis() as u64;
+    if millis == 0 {
+        return Duration::default();
+    }
+    // RandomState is seeded randomly per instance, which suffices for jitter
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(random % millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Transport;
+    use crate::{Future, Github};
+    use futures::prelude::*;
+    use http::{HeaderValue, Request};
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// Serves a scripted sequence of responses, recording the requested uris
+    #[derive(Clone, Debug, Default)]
+    struct Scripted {
+        responses: Arc<Mutex<VecDeque<Response<Vec<u8>>>>>,
+        requested: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Scripted {
+        fn respond(&self, status: StatusCode, headers: &[(&'static str, &str)], body: &str) {
+            self.responses
+                .lock()
+                .unwrap()
+                .push_back(response(status, headers, body).unwrap());
+        }
+
+        fn requested(&self) -> Vec<String> {
+            self.requested.lock().unwrap().clone()
+        }
+    }
+
+    impl Transport for Scripted {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Vec<u8>>> {
+            self.requested
+                .lock()
+                .unwrap()
+                .push(request.uri().to_string());
+            let response = self
+                .responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected request");
+            Box::pin(future::ok(response))
+        }
+    }
+
+    #[cfg(not(feature = "httpcache"))]
+    fn github(transport: Scripted) -> Github {
+        Github::custom("https://api.github.com", "test", None, transport)
+    }
+
+    #[cfg(feature = "httpcache")]
+    fn github(transport: Scripted) -> Github {
+        Github::custom(
+            "https://api.github.com",
+            "test",
+            None,
+            transport,
+            <dyn crate::HttpCache>::noop(),
+        )
+    }
+
+    fn quick() -> RetryPolicy {
+        RetryPolicy::builder()
+            .initial_backoff(Duration::from_millis(2))
+            .build()
+    }
+
+    fn response(
+        status: StatusCode,
+        headers: &[(&'static str, &str)],
+        body: &str,
+    ) -> Result<Response<Vec<u8>>> {
+        let mut response = Response::new(body.as_bytes().to_vec());
+        *response.status_mut() = status;
+        for (name, value) in headers {
+            response
+                .headers_mut()
+                .insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        Ok(response)
+    }
+
+    #[test]
+    fn none_never_retries() {
+        let policy = RetryPolicy::none();
+        assert_eq!(
+            policy.delay(1, &Method::GET, &response(StatusCode::BAD_GATEWAY, &[], "")),
+            None
+        );
+    }
+
+    #[test]
+    fn retries_server_errors_for_idempotent_requests() {
+        let policy = RetryPolicy::builder()
+            .initial_backoff(Duration::from_millis(100))
+            .build();
+        let outcome = response(StatusCode::SERVICE_UNAVAILABLE, &[], "");
+        let delay = policy.delay(1, &Method::GET, &outcome).unwrap();
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        let delay = policy.delay(2, &Method::GET, &outcome).unwrap();
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        assert_eq!(policy.delay(3, &Method::GET, &outcome), None);
+        assert_eq!(policy.delay(1, &Method::POST, &outcome), None);
+        assert_eq!(
+            policy.delay(1, &Method::GET, &response(StatusCode::NOT_FOUND, &[], "")),
+            None
+        );
+    }
+
+    #[test]
+    fn retries_non_idempotent_requests_when_enabled() {
+        let policy = RetryPolicy::builder().retry_non_idempotent(true).build();
+        assert!(policy
+            .delay(
+                1,
+                &Method::POST,
+                &response(StatusCode::BAD_GATEWAY, &[], "")
+            )
+            .is_some());
+    }
+
+    #[test]
+    fn honors_retry_after() {
+        let policy = RetryPolicy::builder().build();
+        let outcome = response(
+            StatusCode::FORBIDDEN,
+            &[("retry-after", "30")],
+            r#"{"message":"You have exceeded a secondary rate limit"}"#,
+        );
+        assert_eq!(
+            policy.delay(1, &Method::POST, &outcome),
+            Some(Duration::from_secs(30))
+        );
+        let outcome = response(StatusCode::TOO_MANY_REQUESTS, &[("retry-after", "120")], "");
+        assert_eq!(policy.delay(1, &Method::GET, &outcome), None);
+    }
+
+    #[test]
+    fn waits_for_secondary_rate_limits() {
+        let policy = RetryPolicy::builder().build();
+        let outcome = response(
+            StatusCode::FORBIDDEN,
+            &[("x-ratelimit-remaining", "4000")],
+            r#"{"message":"You have exceeded a secondary rate limit. Please wait a few minutes before you try again."}"#,
+        );
+        assert_eq!(
+            policy.delay(1, &Method::GET, &outcome),
+            Some(SECONDARY_RATE_LIMIT_WAIT)
+        );
+        for body in &[
+            r#"{"message":"Forbidden"}"#,
+            r#"{"message":"Must have admin rights to Repository.","documentation_url":"https://docs.github.com/rest/repos/abuse"}"#,
+            "secondary rate limit",
+        ] {
+            assert_eq!(
+                policy.delay(1, &Method::GET, &response(StatusCode::FORBIDDEN, &[], body)),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn waits_for_primary_rate_limit_reset() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let reset = (now + 10).to_string();
+        let outcome = response(
+            StatusCode::FORBIDDEN,
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", &reset),
+            ],
+            "",
+        );
+        let delay = RetryPolicy::builder()
+            .build()
+            .delay(1, &Method::GET, &outcome)
+            .unwrap();
+        assert!(delay >= Duration::from_secs(9) && delay <= Duration::from_secs(11));
+        let impatient = RetryPolicy::builder()
+            .max_rate_limit_wait(Duration::from_secs(5))
+            .build();
+        assert_eq!(impatient.delay(1, &Method::GET, &outcome), None);
+    }
+
+    #[tokio::test]
+    async fn client_retries_transient_failures() {
+        let transport = Scripted::default();
+        transport.respond(StatusCode::SERVICE_UNAVAILABLE, &[], "");
+        transport.respond(StatusCode::BAD_GATEWAY, &[], "");
+        transport.respond(
+            StatusCode::OK,
+            &[],
+            r#"[{"url":"u","name":"bug","color":"f00"}]"#,
+        );
+        let mut github = github(transport.clone());
+        github.set_retry_policy(quick());
+        let labels = github.repo("o", "r").labels().list().await.unwrap();
+        assert_eq!(labels[0].name, "bug");
+        assert_eq!(transport.requested().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn client_gives_up_after_max_attempts() {
+        let transport = Scripted::default();
+        for _ in 0..3 {
+            transport.respond(
+                StatusCode::SERVICE_UNAVAILABLE,
+                &[],
+                r#"{"message":"unavailable"}"#,
+            );
+        }
+        let mut github = github(transport.clone());
+        github.set_retry_policy(quick());
+        match github.repo("o", "r").labels().list().await {
+            Err(Error::Fault { code, .. }) => assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(transport.requested().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn client_retries_mid_pagination() {
+        let transport = Scripted::default();
+        transport.respond(
+            StatusCode::OK,
+            &[(
+                "link",
+                r#"<https://api.github.com/repos/o/r/labels?page=2>; rel="next""#,
+            )],
+            r#"[{"url":"u","name":"bug","color":"f00"}]"#,
+        );
+        transport.respond(StatusCode::GATEWAY_TIMEOUT, &[], "");
+        transport.respond(
+            StatusCode::OK,
+            &[],
+            r#"[{"url":"u","name":"docs","color":"00f"}]"#,
+        );
+        let mut github = github(transport.clone());
+        github.set_retry_policy(quick());
+        let names = github
+            .repo("o", "r")
+            .labels()
+            .iter()
+            .map_ok(|label| label.name)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["bug", "docs"]);
+        assert_eq!(
+            transport.requested(),
+            vec![
+                "https://api.github.com/repos/o/r/labels",
+                "https://api.github.com/repos/o/r/labels?page=2",
+                "https://api.github.com/repos/o/r/labels?page=2",
+            ]
+        );
+    }
+}
@@ -1,8 +1,32 @@
 //! Review comments interface
+use std::collections::HashMap;
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
 
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator, SortDirection};
+
+/// Sort orders for review comments
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Sort {
+    /// Sort by time created
+    #[default]
+    Created,
+    /// Sort by last updated
+    Updated,
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Sort::Created => "created",
+            Sort::Updated => "updated",
+        }
+        .fmt(f)
+    }
+}
 
 /// A structure for interfacing with a review comments
 pub struct ReviewComments {
@@ -32,37 +56,211 @@ impl ReviewComments {
         self.github.get::<Vec<ReviewComment>>(&self.path())
     }
 
+    /// provides a stream over all pages of this pull request's review comments
+    pub fn iter(&self) -> Paginator<ReviewComment> {
+        self.github.get_stream(&self.path())
+    }
+
     /// Create new review comment
     pub fn create(&self, review_comment: &ReviewCommentOptions) -> Future<ReviewComment> {
         self.github.post(&self.path(), json!(review_comment))
     }
 
+    /// Reply to a review comment, adding to its thread. Replies to replies
+    /// are added to the thread of the comment replied to
+    pub fn reply(&self, id: u64, body: &str) -> Future<ReviewComment> {
+        let mut reply = HashMap::new();
+        reply.insert("body", body);
+        self.github
+            .post(&format!("{}/{}/replies", self.path(), id), json!(reply))
+    }
+
+    /// Get a review comment by id
+    pub fn get(&self, id: u64) -> Future<ReviewComment> {
+        self.github.get(&self.comment_path(id))
+    }
+
+    /// Edit the body of a review comment
+    pub fn edit(&self, id: u64, body: &str) -> Future<ReviewComment> {
+        let mut edit = HashMap::new();
+        edit.insert("body", body);
+        self.github.patch(&self.comment_path(id), json!(edit))
+    }
+
+    /// Delete a review comment
+    pub fn delete(&self, id: u64) -> Future<()> {
+        self.github.delete(&self.comment_path(id))
+    }
+
     fn path(&self) -> String {
         format!(
             "/repos/{}/{}/pulls/{}/comments",
             self.owner, self.repo, self.number
         )
     }
+
+    /// individual comments are addressed independently of their pull request
+    fn comment_path(&self, id: u64) -> String {
+        format!("/repos/{}/{}/pulls/comments/{}", self.owner, self.repo, id)
+    }
+}
+
+/// A structure for interfacing with the review comments of all of a
+/// repository's pull requests
+pub struct RepoReviewComments {
+    github: Github,
+    owner: String,
+    repo: String,
+}
+
+impl RepoReviewComments {
+    #[doc(hidden)]
+    pub fn new<O, R>(github: Github, owner: O, repo: R) -> Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        RepoReviewComments {
+            github,
+            owner: owner.into(),
+            repo: repo.into(),
+        }
+    }
+
+    /// list review comments
+    pub fn list(&self, options: &ReviewCommentListOptions) -> Future<Vec<ReviewComment>> {
+        let mut uri = vec![self.path()];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get(&uri.join("?"))
+    }
+
+    /// provides a stream over all pages of review comments
+    pub fn iter(&self, options: &ReviewCommentListOptions) -> Paginator<ReviewComment> {
+        let mut uri = vec![self.path()];
+        if let Some(query) = options.serialize() {
+            uri.push(query);
+        }
+        self.github.get_stream(&uri.join("?"))
+    }
+
+    fn path(&self) -> String {
+        format!("/repos/{}/{}/pulls/comments", self.owner, self.repo)
+    }
+}
+
+/// Return a comment body proposing that the lines commented on be replaced
+/// with `replacement`, which reviewers of the pull request may apply as a
+/// commit. An empty `replacement` proposes deleting the lines
+pub fn suggestion(replacement: &str) -> String {
+    // the fence must be longer than any run of backticks in the replacement
+    let longest_run = replacement
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    if replacement.is_empty() {
+        format!("{}suggestion\n{}", fence, fence)
+    } else {
+        // a lone newline is a blank line rather than nothing
+        let replacement = replacement.strip_suffix('\n').unwrap_or(replacement);
+        format!("{}suggestion\n{}\n{}", fence, replacement, fence)
+    }
 }
 
 // representations (todo: replace with derive_builder)
 
-#[derive(Default, Serialize)]
+/// The side of a diff a comment applies to
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Side {
+    /// the previous version of a file, for deletions and unchanged lines
+    Left,
+    /// the new version of a file, for additions and unchanged lines
+    Right,
+}
+
+#[derive(Debug, Default, Serialize)]
 pub struct ReviewCommentOptions {
     pub body: String,
     pub commit_id: String,
     pub path: String,
-    pub position: usize,
+    /// the line of the diff's `side` to comment on. for multi-line
+    /// comments, the last line of the range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    /// the first line of the range for multi-line comments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_side: Option<Side>,
+    /// the line's position in the diff, counted from the first hunk header.
+    /// superseded by `line`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    /// the id of a comment to reply to. when set, all other fields but
+    /// `body` are ignored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_reply_to: Option<u64>,
+}
+
+impl ReviewCommentOptions {
+    /// Comment on a line of the new version of a file at `commit_id`
+    pub fn new<C, P, B>(commit_id: C, path: P, line: u64, body: B) -> Self
+    where
+        C: Into<String>,
+        P: Into<String>,
+        B: Into<String>,
+    {
+        ReviewCommentOptions {
+            body: body.into(),
+            commit_id: commit_id.into(),
+            path: path.into(),
+            line: Some(line),
+            side: Some(Side::Right),
+            ..Default::default()
+        }
+    }
+
+    /// Comment on the range of lines from `start_line` to `line` of the new
+    /// version of a file at `commit_id`
+    pub fn multi_line<C, P, B>(commit_id: C, path: P, start_line: u64, line: u64, body: B) -> Self
+    where
+        C: Into<String>,
+        P: Into<String>,
+        B: Into<String>,
+    {
+        ReviewCommentOptions {
+            start_line: Some(start_line),
+            start_side: Some(Side::Right),
+            ..ReviewCommentOptions::new(commit_id, path, line, body)
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ReviewComment {
     pub id: u64,
     pub url: String,
+    /// the review this comment belongs to
+    pub pull_request_review_id: Option<u64>,
+    /// the comment this comment replies to
+    pub in_reply_to_id: Option<u64>,
     pub diff_hunk: String,
     pub path: String,
-    pub position: u64,
-    pub original_position: u64,
+    /// null when the comment no longer applies to the pull request's diff
+    pub position: Option<u64>,
+    pub original_position: Option<u64>,
+    pub line: Option<u64>,
+    pub original_line: Option<u64>,
+    pub side: Option<Side>,
+    pub start_line: Option<u64>,
+    pub original_start_line: Option<u64>,
+    pub start_side: Option<Side>,
     pub commit_id: String,
     pub original_commit_id: String,
     pub user: User,
@@ -72,3 +270,106 @@ pub struct ReviewComment {
     pub html_url: String,
     pub pull_request_url: String,
 }
+
+#[derive(Default)]
+pub struct ReviewCommentListOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl ReviewCommentListOptions {
+    pub fn builder() -> ReviewCommentListOptionsBuilder {
+        ReviewCommentListOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            let encoded: String = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&self.params)
+                .finish();
+            Some(encoded)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ReviewCommentListOptionsBuilder(ReviewCommentListOptions);
+
+impl ReviewCommentListOptionsBuilder {
+    pub fn sort(&mut self, sort: Sort) -> &mut Self {
+        self.0.params.insert("sort", sort.to_string());
+        self
+    }
+
+    pub fn direction(&mut self, direction: SortDirection) -> &mut Self {
+        self.0.params.insert("direction", direction.to_string());
+        self
+    }
+
+    /// only list comments updated at or after this ISO 8601 timestamp
+    pub fn since<S>(&mut self, since: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.0.params.insert("since", since.into());
+        self
+    }
+
+    pub fn build(&self) -> ReviewCommentListOptions {
+        ReviewCommentListOptions {
+            params: self.0.params.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(suggestion("let x = 1;\n"), "```suggestion\nlet x = 1;\n```");
+        assert_eq!(suggestion(""), "```suggestion\n```");
+        assert_eq!(suggestion("\n"), "```suggestion\n\n```");
+        assert_eq!(suggestion("\n\n"), "```suggestion\n\n\n```");
+        assert_eq!(
+            suggestion("```rust\nfn main() {}\n```"),
+            "````suggestion\n```rust\nfn main() {}\n```\n````"
+        );
+    }
+
+    #[test]
+    fn review_comment_options() {
+        assert_eq!(
+            serde_json::to_string(&ReviewCommentOptions::multi_line(
+                "6dcb09b5b57875f334f61aebed695e2e4193db5e",
+                "src/lib.rs",
+                3,
+                5,
+                suggestion("")
+            ))
+            .unwrap(),
+            concat!(
+                r#"{"body":"```suggestion\n```","commit_id":"6dcb09b5b57875f334f61aebed695e2e4193db5e","#,
+                r#""path":"src/lib.rs","line":5,"side":"RIGHT","start_line":3,"start_side":"RIGHT"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn review_comment_list_options() {
+        assert_eq!(
+            ReviewCommentListOptions::builder().build().serialize(),
+            None
+        );
+        assert_eq!(
+            ReviewCommentListOptions::builder()
+                .sort(Sort::Updated)
+                .build()
+                .serialize(),
+            Some("sort=updated".into())
+        );
+    }
+}
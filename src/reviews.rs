@@ -0,0 +1,307 @@
+//! Pull request reviews interface
+//!
+//! See the [github docs](https://docs.github.com/en/rest/pulls/reviews) for more information
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::review_comments::{ReviewComment, Side};
+use crate::users::User;
+use crate::{Future, Github, Paginator};
+
+/// A structure for interfacing with a pull request's reviews
+pub struct Reviews {
+    github: Github,
+    owner: String,
+    repo: String,
+    number: u64,
+}
+
+impl Reviews {
+    #[doc(hidden)]
+    pub fn new<O, R>(github: Github, owner: O, repo: R, number: u64) -> Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        Reviews {
+            github,
+            owner: owner.into(),
+            repo: repo.into(),
+            number,
+        }
+    }
+
+    /// list reviews, oldest first
+    pub fn list(&self) -> Future<Vec<Review>> {
+        self.github.get(&self.path(""))
+    }
+
+    /// provides a stream over all pages of this pull request's reviews
+    pub fn iter(&self) -> Paginator<Review> {
+        self.github.get_stream(&self.path(""))
+    }
+
+    /// get a review by id
+    pub fn get(&self, id: u64) -> Future<Review> {
+        self.github.get(&self.path(&format!("/{}", id)))
+    }
+
+    /// Create a review. Reviews created without an event are left pending,
+    /// visible only to their author until submitted
+    pub fn create(&self, review: &ReviewOptions) -> Future<Review> {
+        self.github.post(&self.path(""), json!(review))
+    }
+
+    /// Update the body of a review
+    pub fn update(&self, id: u64, body: &str) -> Future<Review> {
+        let mut options = HashMap::new();
+        options.insert("body", body);
+        self.github
+            .put(&self.path(&format!("/{}", id)), json!(options))
+    }
+
+    /// Delete a pending review
+    pub fn delete(&self, id: u64) -> Future<()> {
+        self.github.delete(&self.path(&format!("/{}", id)))
+    }
+
+    /// Submit a pending review
+    pub fn submit(&self, id: u64, event: ReviewEvent, body: Option<&str>) -> Future<Review> {
+        let submission = ReviewSubmission { body, event };
+        self.github
+            .post(&self.path(&format!("/{}/events", id)), json!(submission))
+    }
+
+    /// Dismiss a submitted review. Dismissing a review requires write
+    /// access to the repository
+    pub fn dismiss(&self, id: u64, message: &str) -> Future<Review> {
+        let dismissal = ReviewDismissal {
+            message,
+            event: "DISMISS",
+        };
+        self.github
+            .put(&self.path(&format!("/{}/dismissals", id)), json!(dismissal))
+    }
+
+    /// list the comments of a review
+    pub fn comments(&self, id: u64) -> Future<Vec<ReviewComment>> {
+        self.github.get(&self.path(&format!("/{}/comments", id)))
+    }
+
+    /// provides a stream over all pages of a review's comments
+    pub fn iter_comments(&self, id: u64) -> Paginator<ReviewComment> {
+        self.github
+            .get_stream(&self.path(&format!("/{}/comments", id)))
+    }
+
+    fn path(&self, more: &str) -> String {
+        format!(
+            "/repos/{}/{}/pulls/{}/reviews{}",
+            self.owner, self.repo, self.number, more
+        )
+    }
+}
+
+// representations
+
+/// The state of a review
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReviewState {
+    Approved,
+    ChangesRequested,
+    Commented,
+    Dismissed,
+    /// created but not yet submitted
+    Pending,
+}
+
+/// The verdict a review is submitted with
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReviewEvent {
+    Approve,
+    RequestChanges,
+    Comment,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Review {
+    pub id: u64,
+    /// null when the reviewer's account has been deleted
+    pub user: Option<User>,
+    pub body: String,
+    pub state: ReviewState,
+    pub html_url: String,
+    pub pull_request_url: String,
+    /// the sha of the commit reviewed
+    pub commit_id: Option<String>,
+    /// null for pending reviews
+    pub submitted_at: Option<String>,
+    pub author_association: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReviewOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event: Option<ReviewEvent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    comments: Vec<DraftReviewComment>,
+}
+
+impl ReviewOptions {
+    pub fn builder() -> ReviewOptionsBuilder {
+        ReviewOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct ReviewOptionsBuilder(ReviewOptions);
+
+impl ReviewOptionsBuilder {
+    /// the sha of the commit to review. defaults to the pull request's head
+    pub fn commit_id<C>(&mut self, commit_id: C) -> &mut Self
+    where
+        C: Into<String>,
+    {
+        self.0.commit_id = Some(commit_id.into());
+        self
+    }
+
+    /// set the body of the review. required when submitting with
+    /// `ReviewEvent::RequestChanges` or `ReviewEvent::Comment`
+    pub fn body<B>(&mut self, body: B) -> &mut Self
+    where
+        B: Into<String>,
+    {
+        self.0.body = Some(body.into());
+        self
+    }
+
+    /// submit the review on creation. without an event the review is
+    /// left pending
+    pub fn event(&mut self, event: ReviewEvent) -> &mut Self {
+        self.0.event = Some(event);
+        self
+    }
+
+    /// add an inline comment to the review
+    pub fn comment(&mut self, comment: DraftReviewComment) -> &mut Self {
+        self.0.comments.push(comment);
+        self
+    }
+
+    pub fn build(&self) -> ReviewOptions {
+        ReviewOptions {
+            commit_id: self.0.commit_id.clone(),
+            body: self.0.body.clone(),
+            event: self.0.event,
+            comments: self.0.comments.clone(),
+        }
+    }
+}
+
+/// An inline comment created along with a review
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DraftReviewComment {
+    pub path: String,
+    pub body: String,
+    /// the line of the diff's `side` to comment on. for multi-line
+    /// comments, the last line of the range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    /// the first line of the range for multi-line comments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_side: Option<Side>,
+    /// the line's position in the diff, counted from the first hunk header.
+    /// superseded by `line`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u64>,
+}
+
+impl DraftReviewComment {
+    /// Comment on a line of the new version of a file
+    pub fn new<P, B>(path: P, line: u64, body: B) -> Self
+    where
+        P: Into<String>,
+        B: Into<String>,
+    {
+        DraftReviewComment {
+            path: path.into(),
+            body: body.into(),
+            line: Some(line),
+            side: Some(Side::Right),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReviewSubmission<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<&'a str>,
+    event: ReviewEvent,
+}
+
+#[derive(Serialize)]
+struct ReviewDismissal<'a> {
+    message: &'a str,
+    event: &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_options() {
+        let options = ReviewOptions::builder()
+            .body("a few nits")
+            .comment(DraftReviewComment::new("src/lib.rs", 12, "typo"))
+            .comment(DraftReviewComment {
+                start_line: Some(3),
+                start_side: Some(Side::Left),
+                side: Some(Side::Left),
+                ..DraftReviewComment::new("README.md", 5, "stale")
+            })
+            .build();
+        assert_eq!(
+            serde_json::to_string(&options).unwrap(),
+            concat!(
+                r#"{"body":"a few nits","comments":["#,
+                r#"{"path":"src/lib.rs","body":"typo","line":12,"side":"RIGHT"},"#,
+                r#"{"path":"README.md","body":"stale","line":5,"side":"LEFT","start_line":3,"start_side":"LEFT"}"#,
+                "]}"
+            )
+        );
+        assert_eq!(
+            serde_json::to_string(
+                &ReviewOptions::builder()
+                    .event(ReviewEvent::RequestChanges)
+                    .build()
+            )
+            .unwrap(),
+            r#"{"event":"REQUEST_CHANGES"}"#
+        );
+    }
+
+    #[test]
+    fn review_states() {
+        for (json, state) in &[
+            (r#""APPROVED""#, ReviewState::Approved),
+            (r#""CHANGES_REQUESTED""#, ReviewState::ChangesRequested),
+            (r#""PENDING""#, ReviewState::Pending),
+        ] {
+            assert_eq!(serde_json::from_str::<ReviewState>(json).unwrap(), *state);
+        }
+    }
+}
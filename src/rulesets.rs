@@ -0,0 +1,601 @@
+//! Rulesets interface
+//!
+//! See the [github docs](https://docs.github.com/en/rest/repos/rules) for more information
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::scope::{OrgScope, RepoScope, Scope};
+use crate::{Future, Github, Paginator};
+
+/// Interface for managing repository rulesets
+pub type RepoRulesets = Rulesets<RepoScope>;
+
+/// Interface for managing organization rulesets
+pub type OrgRulesets = Rulesets<OrgScope>;
+
+/// Interface for managing the rulesets of a repository or organization
+pub struct Rulesets<S: Scope = RepoScope> {
+    github: Github,
+    /// path of the owner, like `/repos/{owner}/{repo}`
+    owner_path: String,
+    scope: PhantomData<S>,
+}
+
+impl Rulesets<RepoScope> {
+    #[doc(hidden)]
+    pub fn new<O, R>(github: Github, owner: O, repo: R) -> Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        Rulesets {
+            github,
+            owner_path: format!("/repos/{}/{}", owner.into(), repo.into()),
+            scope: PhantomData,
+        }
+    }
+
+    /// list the rules which apply to a branch, from both repository and
+    /// organization rulesets. only active rules are listed
+    pub fn rules_for_branch<B>(&self, branch: B) -> Future<Vec<BranchRule>>
+    where
+        B: Into<String>,
+    {
+        self.github.get(&format!(
+            "{}/rules/branches/{}",
+            self.owner_path,
+            branch.into()
+        ))
+    }
+}
+
+impl Rulesets<OrgScope> {
+    #[doc(hidden)]
+    pub fn new<O>(github: Github, org: O) -> Self
+    where
+        O: Into<String>,
+    {
+        Rulesets {
+            github,
+            owner_path: format!("/orgs/{}", org.into()),
+            scope: PhantomData,
+        }
+    }
+}
+
+impl<S: Scope> Rulesets<S> {
+    fn path(&self, more: &str) -> String {
+        format!("{}/rulesets{}", self.owner_path, more)
+    }
+
+    /// list rulesets. listed rulesets don't include their conditions or
+    /// rules, `get` them for those
+    pub fn list(&self) -> Future<Vec<Ruleset>> {
+        self.github.get(&self.path(""))
+    }
+
+    /// provides a stream over all pages of rulesets
+    pub fn iter(&self) -> Paginator<Ruleset> {
+        self.github.get_stream(&self.path(""))
+    }
+
+    /// get a ruleset by id
+    pub fn get(&self, id: u64) -> Future<Ruleset> {
+        self.github.get(&self.path(&format!("/{}", id)))
+    }
+
+    /// create a ruleset
+    pub fn create(&self, ruleset: &RulesetOptions) -> Future<Ruleset> {
+        self.github.post(&self.path(""), json!(ruleset))
+    }
+
+    /// replace a ruleset
+    pub fn update(&self, id: u64, ruleset: &RulesetOptions) -> Future<Ruleset> {
+        self.github
+            .put(&self.path(&format!("/{}", id)), json!(ruleset))
+    }
+
+    /// delete a ruleset
+    pub fn delete(&self, id: u64) -> Future<()> {
+        self.github.delete(&self.path(&format!("/{}", id)))
+    }
+}
+
+// representations
+
+/// Whether a ruleset is enforced
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Enforcement {
+    Disabled,
+    Active,
+    /// report violations without enforcing the rules. only available to
+    /// enterprise organizations
+    Evaluate,
+    /// an enforcement not known to this crate. can't be sent back
+    #[serde(other)]
+    Unknown,
+}
+
+/// The kind of refs a ruleset applies to
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RulesetTarget {
+    Branch,
+    Tag,
+    Push,
+    /// a kind of ref not known to this crate. can't be sent back
+    #[serde(other)]
+    Unknown,
+}
+
+/// The kind of owner a ruleset was defined by
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum RulesetSourceType {
+    Repository,
+    Organization,
+    Enterprise,
+    /// a kind of owner not known to this crate
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Ruleset {
+    pub id: u64,
+    pub name: String,
+    pub target: Option<RulesetTarget>,
+    pub source_type: Option<RulesetSourceType>,
+    /// the name of the repository or organization defining the ruleset
+    pub source: String,
+    pub enforcement: Enforcement,
+    /// only provided to users who may bypass the ruleset
+    #[serde(default)]
+    pub bypass_actors: Vec<BypassActor>,
+    pub conditions: Option<Conditions>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Which refs and repositories a ruleset applies to
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ref_name: Option<RefNameCondition>,
+    /// only applies to organization rulesets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository_name: Option<RepositoryNameCondition>,
+}
+
+/// Ref names or patterns a ruleset applies to. along with fnmatch patterns
+/// like `refs/heads/release/*`, `~DEFAULT_BRANCH` matches the default branch
+/// and `~ALL` matches every ref
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RefNameCondition {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+/// Repository names or patterns an organization ruleset applies to. `~ALL`
+/// matches every repository
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RepositoryNameCondition {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// prevent renaming repositories to escape the ruleset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protected: Option<bool>,
+}
+
+/// A rule of a ruleset
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawRule", into = "RawRule")]
+pub enum Rule {
+    /// only allow users with bypass permission to create matching refs
+    Creation,
+    /// only allow users with bypass permission to update matching refs
+    Update,
+    /// only allow users with bypass permission to delete matching refs
+    Deletion,
+    /// prevent merge commits from being pushed to matching refs
+    RequiredLinearHistory,
+    /// require commits pushed to matching refs to be signed
+    RequiredSignatures,
+    /// require pull requests, with reviews, before merging
+    PullRequest(PullRequestRule),
+    /// require status checks to pass before merging
+    RequiredStatusChecks(StatusChecksRule),
+    /// prevent force pushes to matching refs
+    NonFastForward,
+    /// a rule this crate doesn't model yet, kept as is so rulesets
+    /// containing it may be updated
+    Other {
+        kind: String,
+        parameters: Option<serde_json::Value>,
+    },
+}
+
+/// rules are objects with a type and, for some types, parameters
+#[derive(Serialize, Deserialize)]
+struct RawRule {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parameters: Option<serde_json::Value>,
+}
+
+impl TryFrom<RawRule> for Rule {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawRule) -> Result<Self, Self::Error> {
+        let parameters = || raw.parameters.clone().unwrap_or_default();
+        Ok(match raw.kind.as_str() {
+            "creation" => Rule::Creation,
+            "update" => Rule::Update,
+            "deletion" => Rule::Deletion,
+            "required_linear_history" => Rule::RequiredLinearHistory,
+            "required_signatures" => Rule::RequiredSignatures,
+            "pull_request" => Rule::PullRequest(serde_json::from_value(parameters())?),
+            "required_status_checks" => {
+                Rule::RequiredStatusChecks(serde_json::from_value(parameters())?)
+            }
+            "non_fast_forward" => Rule::NonFastForward,
+            _ => Rule::Other {
+                kind: raw.kind,
+                parameters: raw.parameters,
+            },
+        })
+    }
+}
+
+impl From<Rule> for RawRule {
+    fn from(rule: Rule) -> Self {
+        let (kind, parameters) = match rule {
+            Rule::Creation => ("creation", None),
+            Rule::Update => ("update", None),
+            Rule::Deletion => ("deletion", None),
+            Rule::RequiredLinearHistory => ("required_linear_history", None),
+            Rule::RequiredSignatures => ("required_signatures", None),
+            Rule::PullRequest(parameters) => {
+                ("pull_request", serde_json::to_value(parameters).ok())
+            }
+            Rule::RequiredStatusChecks(parameters) => (
+                "required_status_checks",
+                serde_json::to_value(parameters).ok(),
+            ),
+            Rule::NonFastForward => ("non_fast_forward", None),
+            Rule::Other { kind, parameters } => return RawRule { kind, parameters },
+        };
+        RawRule {
+            kind: kind.into(),
+            parameters,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PullRequestRule {
+    pub dismiss_stale_reviews_on_push: bool,
+    pub require_code_owner_review: bool,
+    /// require approval from someone other than the last pusher
+    pub require_last_push_approval: bool,
+    pub required_approving_review_count: u32,
+    /// require all review threads to be resolved
+    pub required_review_thread_resolution: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusChecksRule {
+    pub required_status_checks: Vec<RequiredStatusCheck>,
+    /// require branches to be up to date with the base branch
+    pub strict_required_status_checks_policy: bool,
+}
+
+/// A required status check, optionally expected from a specific app
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequiredStatusCheck {
+    pub context: String,
+    /// when absent, any app may set the status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integration_id: Option<u64>,
+}
+
+/// The kind of actor which may bypass a ruleset
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BypassActorType {
+    Integration,
+    OrganizationAdmin,
+    RepositoryRole,
+    Team,
+    DeployKey,
+    EnterpriseOwner,
+    /// a kind of actor not known to this crate. can't be sent back
+    #[serde(other)]
+    Unknown,
+}
+
+/// When an actor may bypass a ruleset
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BypassMode {
+    Always,
+    /// only when merging pull requests
+    PullRequest,
+    /// without the rules being evaluated or the bypass being recorded
+    Exempt,
+    /// a mode not known to this crate. can't be sent back
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BypassActor {
+    /// the id of the app, repository role or team. not needed for
+    /// organization admins and deploy keys
+    pub actor_id: Option<u64>,
+    pub actor_type: BypassActorType,
+    pub bypass_mode: BypassMode,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulesetOptions {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<RulesetTarget>,
+    enforcement: Enforcement,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    bypass_actors: Vec<BypassActor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conditions: Option<Conditions>,
+    rules: Vec<Rule>,
+}
+
+impl RulesetOptions {
+    pub fn builder<N>(name: N, enforcement: Enforcement) -> RulesetOptionsBuilder
+    where
+        N: Into<String>,
+    {
+        RulesetOptionsBuilder(RulesetOptions {
+            name: name.into(),
+            target: None,
+            enforcement,
+            bypass_actors: Vec::new(),
+            conditions: None,
+            rules: Vec::new(),
+        })
+    }
+}
+
+pub struct RulesetOptionsBuilder(RulesetOptions);
+
+impl RulesetOptionsBuilder {
+    /// defaults to branches
+    pub fn target(&mut self, target: RulesetTarget) -> &mut Self {
+        self.0.target = Some(target);
+        self
+    }
+
+    /// apply the ruleset to refs matching a pattern
+    pub fn include_ref<P>(&mut self, pattern: P) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.ref_name().include.push(pattern.into());
+        self
+    }
+
+    /// don't apply the ruleset to refs matching a pattern
+    pub fn exclude_ref<P>(&mut self, pattern: P) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.ref_name().exclude.push(pattern.into());
+        self
+    }
+
+    /// apply an organization ruleset to repositories matching a pattern
+    pub fn include_repository<P>(&mut self, pattern: P) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.repository_name().include.push(pattern.into());
+        self
+    }
+
+    /// don't apply an organization ruleset to repositories matching a
+    /// pattern
+    pub fn exclude_repository<P>(&mut self, pattern: P) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        self.repository_name().exclude.push(pattern.into());
+        self
+    }
+
+    pub fn bypass_actor(&mut self, actor: BypassActor) -> &mut Self {
+        self.0.bypass_actors.push(actor);
+        self
+    }
+
+    pub fn rule(&mut self, rule: Rule) -> &mut Self {
+        self.0.rules.push(rule);
+        self
+    }
+
+    pub fn build(&self) -> RulesetOptions {
+        RulesetOptions {
+            name: self.0.name.clone(),
+            target: self.0.target,
+            enforcement: self.0.enforcement,
+            bypass_actors: self.0.bypass_actors.clone(),
+            conditions: self.0.conditions.clone(),
+            rules: self.0.rules.clone(),
+        }
+    }
+
+    fn ref_name(&mut self) -> &mut RefNameCondition {
+        self.0
+            .conditions
+            .get_or_insert_with(Conditions::default)
+            .ref_name
+            .get_or_insert_with(RefNameCondition::default)
+    }
+
+    fn repository_name(&mut self) -> &mut RepositoryNameCondition {
+        self.0
+            .conditions
+            .get_or_insert_with(Conditions::default)
+            .repository_name
+            .get_or_insert_with(RepositoryNameCondition::default)
+    }
+}
+
+/// A rule which applies to a branch, along with the ruleset defining it
+#[derive(Debug, Deserialize)]
+pub struct BranchRule {
+    #[serde(flatten)]
+    pub rule: Rule,
+    pub ruleset_source_type: RulesetSourceType,
+    /// the name of the repository or organization defining the ruleset
+    pub ruleset_source: String,
+    pub ruleset_id: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ruleset_options() {
+        let options = RulesetOptions::builder("protect main", Enforcement::Active)
+            .include_ref("~DEFAULT_BRANCH")
+            .exclude_ref("refs/heads/dev/*")
+            .bypass_actor(BypassActor {
+                actor_id: None,
+                actor_type: BypassActorType::OrganizationAdmin,
+                bypass_mode: BypassMode::Always,
+            })
+            .rule(Rule::Deletion)
+            .rule(Rule::RequiredStatusChecks(StatusChecksRule {
+                required_status_checks: vec![RequiredStatusCheck {
+                    context: "ci".into(),
+                    integration_id: None,
+                }],
+                strict_required_status_checks_policy: true,
+            }))
+            .build();
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            serde_json::json!({
+                "name": "protect main",
+                "enforcement": "active",
+                "bypass_actors": [
+                    { "actor_id": null, "actor_type": "OrganizationAdmin", "bypass_mode": "always" }
+                ],
+                "conditions": {
+                    "ref_name": { "include": ["~DEFAULT_BRANCH"], "exclude": ["refs/heads/dev/*"] }
+                },
+                "rules": [
+                    { "type": "deletion" },
+                    {
+                        "type": "required_status_checks",
+                        "parameters": {
+                            "required_status_checks": [{ "context": "ci" }],
+                            "strict_required_status_checks_policy": true
+                        }
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn deserialize_branch_rules() {
+        let rules: Vec<BranchRule> = serde_json::from_value(serde_json::json!([
+            {
+                "type": "non_fast_forward",
+                "ruleset_source_type": "Organization",
+                "ruleset_source": "octo-org",
+                "ruleset_id": 1
+            },
+            {
+                "type": "pull_request",
+                "parameters": {
+                    "dismiss_stale_reviews_on_push": true,
+                    "require_code_owner_review": false,
+                    "require_last_push_approval": false,
+                    "required_approving_review_count": 2,
+                    "required_review_thread_resolution": true
+                },
+                "ruleset_source_type": "Repository",
+                "ruleset_source": "octo-org/hello-world",
+                "ruleset_id": 2
+            },
+            {
+                "type": "commit_message_pattern",
+                "parameters": { "operator": "starts_with", "pattern": "feat" },
+                "ruleset_source_type": "Repository",
+                "ruleset_source": "octo-org/hello-world",
+                "ruleset_id": 2
+            }
+        ]))
+        .unwrap();
+        assert_eq!(rules[0].rule, Rule::NonFastForward);
+        assert_eq!(
+            rules[0].ruleset_source_type,
+            RulesetSourceType::Organization
+        );
+        match &rules[1].rule {
+            Rule::PullRequest(rule) => assert_eq!(rule.required_approving_review_count, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            rules[2].rule,
+            Rule::Other {
+                kind: "commit_message_pattern".into(),
+                parameters: Some(
+                    serde_json::json!({ "operator": "starts_with", "pattern": "feat" })
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_unknown_kinds() {
+        let ruleset: Ruleset = serde_json::from_value(serde_json::json!({
+            "id": 42,
+            "name": "enterprise",
+            "target": "repository",
+            "source_type": "Marketplace",
+            "source": "octo-enterprise",
+            "enforcement": "audit",
+            "bypass_actors": [
+                { "actor_id": 1, "actor_type": "EnterpriseOwner", "bypass_mode": "exempt" },
+                { "actor_id": 2, "actor_type": "Robot", "bypass_mode": "sometimes" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(ruleset.target, Some(RulesetTarget::Unknown));
+        assert_eq!(ruleset.enforcement, Enforcement::Unknown);
+        assert_eq!(ruleset.source_type, Some(RulesetSourceType::Unknown));
+        assert_eq!(
+            ruleset.bypass_actors,
+            vec![
+                BypassActor {
+                    actor_id: Some(1),
+                    actor_type: BypassActorType::EnterpriseOwner,
+                    bypass_mode: BypassMode::Exempt,
+                },
+                BypassActor {
+                    actor_id: Some(2),
+                    actor_type: BypassActorType::Unknown,
+                    bypass_mode: BypassMode::Unknown,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Owner scopes
+//!
+//! Interfaces available to both repositories and organizations, like hooks
+//! and rulesets, are generic over the kind of owner they belong to
+/// Marks the kind of owner a set of resources belongs to
+pub trait Scope: private::Sealed {}
+
+/// Resources belonging to a repository
+#[derive(Debug)]
+pub enum RepoScope {}
+
+/// Resources belonging to an organization
+#[derive(Debug)]
+pub enum OrgScope {}
+
+impl Scope for RepoScope {}
+impl Scope for OrgScope {}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for super::RepoScope {}
+    impl Sealed for super::OrgScope {}
+}
@@ -8,7 +8,7 @@ use url::{self, form_urlencoded};
 
 use crate::labels::Label;
 use crate::users::User;
-use crate::{unfold, Future, Github, SortDirection, Stream};
+use crate::{Future, Github, Paginator, SortDirection};
 
 mod repos;
 
@@ -43,11 +43,11 @@ pub struct Search {
     github: Github,
 }
 
-fn items<D>(result: SearchResult<D>) -> Vec<D>
+fn contents<D>(result: SearchResult<D>) -> (Vec<D>, Option<u64>)
 where
     D: DeserializeOwned + 'static + Send,
 {
-    result.items
+    (result.items, Some(result.total_count))
 }
 
 impl Search {
@@ -66,11 +66,11 @@ impl Search {
         SearchRepos::new(self.clone())
     }
 
-    fn iter<D>(&self, url: &str) -> Stream<D>
+    fn iter<D>(&self, url: &str) -> Paginator<D>
     where
         D: DeserializeOwned + 'static + Send,
     {
-        unfold(self.github.clone(), self.github.get_pages(url), items)
+        self.github.get_paginator(url, contents)
     }
 
     fn search<D>(&self, url: &str) -> Future<SearchResult<D>>
@@ -109,7 +109,7 @@ impl SearchIssues {
     /// Return a stream of search results repository query
     /// See [github docs](https://developer.github.com/v3/search/#parameters-3)
     /// for query format options
-    pub fn iter<Q>(&self, q: Q, options: &SearchIssuesOptions) -> Stream<IssuesItem>
+    pub fn iter<Q>(&self, q: Q, options: &SearchIssuesOptions) -> Paginator<IssuesItem>
     where
         Q: Into<String>,
     {
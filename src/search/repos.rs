@@ -6,7 +6,7 @@ use url::form_urlencoded;
 
 use super::{Search, SearchResult};
 use crate::users::User;
-use crate::{Future, SortDirection, Stream};
+use crate::{Future, Paginator, SortDirection};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ReposSort {
@@ -58,7 +58,7 @@ impl SearchRepos {
     /// Return a stream of search results repository query
     /// See [github docs](https://developer.github.com/v3/search/#parameters)
     /// for query format options
-    pub fn iter<Q>(&self, q: Q, options: &SearchReposOptions) -> Stream<ReposItem>
+    pub fn iter<Q>(&self, q: Q, options: &SearchReposOptions) -> Paginator<ReposItem>
     where
         Q: Into<String>,
     {
@@ -203,8 +203,8 @@ pub struct ReposItem {
 
 #[derive(Debug, Deserialize)]
 pub struct License {
-    key: String,
-    name: String,
-    spdx_id: String,
-    url: String,
+    pub key: String,
+    pub name: String,
+    pub spdx_id: String,
+    pub url: String,
 }
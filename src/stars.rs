@@ -3,7 +3,7 @@ use futures::prelude::*;
 use http::StatusCode;
 
 use crate::repositories::Repo;
-use crate::Stream;
+use crate::Paginator;
 use crate::{Error, Future, Github};
 
 pub struct Stars {
@@ -71,7 +71,7 @@ impl Stars {
     }
 
     /// provides a stream over all pages of starred repos
-    pub fn iter<U>(&self, username: U) -> Stream<Repo>
+    pub fn iter<U>(&self, username: U) -> Paginator<Repo>
     where
         U: Into<String>,
     {
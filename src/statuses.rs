@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::users::User;
-use crate::{Future, Github};
+use crate::{Future, Github, Paginator};
 
 /// interface for statuses associated with a repository
 pub struct Statuses {
@@ -43,6 +43,14 @@ impl Statuses {
         ))
     }
 
+    /// provides a stream over all pages of statuses associated with a given git sha
+    pub fn iter(&self, sha: &str) -> Paginator<Status> {
+        self.github.get_stream(&format!(
+            "/repos/{}/{}/commits/{}/statuses",
+            self.owner, self.repo, sha
+        ))
+    }
+
     /// list the combined statuses for a given git sha
     /// fixme: give this a type
     pub fn combined(&self, sha: &str) -> Future<String> {
@@ -150,10 +158,11 @@ impl StatusOptions {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Default)]
 pub enum State {
     /// pending
     #[serde(rename = "pending")]
+    #[default]
     Pending,
     /// success
     #[serde(rename = "success")]
@@ -166,12 +175,6 @@ pub enum State {
     Failure,
 }
 
-impl Default for State {
-    fn default() -> State {
-        State::Pending
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,7 +189,7 @@ mod tests {
 
     #[test]
     fn deserialize_status_state() {
-        for (json, value) in vec![
+        for (json, value) in [
             ("\"pending\"", State::Pending),
             ("\"success\"", State::Success),
             ("\"error\"", State::Error),
@@ -198,7 +201,7 @@ mod tests {
 
     #[test]
     fn serialize_status_state() {
-        for (json, value) in vec![
+        for (json, value) in [
             ("\"pending\"", State::Pending),
             ("\"success\"", State::Success),
             ("\"error\"", State::Error),
@@ -0,0 +1,1233 @@
+//! Declarative repository settings
+//!
+//! A `RepoSettings` document describes the desired settings of a repository,
+//! typically deserialized from a file kept under version control. A
+//! `Reconciler` compares it with the repository's live settings, plans the
+//! changes needed to bring the repository in line and applies them.
+//!
+//! Sections left out of a document are left alone. Labels, collaborators,
+//! teams and hooks which are live but missing from a section which is
+//! present are removed. Branches missing from `branches` keep their
+//! protection, and protection rules left out keep their current setting. Set
+//! a protection section to `null` to remove it.
+//!
+//! ```no_run
+//! # async fn run(github: hubcaps::Github) -> Result<(), Box<dyn std::error::Error>> {
+//! use hubcaps::sync::{Reconciler, RepoSettings};
+//!
+//! let desired: RepoSettings = serde_json::from_str(
+//!     r#"{
+//!         "repository": { "has_wiki": false },
+//!         "labels": [{ "name": "bug", "color": "d73a4a" }],
+//!         "branches": { "main": { "enforce_admins": true } }
+//!     }"#,
+//! )?;
+//! let plan = Reconciler::new(github, "owner", "repo")
+//!     .sync(&desired, true)
+//!     .await?;
+//! println!("{}", plan);
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use futures::prelude::*;
+use http::StatusCode;
+use serde::{Deserialize, Deserializer};
+
+use crate::branches::{
+    BranchProtection, Protection, ProtectionSetting, ProtectionState, PullRequestReviewsState,
+    RequiredPullRequestReviews, Restrictions, RestrictionsState, StatusCheck, StatusChecks,
+};
+use crate::collaborators::{Collaborators, Permissions};
+use crate::hooks::{Hook, HookCreateOptions, HookEditOptions, WebHookContentType};
+use crate::labels::{Label, LabelOptions};
+use crate::repositories::{Repo, RepoEditOptions, Repository};
+use crate::teams::{Permission, Team};
+use crate::{Error, Future, Github};
+
+/// The desired settings of a repository
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoSettings {
+    pub repository: Option<RepositorySettings>,
+    pub labels: Option<Vec<LabelSettings>>,
+    pub collaborators: Option<Vec<CollaboratorSettings>>,
+    pub teams: Option<Vec<TeamSettings>>,
+    /// protection by branch name
+    #[serde(default)]
+    pub branches: BTreeMap<String, ProtectionSettings>,
+    pub hooks: Option<Vec<HookSettings>>,
+}
+
+/// The desired protection of a branch. Rules left out keep their current
+/// setting. Sections set to null are removed
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtectionSettings {
+    #[serde(default, deserialize_with = "present")]
+    pub required_status_checks: Option<Option<StatusChecks>>,
+    pub enforce_admins: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub required_pull_request_reviews: Option<Option<RequiredPullRequestReviews>>,
+    /// who may push to the branch. only available for organization
+    /// repositories
+    #[serde(default, deserialize_with = "present")]
+    pub restrictions: Option<Option<Restrictions>>,
+    pub required_linear_history: Option<bool>,
+    pub allow_force_pushes: Option<bool>,
+    pub allow_deletions: Option<bool>,
+    pub required_conversation_resolution: Option<bool>,
+    pub block_creations: Option<bool>,
+}
+
+/// tell null fields, which deserialize to `Some(None)`, apart from absent
+/// ones, which default to `None`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Settings of the repository itself. settings left out are left alone
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepositorySettings {
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub private: Option<bool>,
+    pub has_issues: Option<bool>,
+    pub has_projects: Option<bool>,
+    pub has_wiki: Option<bool>,
+    pub default_branch: Option<String>,
+    pub allow_squash_merge: Option<bool>,
+    pub allow_merge_commit: Option<bool>,
+    pub allow_rebase_merge: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LabelSettings {
+    /// labels are matched by name, ignoring case
+    pub name: String,
+    /// hex color, without the leading `#`
+    pub color: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CollaboratorSettings {
+    pub username: String,
+    #[serde(default)]
+    pub permission: Permissions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TeamSettings {
+    /// the slug of a team of the repository's organization
+    pub slug: String,
+    pub permission: Permission,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookSettings {
+    /// web hooks are matched by url
+    pub url: String,
+    #[serde(default)]
+    pub content_type: WebHookContentType,
+    #[serde(default = "default_events")]
+    pub events: Vec<String>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    /// secrets can't be read back, so they're only sent when a hook is
+    /// created or its config changes
+    pub secret: Option<String>,
+}
+
+fn default_events() -> Vec<String> {
+    vec!["push".into()]
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Whether an action creates, updates or deletes something
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionKind {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A change planned to bring a repository in line with its settings
+#[derive(Debug)]
+pub struct Action {
+    pub kind: ActionKind,
+    /// what is changed, like `label bug` or `protection of main`
+    pub subject: String,
+    /// the values which change, like `color d73a4a -> ff0000`
+    pub details: Vec<String>,
+    step: Step,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = match self.kind {
+            ActionKind::Create => '+',
+            ActionKind::Update => '~',
+            ActionKind::Delete => '-',
+        };
+        write!(f, "{} {}", sign, self.subject)?;
+        if !self.details.is_empty() {
+            write!(f, ": {}", self.details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The calls a step makes when applied
+#[derive(Debug)]
+enum Step {
+    EditRepository(RepoEditOptions),
+    CreateLabel(LabelOptions),
+    UpdateLabel(String, LabelOptions),
+    DeleteLabel(String),
+    AddCollaborator(String, Permissions),
+    RemoveCollaborator(String),
+    AddTeam(String, Permission),
+    RemoveTeam(String),
+    Protect(String, Protection),
+    CreateHook(HookCreateOptions),
+    EditHook(u64, HookEditOptions),
+    DeleteHook(u64),
+}
+
+/// The actions needed to bring a repository in line with its settings, in
+/// the order they are applied
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub actions: Vec<Action>,
+}
+
+impl Plan {
+    /// true when the repository already matches its settings
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        for action in &self.actions {
+            writeln!(f, "{}", action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Brings a repository in line with a `RepoSettings` document
+pub struct Reconciler {
+    github: Github,
+    owner: String,
+    repo: String,
+}
+
+impl Reconciler {
+    pub fn new<O, R>(github: Github, owner: O, repo: R) -> Self
+    where
+        O: Into<String>,
+        R: Into<String>,
+    {
+        Reconciler {
+            github,
+            owner: owner.into(),
+            repo: repo.into(),
+        }
+    }
+
+    /// Fetch the live settings covered by `desired` and plan the changes
+    /// needed to match it. nothing is changed
+    pub fn plan(&self, desired: &RepoSettings) -> Future<Plan> {
+        let live = self.live(desired);
+        let desired = desired.clone();
+        let owner = self.owner.clone();
+        Box::pin(async move {
+            let live = live.await?;
+            Ok(plan(&desired, &live, &owner))
+        })
+    }
+
+    /// Apply a plan's actions in order, stopping at the first which fails
+    pub fn apply(&self, plan: &Plan) -> Future<()> {
+        let steps = plan
+            .actions
+            .iter()
+            .map(|action| self.step(&action.step))
+            .collect::<Vec<_>>();
+        Box::pin(async move {
+            for step in steps {
+                step.await?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Plan the changes needed to match `desired` and, unless `dry_run` is
+    /// true, apply them. returns the plan
+    pub fn sync(&self, desired: &RepoSettings, dry_run: bool) -> Future<Plan> {
+        let planned = self.plan(desired);
+        let reconciler =
+            Reconciler::new(self.github.clone(), self.owner.as_str(), self.repo.as_str());
+        Box::pin(async move {
+            let plan = planned.await?;
+            if !dry_run {
+                reconciler.apply(&plan).await?;
+            }
+            Ok(plan)
+        })
+    }
+
+    fn repository(&self) -> Repository {
+        Repository::new(self.github.clone(), self.owner.as_str(), self.repo.as_str())
+    }
+
+    fn live(&self, desired: &RepoSettings) -> Future<Live> {
+        let repository = self.repository();
+        let collaborators =
+            Collaborators::new(self.github.clone(), self.owner.as_str(), self.repo.as_str());
+        let wanted_collaborators = desired.collaborators.as_ref().map(|wanted| {
+            wanted
+                .iter()
+                .map(|collaborator| collaborator.username.to_lowercase())
+                .collect::<Vec<_>>()
+        });
+        let manage_repository = desired.repository.is_some();
+        let manage_labels = desired.labels.is_some();
+        let manage_teams = desired.teams.is_some();
+        let manage_hooks = desired.hooks.is_some();
+        let branches = desired.branches.keys().cloned().collect::<Vec<_>>();
+        Box::pin(async move {
+            let mut live = Live::default();
+            if manage_repository {
+                live.repo = Some(repository.get().await?);
+            }
+            if manage_labels {
+                live.labels = Some(repository.labels().iter().try_collect().await?);
+            }
+            if let Some(wanted) = wanted_collaborators {
+                let mut roles = Vec::new();
+                let users = collaborators.iter_direct().try_collect::<Vec<_>>().await?;
+                for user in users {
+                    // only the roles of collaborators who stay matter
+                    let role = if wanted.contains(&user.login.to_lowercase()) {
+                        let permission = collaborators.permission(&user.login).await?;
+                        Some(permission.role_name.unwrap_or(permission.permission))
+                    } else {
+                        None
+                    };
+                    roles.push((user.login, role));
+                }
+                live.collaborators = Some(roles);
+            }
+            if manage_teams {
+                live.teams = Some(repository.teams().iter().try_collect().await?);
+            }
+            for branch in branches {
+                let protection = match repository
+                    .branches()
+                    .protection_rules(branch.as_str())
+                    .get()
+                    .await
+                {
+                    Ok(protection) => Some(protection),
+                    Err(Error::Fault {
+                        code: StatusCode::NOT_FOUND,
+                        ..
+                    }) => None,
+                    Err(err) => return Err(err),
+                };
+                live.protections.insert(branch, protection);
+            }
+            if manage_hooks {
+                live.hooks = Some(repository.hooks().iter().try_collect().await?);
+            }
+            Ok(live)
+        })
+    }
+
+    fn step(&self, step: &Step) -> Future<()> {
+        let repository = self.repository();
+        match step {
+            Step::EditRepository(options) => Box::pin(repository.edit(options).map_ok(|_| ())),
+            Step::CreateLabel(options) => {
+                Box::pin(repository.labels().create(options).map_ok(|_| ()))
+            }
+            Step::UpdateLabel(name, options) => {
+                Box::pin(repository.labels().update(name, options).map_ok(|_| ()))
+            }
+            Step::DeleteLabel(name) => repository.labels().delete(name),
+            Step::AddCollaborator(username, permission) => {
+                repository.collaborators().add(username, permission)
+            }
+            Step::RemoveCollaborator(username) => repository.collaborators().remove(username),
+            Step::AddTeam(slug, permission) => repository.teams().add(slug, *permission),
+            Step::RemoveTeam(slug) => repository.teams().remove(slug),
+            Step::Protect(branch, protection) => Box::pin(
+                BranchProtection::new(
+                    self.github.clone(),
+                    self.owner.as_str(),
+                    self.repo.as_str(),
+                    branch.as_str(),
+                )
+                .update(protection)
+                .map_ok(|_| ()),
+            ),
+            Step::CreateHook(options) => {
+                Box::pin(repository.hooks().create(options).map_ok(|_| ()))
+            }
+            Step::EditHook(id, options) => {
+                Box::pin(repository.hooks().edit(*id, options).map_ok(|_| ()))
+            }
+            Step::DeleteHook(id) => repository.hooks().delete(*id),
+        }
+    }
+}
+
+/// The live settings covered by a document
+#[derive(Default)]
+struct Live {
+    repo: Option<Repo>,
+    labels: Option<Vec<Label>>,
+    /// logins, with the roles of those which are to stay
+    collaborators: Option<Vec<(String, Option<String>)>>,
+    teams: Option<Vec<Team>>,
+    /// `None` for unprotected branches
+    protections: BTreeMap<String, Option<ProtectionState>>,
+    hooks: Option<Vec<Hook>>,
+}
+
+fn plan(desired: &RepoSettings, live: &Live, owner: &str) -> Plan {
+    let mut actions = Vec::new();
+    if let (Some(desired), Some(repo)) = (&desired.repository, &live.repo) {
+        actions.extend(plan_repository(desired, repo));
+    }
+    if let (Some(desired), Some(labels)) = (&desired.labels, &live.labels) {
+        actions.extend(plan_labels(desired, labels));
+    }
+    if let (Some(desired), Some(collaborators)) = (&desired.collaborators, &live.collaborators) {
+        actions.extend(plan_collaborators(desired, collaborators, owner));
+    }
+    if let (Some(desired), Some(teams)) = (&desired.teams, &live.teams) {
+        actions.extend(plan_teams(desired, teams));
+    }
+    for (branch, protection) in &desired.branches {
+        let live = live.protections.get(branch).and_then(Option::as_ref);
+        actions.extend(plan_protection(branch, protection, live));
+    }
+    if let (Some(desired), Some(hooks)) = (&desired.hooks, &live.hooks) {
+        actions.extend(plan_hooks(desired, hooks));
+    }
+    Plan { actions }
+}
+
+fn plan_repository(desired: &RepositorySettings, repo: &Repo) -> Option<Action> {
+    let mut details = Vec::new();
+    let mut options = RepoEditOptions::builder(repo.name.as_str());
+    macro_rules! diff {
+        ($field:ident, $live:expr) => {
+            if let Some(value) = &desired.$field {
+                if Some(value) != $live.as_ref() {
+                    details.push(format!(
+                        "{} {} -> {:?}",
+                        stringify!($field),
+                        $live.map_or_else(|| "unset".to_string(), |live| format!("{:?}", live)),
+                        value
+                    ));
+                    options.$field(value.clone());
+                }
+            }
+        };
+    }
+    diff!(description, repo.description.clone());
+    diff!(homepage, repo.homepage.clone());
+    diff!(private, Some(repo.private));
+    diff!(has_issues, Some(repo.has_issues));
+    diff!(has_projects, repo.has_projects);
+    diff!(has_wiki, Some(repo.has_wiki));
+    diff!(default_branch, Some(repo.default_branch.clone()));
+    diff!(allow_squash_merge, repo.allow_squash_merge);
+    diff!(allow_merge_commit, repo.allow_merge_commit);
+    diff!(allow_rebase_merge, repo.allow_rebase_merge);
+    if details.is_empty() {
+        return None;
+    }
+    Some(Action {
+        kind: ActionKind::Update,
+        subject: "repository".into(),
+        details,
+        step: Step::EditRepository(options.build()),
+    })
+}
+
+fn plan_labels(desired: &[LabelSettings], live: &[Label]) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let live_by_name = live
+        .iter()
+        .map(|label| (label.name.to_lowercase(), label))
+        .collect::<HashMap<_, _>>();
+    for label in desired {
+        let options = LabelOptions::new(
+            label.name.as_str(),
+            label.color.to_lowercase(),
+            label.description.as_str(),
+        );
+        match live_by_name.get(&label.name.to_lowercase()) {
+            None => actions.push(Action {
+                kind: ActionKind::Create,
+                subject: format!("label {}", label.name),
+                details: vec![format!("color {}", options.color)],
+                step: Step::CreateLabel(options),
+            }),
+            Some(current) => {
+                let mut details = Vec::new();
+                if current.name != label.name {
+                    details.push(format!("name {} -> {}", current.name, label.name));
+                }
+                if !current.color.eq_ignore_ascii_case(&label.color) {
+                    details.push(format!("color {} -> {}", current.color, options.color));
+                }
+                let description = current.description.as_deref().unwrap_or_default();
+                if description != label.description {
+                    details.push(format!(
+                        "description {:?} -> {:?}",
+                        description, label.description
+                    ));
+                }
+                if !details.is_empty() {
+                    actions.push(Action {
+                        kind: ActionKind::Update,
+                        subject: format!("label {}", current.name),
+                        details,
+                        step: Step::UpdateLabel(current.name.clone(), options),
+                    });
+                }
+            }
+        }
+    }
+    for label in live {
+        if !desired
+            .iter()
+            .any(|wanted| wanted.name.eq_ignore_ascii_case(&label.name))
+        {
+            actions.push(Action {
+                kind: ActionKind::Delete,
+                subject: format!("label {}", label.name),
+                details: Vec::new(),
+                step: Step::DeleteLabel(label.name.clone()),
+            });
+        }
+    }
+    actions
+}
+
+/// whether a collaborator's role grants exactly a permission
+fn has_permission(role: &str, permission: Permissions) -> bool {
+    match permission {
+        Permissions::Admin => role == "admin",
+        Permissions::Push => role == "write" || role == "push",
+        Permissions::Pull => role == "read" || role == "pull",
+    }
+}
+
+fn plan_collaborators(
+    desired: &[CollaboratorSettings],
+    live: &[(String, Option<String>)],
+    owner: &str,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+    for collaborator in desired {
+        let current = live
+            .iter()
+            .find(|(login, _)| login.eq_ignore_ascii_case(&collaborator.username));
+        match current {
+            None => actions.push(Action {
+                kind: ActionKind::Create,
+                subject: format!("collaborator {}", collaborator.username),
+                details: vec![format!("permission {}", collaborator.permission)],
+                step: Step::AddCollaborator(collaborator.username.clone(), collaborator.permission),
+            }),
+            Some((login, Some(role))) if !has_permission(role, collaborator.permission) => actions
+                .push(Action {
+                    kind: ActionKind::Update,
+                    subject: format!("collaborator {}", login),
+                    details: vec![format!(
+                        "permission {} -> {}",
+                        role, collaborator.permission
+                    )],
+                    step: Step::AddCollaborator(login.clone(), collaborator.permission),
+                }),
+            Some(_) => (),
+        }
+    }
+    for (login, _) in live {
+        // the owner of a personal repository can't be removed from it
+        let wanted = desired
+            .iter()
+            .any(|collaborator| collaborator.username.eq_ignore_ascii_case(login));
+        if !wanted && !login.eq_ignore_ascii_case(owner) {
+            actions.push(Action {
+                kind: ActionKind::Delete,
+                subject: format!("collaborator {}", login),
+                details: Vec::new(),
+                step: Step::RemoveCollaborator(login.clone()),
+            });
+        }
+    }
+    actions
+}
+
+fn plan_teams(desired: &[TeamSettings], live: &[Team]) -> Vec<Action> {
+    let mut actions = Vec::new();
+    for team in desired {
+        match live.iter().find(|current| current.slug == team.slug) {
+            None => actions.push(Action {
+                kind: ActionKind::Create,
+                subject: format!("team {}", team.slug),
+                details: vec![format!("permission {}", team.permission)],
+                step: Step::AddTeam(team.slug.clone(), team.permission),
+            }),
+            Some(current) if current.permission != team.permission.to_string() => {
+                actions.push(Action {
+                    kind: ActionKind::Update,
+                    subject: format!("team {}", team.slug),
+                    details: vec![format!(
+                        "permission {} -> {}",
+                        current.permission, team.permission
+                    )],
+                    step: Step::AddTeam(team.slug.clone(), team.permission),
+                })
+            }
+            Some(_) => (),
+        }
+    }
+    for team in live {
+        if !desired.iter().any(|wanted| wanted.slug == team.slug) {
+            actions.push(Action {
+                kind: ActionKind::Delete,
+                subject: format!("team {}", team.slug),
+                details: Vec::new(),
+                step: Step::RemoveTeam(team.slug.clone()),
+            });
+        }
+    }
+    actions
+}
+
+fn sorted<I, S>(names: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut names = names
+        .into_iter()
+        .map(|name| name.as_ref().to_lowercase())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+fn same_restrictions(desired: &Restrictions, live: &RestrictionsState) -> bool {
+    sorted(&desired.users) == sorted(live.users.iter().map(|user| &user.login))
+        && sorted(&desired.teams) == sorted(live.teams.iter().map(|team| &team.slug))
+        && sorted(&desired.apps) == sorted(live.apps.iter().map(|app| &app.slug))
+}
+
+fn enabled(setting: &Option<ProtectionSetting>) -> bool {
+    setting.as_ref().is_some_and(|setting| setting.enabled)
+}
+
+fn same_status_checks(desired: &StatusChecks, live: &StatusChecks) -> bool {
+    desired.strict == live.strict
+        && if desired.checks.is_empty() {
+            sorted(&desired.contexts) == sorted(&live.contexts)
+        } else {
+            let checks = |checks: &[StatusCheck]| {
+                let mut checks = checks
+                    .iter()
+                    .map(|check| (check.context.clone(), check.app_id))
+                    .collect::<Vec<_>>();
+                checks.sort();
+                checks
+            };
+            checks(&desired.checks) == checks(&live.checks)
+        }
+}
+
+fn same_reviews(desired: &RequiredPullRequestReviews, live: &PullRequestReviewsState) -> bool {
+    desired.dismiss_stale_reviews == live.dismiss_stale_reviews
+        && desired.require_code_owner_reviews == live.require_code_owner_reviews
+        && desired.required_approving_review_count == live.required_approving_review_count
+        && desired
+            .require_last_push_approval
+            .is_none_or(|approval| approval == live.require_last_push_approval)
+        && match (
+            &desired.dismissal_restrictions,
+            &live.dismissal_restrictions,
+        ) {
+            (None, _) => true,
+            (Some(desired), Some(live)) => same_restrictions(desired, live),
+            (Some(_), None) => false,
+        }
+}
+
+/// whether a section a document sets matches a branch's. sections the
+/// document leaves out always match
+fn same_section<D, L, F>(desired: &Option<Option<D>>, live: &Option<L>, same: F) -> bool
+where
+    F: Fn(&D, &L) -> bool,
+{
+    match (desired, live) {
+        (None, _) | (Some(None), None) => true,
+        (Some(Some(desired)), Some(live)) => same(desired, live),
+        _ => false,
+    }
+}
+
+/// the names of the rules of a protection which differ from a branch's
+fn protection_changes(desired: &ProtectionSettings, live: &ProtectionState) -> Vec<String> {
+    let mut changes = Vec::new();
+    if !same_section(
+        &desired.required_status_checks,
+        &live.required_status_checks,
+        same_status_checks,
+    ) {
+        changes.push("required_status_checks".into());
+    }
+    if let Some(enforce_admins) = desired.enforce_admins {
+        if enforce_admins
+            != live
+                .enforce_admins
+                .as_ref()
+                .is_some_and(|admins| admins.enabled)
+        {
+            changes.push(format!("enforce_admins -> {}", enforce_admins));
+        }
+    }
+    if !same_section(
+        &desired.required_pull_request_reviews,
+        &live.required_pull_request_reviews,
+        same_reviews,
+    ) {
+        changes.push("required_pull_request_reviews".into());
+    }
+    if !same_section(&desired.restrictions, &live.restrictions, same_restrictions) {
+        changes.push("restrictions".into());
+    }
+    for (name, desired, live) in [
+        (
+            "required_linear_history",
+            desired.required_linear_history,
+            &live.required_linear_history,
+        ),
+        (
+            "allow_force_pushes",
+            desired.allow_force_pushes,
+            &live.allow_force_pushes,
+        ),
+        (
+            "allow_deletions",
+            desired.allow_deletions,
+            &live.allow_deletions,
+        ),
+        (
+            "required_conversation_resolution",
+            desired.required_conversation_resolution,
+            &live.required_conversation_resolution,
+        ),
+        (
+            "block_creations",
+            desired.block_creations,
+            &live.block_creations,
+        ),
+    ] {
+        if let Some(desired) = desired {
+            if desired != enabled(live) {
+                changes.push(format!("{} -> {}", name, desired));
+            }
+        }
+    }
+    changes
+}
+
+/// a section a document sets, or a branch's current one when left out
+fn section<T: Clone>(desired: &Option<Option<T>>, live: Option<T>) -> Option<T> {
+    match desired {
+        Some(desired) => desired.clone(),
+        None => live,
+    }
+}
+
+fn restrictions(live: &RestrictionsState) -> Restrictions {
+    Restrictions {
+        users: live.users.iter().map(|user| user.login.clone()).collect(),
+        teams: live.teams.iter().map(|team| team.slug.clone()).collect(),
+        apps: live.apps.iter().map(|app| app.slug.clone()).collect(),
+    }
+}
+
+fn reviews(live: &PullRequestReviewsState) -> RequiredPullRequestReviews {
+    RequiredPullRequestReviews {
+        dismissal_restrictions: live.dismissal_restrictions.as_ref().map(restrictions),
+        dismiss_stale_reviews: live.dismiss_stale_reviews,
+        require_code_owner_reviews: live.require_code_owner_reviews,
+        required_approving_review_count: live.required_approving_review_count,
+        require_last_push_approval: Some(live.require_last_push_approval),
+    }
+}
+
+/// Fill the rules a document leaves out with a branch's current ones.
+/// Protection is replaced as a whole, so rules left out would otherwise be
+/// reset rather than left alone
+fn merge_protection(desired: &ProtectionSettings, live: Option<&ProtectionState>) -> Protection {
+    let setting = |desired: Option<bool>, live: Option<&Option<ProtectionSetting>>| {
+        desired.or_else(|| live.and_then(Option::as_ref).map(|setting| setting.enabled))
+    };
+    let mut protection = Protection {
+        required_status_checks: section(
+            &desired.required_status_checks,
+            live.and_then(|live| live.required_status_checks.clone()),
+        ),
+        enforce_admins: desired.enforce_admins.unwrap_or_else(|| {
+            live.and_then(|live| live.enforce_admins.as_ref())
+                .is_some_and(|admins| admins.enabled)
+        }),
+        required_pull_request_reviews: section(
+            &desired.required_pull_request_reviews,
+            live.and_then(|live| live.required_pull_request_reviews.as_ref())
+                .map(reviews),
+        ),
+        restrictions: section(
+            &desired.restrictions,
+            live.and_then(|live| live.restrictions.as_ref())
+                .map(restrictions),
+        ),
+        required_linear_history: setting(
+            desired.required_linear_history,
+            live.map(|live| &live.required_linear_history),
+        ),
+        allow_force_pushes: setting(
+            desired.allow_force_pushes,
+            live.map(|live| &live.allow_force_pushes),
+        ),
+        allow_deletions: setting(
+            desired.allow_deletions,
+            live.map(|live| &live.allow_deletions),
+        ),
+        required_conversation_resolution: setting(
+            desired.required_conversation_resolution,
+            live.map(|live| &live.required_conversation_resolution),
+        ),
+        block_creations: setting(
+            desired.block_creations,
+            live.map(|live| &live.block_creations),
+        ),
+    };
+    if let (Some(desired), Some(live)) = (
+        protection.required_pull_request_reviews.as_mut(),
+        live.and_then(|live| live.required_pull_request_reviews.as_ref()),
+    ) {
+        desired
+            .require_last_push_approval
+            .get_or_insert(live.require_last_push_approval);
+        if desired.dismissal_restrictions.is_none() {
+            desired.dismissal_restrictions = live.dismissal_restrictions.as_ref().map(restrictions);
+        }
+    }
+    protection
+}
+
+fn plan_protection(
+    branch: &str,
+    desired: &ProtectionSettings,
+    live: Option<&ProtectionState>,
+) -> Option<Action> {
+    let (kind, details) = match live {
+        None => (ActionKind::Create, Vec::new()),
+        Some(live) => {
+            let changes = protection_changes(desired, live);
+            if changes.is_empty() {
+                return None;
+            }
+            (ActionKind::Update, changes)
+        }
+    };
+    Some(Action {
+        kind,
+        subject: format!("protection of {}", branch),
+        details,
+        step: Step::Protect(branch.to_string(), merge_protection(desired, live)),
+    })
+}
+
+fn plan_hooks(desired: &[HookSettings], live: &[Hook]) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let web_hooks = live
+        .iter()
+        .filter(|hook| hook.name == "web")
+        .filter_map(|hook| hook.config_string("url").map(|url| (url, hook)))
+        .collect::<Vec<_>>();
+    for hook in desired {
+        match web_hooks.iter().find(|(url, _)| *url == hook.url) {
+            None => {
+                let mut options = HookCreateOptions::web();
+                options
+                    .url(hook.url.as_str())
+                    .content_type(hook.content_type)
+                    .events(hook.events.clone())
+                    .active(hook.active);
+                if let Some(secret) = &hook.secret {
+                    options.secret(secret.as_str());
+                }
+                actions.push(Action {
+                    kind: ActionKind::Create,
+                    subject: format!("hook {}", hook.url),
+                    details: vec![format!("events {}", hook.events.join(" "))],
+                    step: Step::CreateHook(options.build()),
+                });
+            }
+            Some((_, current)) => {
+                let mut details = Vec::new();
+                let mut options = HookEditOptions::builder();
+                options.events(hook.events.clone()).active(hook.active);
+                if sorted(&current.events) != sorted(&hook.events) {
+                    details.push(format!(
+                        "events {} -> {}",
+                        current.events.join(" "),
+                        hook.events.join(" ")
+                    ));
+                }
+                if current.active != hook.active {
+                    details.push(format!("active {} -> {}", current.active, hook.active));
+                }
+                let content_type = hook.content_type.to_string();
+                if current.config_string("content_type").as_deref() != Some(content_type.as_str()) {
+                    details.push(format!("content_type -> {}", content_type));
+                    options
+                        .url(hook.url.as_str())
+                        .content_type(hook.content_type);
+                    if let Some(secret) = &hook.secret {
+                        options.secret(secret.as_str());
+                    }
+                }
+                if !details.is_empty() {
+                    actions.push(Action {
+                        kind: ActionKind::Update,
+                        subject: format!("hook {}", hook.url),
+                        details,
+                        step: Step::EditHook(current.id, options.build()),
+                    });
+                }
+            }
+        }
+    }
+    for (url, hook) in &web_hooks {
+        if !desired.iter().any(|wanted| wanted.url == *url) {
+            actions.push(Action {
+                kind: ActionKind::Delete,
+                subject: format!("hook {}", url),
+                details: Vec::new(),
+                step: Step::DeleteHook(hook.id),
+            });
+        }
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(name: &str, color: &str, description: Option<&str>) -> Label {
+        Label {
+            url: format!(
+                "https://api.github.com/repos/octocat/hello-world/labels/{}",
+                name
+            ),
+            name: name.into(),
+            color: color.into(),
+            description: description.map(Into::into),
+        }
+    }
+
+    fn lines(actions: &[Action]) -> Vec<String> {
+        actions.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn deserialize_settings() {
+        let settings: RepoSettings = serde_json::from_value(serde_json::json!({
+            "repository": { "has_wiki": false },
+            "collaborators": [{ "username": "octocat", "permission": "admin" }],
+            "teams": [{ "slug": "core", "permission": "push" }],
+            "branches": { "main": { "enforce_admins": true, "allow_force_pushes": false } },
+            "hooks": [{ "url": "https://example.com/hook" }]
+        }))
+        .unwrap();
+        assert!(settings.labels.is_none());
+        assert_eq!(
+            settings.collaborators.unwrap()[0].permission,
+            Permissions::Admin
+        );
+        assert_eq!(settings.branches["main"].enforce_admins, Some(true));
+        let hook = &settings.hooks.unwrap()[0];
+        assert_eq!(hook.events, vec!["push"]);
+        assert!(hook.active);
+        assert!(
+            serde_json::from_value::<RepoSettings>(serde_json::json!({ "lables": [] })).is_err()
+        );
+    }
+
+    #[test]
+    fn plans_labels() {
+        let desired = vec![
+            LabelSettings {
+                name: "bug".into(),
+                color: "D73A4A".into(),
+                description: "".into(),
+            },
+            LabelSettings {
+                name: "Docs".into(),
+                color: "0075ca".into(),
+                description: "documentation".into(),
+            },
+            LabelSettings {
+                name: "help wanted".into(),
+                color: "008672".into(),
+                description: "".into(),
+            },
+        ];
+        let live = vec![
+            label("bug", "d73a4a", None),
+            label(
+                "docs",
+                "0075ca",
+                Some("Improvements or additions to documentation"),
+            ),
+            label("wontfix", "ffffff", None),
+        ];
+        assert_eq!(
+            lines(&plan_labels(&desired, &live)),
+            vec![
+                "~ label docs: name docs -> Docs, description \"Improvements or additions to documentation\" -> \"documentation\"",
+                "+ label help wanted: color 008672",
+                "- label wontfix",
+            ]
+        );
+    }
+
+    #[test]
+    fn plans_collaborators() {
+        let desired = vec![
+            CollaboratorSettings {
+                username: "octocat".into(),
+                permission: Permissions::Push,
+            },
+            CollaboratorSettings {
+                username: "hubot".into(),
+                permission: Permissions::Admin,
+            },
+            CollaboratorSettings {
+                username: "monalisa".into(),
+                permission: Permissions::Pull,
+            },
+        ];
+        let live = vec![
+            ("octocat".to_string(), Some("write".to_string())),
+            ("hubot".to_string(), Some("maintain".to_string())),
+            ("owner".to_string(), None),
+            ("someone".to_string(), None),
+        ];
+        assert_eq!(
+            lines(&plan_collaborators(&desired, &live, "owner")),
+            vec![
+                "~ collaborator hubot: permission maintain -> admin",
+                "+ collaborator monalisa: permission pull",
+                "- collaborator someone",
+            ]
+        );
+    }
+
+    #[test]
+    fn plans_protection() {
+        let desired: ProtectionSettings = serde_json::from_value(serde_json::json!({
+            "required_status_checks": { "strict": true, "contexts": ["ci"] },
+            "enforce_admins": true,
+            "required_pull_request_reviews": null,
+            "restrictions": null,
+            "required_linear_history": true
+        }))
+        .unwrap();
+        assert_eq!(
+            plan_protection("main", &desired, None).unwrap().to_string(),
+            "+ protection of main"
+        );
+        let live: ProtectionState = serde_json::from_value(serde_json::json!({
+            "required_status_checks": { "strict": true, "contexts": ["ci"], "checks": [] },
+            "enforce_admins": { "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection/enforce_admins", "enabled": true },
+            "required_linear_history": { "enabled": false },
+            "allow_force_pushes": { "enabled": true }
+        }))
+        .unwrap();
+        assert_eq!(
+            plan_protection("main", &desired, Some(&live))
+                .unwrap()
+                .to_string(),
+            "~ protection of main: required_linear_history -> true"
+        );
+    }
+
+    #[test]
+    fn protection_keeps_unset_rules() {
+        let desired: ProtectionSettings = serde_json::from_value(serde_json::json!({
+            "required_status_checks": null,
+            "enforce_admins": true,
+            "required_pull_request_reviews": {
+                "dismiss_stale_reviews": true,
+                "require_code_owner_reviews": false,
+                "required_approving_review_count": 2
+            },
+            "restrictions": null,
+            "required_linear_history": true
+        }))
+        .unwrap();
+        let live: ProtectionState = serde_json::from_value(serde_json::json!({
+            "enforce_admins": { "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection/enforce_admins", "enabled": true },
+            "required_pull_request_reviews": {
+                "dismissal_restrictions": { "users": [], "teams": [{
+                    "id": 1,
+                    "url": "https://api.github.com/teams/1",
+                    "name": "Justice League",
+                    "slug": "justice-league",
+                    "description": null,
+                    "privacy": "closed",
+                    "permission": "admin",
+                    "members_url": "https://api.github.com/teams/1/members{/member}",
+                    "repositories_url": "https://api.github.com/teams/1/repos"
+                }] },
+                "dismiss_stale_reviews": true,
+                "require_code_owner_reviews": false,
+                "required_approving_review_count": 1,
+                "require_last_push_approval": true
+            },
+            "required_linear_history": { "enabled": false },
+            "allow_force_pushes": { "enabled": true },
+            "allow_deletions": { "enabled": false }
+        }))
+        .unwrap();
+        let action = plan_protection("main", &desired, Some(&live)).unwrap();
+        let protection = match action.step {
+            Step::Protect(_, protection) => protection,
+            _ => panic!("unexpected step"),
+        };
+        assert_eq!(
+            serde_json::to_value(&protection).unwrap(),
+            serde_json::json!({
+                "required_status_checks": null,
+                "enforce_admins": true,
+                "required_pull_request_reviews": {
+                    "dismissal_restrictions": { "users": [], "teams": ["justice-league"] },
+                    "dismiss_stale_reviews": true,
+                    "require_code_owner_reviews": false,
+                    "required_approving_review_count": 2,
+                    "require_last_push_approval": true
+                },
+                "restrictions": null,
+                "required_linear_history": true,
+                "allow_force_pushes": true,
+                "allow_deletions": false
+            })
+        );
+    }
+
+    #[test]
+    fn protection_keeps_omitted_sections() {
+        let desired: ProtectionSettings = serde_json::from_value(serde_json::json!({
+            "enforce_admins": true
+        }))
+        .unwrap();
+        let live: ProtectionState = serde_json::from_value(serde_json::json!({
+            "required_status_checks": { "strict": true, "contexts": ["ci"], "checks": [] },
+            "enforce_admins": { "url": "https://api.github.com/repos/octocat/hello-world/branches/main/protection/enforce_admins", "enabled": false },
+            "required_pull_request_reviews": {
+                "dismiss_stale_reviews": false,
+                "require_code_owner_reviews": true,
+                "required_approving_review_count": 1
+            },
+            "restrictions": { "users": [], "teams": [], "apps": [] }
+        }))
+        .unwrap();
+        let action = plan_protection("main", &desired, Some(&live)).unwrap();
+        assert_eq!(
+            action.to_string(),
+            "~ protection of main: enforce_admins -> true"
+        );
+        let protection = match action.step {
+            Step::Protect(_, protection) => protection,
+            _ => panic!("unexpected step"),
+        };
+        assert_eq!(
+            serde_json::to_value(&protection).unwrap(),
+            serde_json::json!({
+                "required_status_checks": { "strict": true, "contexts": ["ci"] },
+                "enforce_admins": true,
+                "required_pull_request_reviews": {
+                    "dismiss_stale_reviews": false,
+                    "require_code_owner_reviews": true,
+                    "required_approving_review_count": 1,
+                    "require_last_push_approval": false
+                },
+                "restrictions": { "users": [], "teams": [] }
+            })
+        );
+    }
+
+    #[test]
+    fn plans_hooks() {
+        let hook: Hook = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "url": "https://api.github.com/repos/octocat/hello-world/hooks/1",
+            "test_url": null,
+            "ping_url": "https://api.github.com/repos/octocat/hello-world/hooks/1/pings",
+            "name": "web",
+            "events": ["push", "pull_request"],
+            "config": { "url": "https://example.com/hook", "content_type": "json", "secret": "********" },
+            "created_at": "2011-09-06T17:26:27Z",
+            "updated_at": "2011-09-06T20:39:23Z",
+            "active": true
+        }))
+        .unwrap();
+        let desired = vec![HookSettings {
+            url: "https://example.com/hook".into(),
+            content_type: WebHookContentType::Json,
+            events: vec!["pull_request".into(), "push".into()],
+            active: false,
+            secret: None,
+        }];
+        assert_eq!(
+            lines(&plan_hooks(&desired, &[hook])),
+            vec!["~ hook https://example.com/hook: active true -> false"]
+        );
+        assert_eq!(lines(&plan_hooks(&[], &[])), Vec::<String>::new());
+    }
+}
@@ -4,10 +4,11 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use crate::users::User;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Paginator};
 
 /// Team repository permissions
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Permission {
     Pull,
     Push,
@@ -53,10 +54,33 @@ impl RepoTeams {
     }
 
     /// provides a stream over all pages of teams
-    pub fn iter(&self) -> Stream<Team> {
+    pub fn iter(&self) -> Paginator<Team> {
         self.github
             .get_stream(&format!("/repos/{}/{}/teams", self.owner, self.repo))
     }
+
+    /// grant a team of the repository's organization access to this repo,
+    /// or change its permission
+    pub fn add(&self, team_slug: &str, permission: Permission) -> Future<()> {
+        self.github.put_no_response(
+            &self.team_path(team_slug),
+            json_lit!({ "permission": permission.to_string() }),
+        )
+    }
+
+    /// revoke a team's access to this repo
+    pub fn remove(&self, team_slug: &str) -> Future<()> {
+        self.github.delete(&self.team_path(team_slug))
+    }
+
+    fn team_path(&self, team_slug: &str) -> String {
+        format!(
+            "/orgs/{owner}/teams/{slug}/repos/{owner}/{repo}",
+            owner = self.owner,
+            slug = team_slug,
+            repo = self.repo
+        )
+    }
 }
 
 /// reference to teams associated with a github org
@@ -95,7 +119,7 @@ impl OrgTeams {
     }
 
     /// provides an iterator over all pages of teams
-    pub fn iter(&self) -> Stream<Team> {
+    pub fn iter(&self) -> Paginator<Team> {
         self.github.get_stream(&format!("/orgs/{}/teams", self.org))
     }
 
@@ -154,7 +178,7 @@ impl OrgTeamActions {
     }
 
     /// provides an iterator over all pages of members
-    pub fn iter_members(&self) -> Stream<User> {
+    pub fn iter_members(&self) -> Paginator<User> {
         self.github.get_stream(&self.path("/members"))
     }
 
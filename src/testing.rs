@@ -0,0 +1,422 @@
+//! Record and replay of http interactions
+//!
+//! A `Cassette` is a `Transport` which either records every request/response
+//! pair issued through a `Github` client to a JSON file, or replays a
+//! previously recorded file without touching the network. Requests are matched
+//! on their method, path, query, accepted media type and body.
+//!
+//! ```no_run
+//! use hubcaps::testing::Cassette;
+//!
+//! # fn main() -> hubcaps::Result<()> {
+//! // record against the live api with `Cassette::record(path, reqwest::Client::new())`
+//! // then replay in tests, passing the cassette as `Github::custom`'s transport
+//! let cassette = Cassette::replay("tests/fixtures/labels.json")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use data_encoding::BASE64;
+use futures::prelude::*;
+use http::header::ACCEPT;
+use http::{Request, Response, StatusCode};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+
+use crate::transport::{BoxedTransport, Transport};
+use crate::{Error, Future, Result};
+
+/// Query parameters which carry credentials and are never recorded
+const SCRUBBED_PARAMS: &[&str] = &["client_id", "client_secret"];
+
+/// Whether a cassette is capturing or serving interactions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Forward requests to an underlying transport and record them
+    Record,
+    /// Serve responses from previously recorded interactions
+    Replay,
+}
+
+/// A recording or replaying `Transport`
+#[derive(Clone, Debug)]
+pub struct Cassette {
+    mode: Mode,
+    path: PathBuf,
+    inner: Option<BoxedTransport>,
+    state: Arc<Mutex<Vec<Recorded>>>,
+}
+
+#[derive(Debug)]
+struct Recorded {
+    interaction: Interaction,
+    replayed: bool,
+}
+
+impl Cassette {
+    /// Create a cassette which forwards requests to `transport` and
+    /// writes each interaction to the file at `path` as it completes
+    pub fn record<P, T>(path: P, transport: T) -> Self
+    where
+        P: Into<PathBuf>,
+        T: Transport + Clone + Send + Sync + 'static,
+    {
+        Cassette {
+            mode: Mode::Record,
+            path: path.into(),
+            inner: Some(Box::new(transport)),
+            state: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Create a cassette which serves the interactions recorded in the file at `path`
+    pub fn replay<P>(path: P) -> Result<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let interactions: Vec<Interaction> = serde_json::from_slice(&fs::read(&path)?)?;
+        Ok(Cassette {
+            mode: Mode::Replay,
+            path,
+            inner: None,
+            state: Arc::new(Mutex::new(
+                interactions
+                    .into_iter()
+                    .map(|interaction| Recorded {
+                        interaction,
+                        replayed: false,
+                    })
+                    .collect(),
+            )),
+        })
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the interactions recorded or loaded so far
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|recorded| recorded.interaction.clone())
+            .collect()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let body = serde_json::to_vec_pretty(&self.interactions())?;
+        fs::write(&self.path, body).map_err(Error::from)
+    }
+
+    fn play(&self, request: &RecordedRequest) -> Result<Response<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap();
+        match state
+            .iter_mut()
+            .find(|recorded| !recorded.replayed && recorded.interaction.request.matches(request))
+        {
+            Some(recorded) => {
+                recorded.replayed = true;
+                recorded.interaction.response.to_response()
+            }
+            None => Err(Error::from(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No recorded interaction for {} {} accepting {} in {}",
+                    request.method,
+                    request.uri(),
+                    request.accept.as_deref().unwrap_or("anything"),
+                    self.path.display()
+                ),
+            ))),
+        }
+    }
+}
+
+impl Transport for Cassette {
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Vec<u8>>> {
+        let recorded = RecordedRequest::from_request(&request);
+        match self.inner {
+            None => Box::pin(future::ready(self.play(&recorded))),
+            Some(ref inner) => {
+                let cassette = self.clone();
+                Box::pin(inner.send(request).and_then(|response| async move {
+                    cassette.state.lock().unwrap().push(Recorded {
+                        interaction: Interaction {
+                            request: recorded,
+                            response: RecordedResponse::from_response(&response),
+                        },
+                        replayed: false,
+                    });
+                    if let Err(e) = cassette.save() {
+                        // failing to record isn't fatal, so just log & swallow the error
+                        debug!("Failed to save cassette: {}", e);
+                    }
+                    Ok(response)
+                }))
+            }
+        }
+    }
+}
+
+// representations
+
+/// A single recorded request/response pair
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// A recorded request or response body. Bodies which are not utf-8, like raw
+/// file contents, are kept as base64
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum RecordedBody {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl RecordedBody {
+    fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => RecordedBody::Text(text.to_owned()),
+            Err(_) => RecordedBody::Binary {
+                base64: BASE64.encode(bytes),
+            },
+        }
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            RecordedBody::Text(text) => Ok(text.clone().into_bytes()),
+            RecordedBody::Binary { base64 } => BASE64.decode(base64.as_bytes()).map_err(|e| {
+                Error::from(io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+            }),
+        }
+    }
+}
+
+impl Default for RecordedBody {
+    fn default() -> Self {
+        RecordedBody::Text(String::new())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub query: Option<String>,
+    /// the media type of the request's `Accept` header
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub accept: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub body: Option<RecordedBody>,
+}
+
+impl RecordedRequest {
+    fn from_request(request: &Request<Vec<u8>>) -> Self {
+        let query = request.uri().query().map(|query| {
+            form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(
+                    form_urlencoded::parse(query.as_bytes())
+                        .filter(|(k, _)| !SCRUBBED_PARAMS.contains(&k.as_ref())),
+                )
+                .finish()
+        });
+        RecordedRequest {
+            method: request.method().to_string(),
+            path: request.uri().path().to_owned(),
+            query: query.filter(|q| !q.is_empty()),
+            accept: request
+                .headers()
+                .get(ACCEPT)
+                .and_then(|accept| accept.to_str().ok())
+                .map(str::to_owned),
+            body: Some(request.body())
+                .filter(|body| !body.is_empty())
+                .map(|body| RecordedBody::new(body)),
+        }
+    }
+
+    fn uri(&self) -> String {
+        match self.query {
+            Some(ref query) => format!("{}?{}", self.path, query),
+            None => self.path.clone(),
+        }
+    }
+
+    /// Requests match when their methods, paths and accepted media types are
+    /// equal, their query parameters are equal regardless of order and their
+    /// bodies are equivalent json documents or, failing that, identical
+    pub fn matches(&self, other: &RecordedRequest) -> bool {
+        fn params(query: &Option<String>) -> Vec<(String, String)> {
+            let mut params: Vec<(String, String)> = query
+                .as_ref()
+                .map(|q| form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+                .unwrap_or_default();
+            params.sort();
+            params
+        }
+        fn json(body: &Option<RecordedBody>) -> Option<serde_json::Value> {
+            match body {
+                Some(RecordedBody::Text(text)) => serde_json::from_str(text).ok(),
+                _ => None,
+            }
+        }
+        self.method.eq_ignore_ascii_case(&other.method)
+            && self.path == other.path
+            && params(&self.query) == params(&other.query)
+            && self.accept == other.accept
+            && match (json(&self.body), json(&other.body)) {
+                (Some(a), Some(b)) => a == b,
+                _ => self.body == other.body,
+            }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: RecordedBody,
+}
+
+impl RecordedResponse {
+    fn from_response(response: &Response<Vec<u8>>) -> Self {
+        let mut headers = BTreeMap::new();
+        for (name, value) in response.headers() {
+            if let Ok(value) = value.to_str() {
+                headers
+                    .entry(name.as_str().to_owned())
+                    .and_modify(|existing: &mut String| {
+                        existing.push_str(", ");
+                        existing.push_str(value)
+                    })
+                    .or_insert_with(|| value.to_owned());
+            }
+        }
+        RecordedResponse {
+            status: response.status().as_u16(),
+            headers,
+            body: RecordedBody::new(response.body()),
+        }
+    }
+
+    fn to_response(&self) -> Result<Response<Vec<u8>>> {
+        let mut response = Response::builder().status(
+            StatusCode::from_u16(self.status).map_err(|e| Error::from(http::Error::from(e)))?,
+        );
+        for (name, value) in &self.headers {
+            response = response.header(name.as_str(), value.as_str());
+        }
+        response.body(self.body.to_bytes()?).map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, uri: &str, body: &str) -> RecordedRequest {
+        RecordedRequest::from_request(
+            &Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(body.as_bytes().to_vec())
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn scrubs_credentials_from_query() {
+        let recorded = request(
+            "GET",
+            "https://api.github.com/user/repos?per_page=5&client_id=id&client_secret=secret",
+            "",
+        );
+        assert_eq!(recorded.path, "/user/repos");
+        assert_eq!(recorded.query, Some("per_page=5".into()));
+        assert_eq!(recorded.body, None);
+    }
+
+    #[test]
+    fn matches_ignore_param_order_and_json_formatting() {
+        let recorded = request(
+            "POST",
+            "https://api.github.com/repos/o/r/issues?a=1&b=2",
+            r#"{"title":"t","labels":[]}"#,
+        );
+        assert!(recorded.matches(&request(
+            "POST",
+            "https://example.com/repos/o/r/issues?b=2&a=1",
+            r#"{ "labels": [], "title": "t" }"#,
+        )));
+        assert!(!recorded.matches(&request(
+            "PATCH",
+            "https://api.github.com/repos/o/r/issues?a=1&b=2",
+            r#"{"title":"t","labels":[]}"#,
+        )));
+        assert!(!recorded.matches(&request(
+            "POST",
+            "https://api.github.com/repos/o/r/issues?a=1",
+            r#"{"title":"t","labels":[]}"#,
+        )));
+        assert!(!recorded.matches(&request(
+            "POST",
+            "https://api.github.com/repos/o/r/issues?a=1&b=2",
+            r#"{"title":"other","labels":[]}"#,
+        )));
+    }
+
+    #[test]
+    fn matches_accepted_media_type() {
+        let accept = |media: &str| {
+            RecordedRequest::from_request(
+                &Request::builder()
+                    .uri("https://api.github.com/repos/o/r/contents/README.md")
+                    .header(ACCEPT, media)
+                    .body(Vec::new())
+                    .unwrap(),
+            )
+        };
+        let json = accept("application/vnd.github.v3+json");
+        assert_eq!(
+            json.accept.as_deref(),
+            Some("application/vnd.github.v3+json")
+        );
+        assert!(json.matches(&accept("application/vnd.github.v3+json")));
+        assert!(!json.matches(&accept("application/vnd.github.v3.raw")));
+    }
+
+    #[test]
+    fn keeps_binary_bodies() {
+        let bytes = vec![0x89, b'P', b'N', b'G', 0xff, 0x00];
+        let recorded = RecordedResponse::from_response(
+            &Response::builder().status(200).body(bytes.clone()).unwrap(),
+        );
+        let json = serde_json::to_value(&recorded).unwrap();
+        assert_eq!(json["body"], serde_json::json!({ "base64": "iVBOR/8A" }));
+        let replayed: RecordedResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(replayed.to_response().unwrap().into_body(), bytes);
+    }
+}
@@ -23,6 +23,9 @@ impl fmt::Display for TimeUnit {
 }
 
 /// Provides access to the traffic information for a repository
+///
+/// GitHub limits these listings to their top entries, so unlike most listings
+/// they are not paginated
 pub struct Traffic {
     github: Github,
     owner: String,
@@ -0,0 +1,156 @@
+//! Pluggable HTTP transports
+//!
+//! Every request issued through a `Github` client is handed to a `Transport`,
+//! which is responsible for delivering it and returning the raw response.
+//! `reqwest::Client` is the default implementation. Alternative transports,
+//! such as in-memory or recorded backends, may be provided via `Github::custom`.
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+use futures::prelude::*;
+use http::{Request, Response};
+use reqwest::Client;
+
+use crate::{Error, Future, Stream};
+
+/// A type-erased `Transport`, as held by a `Github` client. Cloning one
+/// clones the transport it boxes
+pub type BoxedTransport = Box<dyn Transport + Send + Sync>;
+
+/// Delivers fully prepared http requests and returns their responses
+///
+/// Implementations should only fail for transport level errors. Responses
+/// with non-success status codes are interpreted by the `Github` client.
+pub trait Transport: TransportClone + Debug {
+    /// Deliver a request, resolving to its response once the body has been
+    /// read in full. The request's uri is absolute and its headers, including
+    /// any authorization, are already set
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Vec<u8>>>;
+
+    /// Like `send`, but resolves as soon as the response's headers arrive,
+    /// delivering its body in chunks. Defaults to delivering the body `send`
+    /// returns as a single chunk
+    fn send_streaming(&self, request: Request<Vec<u8>>) -> Future<Response<Stream<Vec<u8>>>> {
+        Box::pin(self.send(request).map_ok(|response| {
+            let (parts, body) = response.into_parts();
+            let body: Stream<Vec<u8>> = Box::pin(stream::once(future::ok(body)));
+            Response::from_parts(parts, body)
+        }))
+    }
+}
+
+impl Transport for Client {
+    fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Vec<u8>>> {
+        let request = match reqwest::Request::try_from(request) {
+            Ok(request) => request,
+            Err(err) => return Box::pin(future::err(err.into())),
+        };
+        Box::pin(
+            self.execute(request)
+                .map_err(Error::from)
+                .and_then(|response| async move {
+                    let status = response.status();
+                    let headers = response.headers().clone();
+                    let body = response.bytes().await?;
+                    let mut response = Response::new(body.to_vec());
+                    *response.status_mut() = status;
+                    *response.headers_mut() = headers;
+                    Ok(response)
+                }),
+        )
+    }
+
+    fn send_streaming(&self, request: Request<Vec<u8>>) -> Future<Response<Stream<Vec<u8>>>> {
+        let request = match reqwest::Request::try_from(request) {
+            Ok(request) => request,
+            Err(err) => return Box::pin(future::err(err.into())),
+        };
+        Box::pin(
+            self.execute(request)
+                .map_err(Error::from)
+                .map_ok(|response| {
+                    let status = response.status();
+                    let headers = response.headers().clone();
+                    let body: Stream<Vec<u8>> =
+                        Box::pin(stream::try_unfold(response, |mut response| async move {
+                            let chunk = response.chunk().await?;
+                            Ok(chunk.map(|chunk| (chunk.to_vec(), response)))
+                        }));
+                    let mut response = Response::new(body);
+                    *response.status_mut() = status;
+                    *response.headers_mut() = headers;
+                    response
+                }),
+        )
+    }
+}
+
+impl Clone for BoxedTransport {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+// Separate to provide a blanket implementation for `T: Transport + Clone`
+// https://stackoverflow.com/a/30353928/463761
+#[doc(hidden)]
+pub trait TransportClone {
+    #[doc(hidden)]
+    fn box_clone(&self) -> BoxedTransport;
+}
+
+impl<T> TransportClone for T
+where
+    T: 'static + Transport + Clone + Send + Sync,
+{
+    fn box_clone(&self) -> BoxedTransport {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Github;
+    use http::header::USER_AGENT;
+    use http::StatusCode;
+
+    #[derive(Clone, Debug)]
+    struct Canned(&'static str);
+
+    impl Transport for Canned {
+        fn send(&self, request: Request<Vec<u8>>) -> Future<Response<Vec<u8>>> {
+            assert_eq!(request.uri(), "https://api.github.com/repos/o/r/labels");
+            assert_eq!(request.headers()[USER_AGENT], "test");
+            let mut response = Response::new(self.0.as_bytes().to_vec());
+            *response.status_mut() = StatusCode::OK;
+            Box::pin(future::ok(response))
+        }
+    }
+
+    #[cfg(not(feature = "httpcache"))]
+    fn github(transport: Canned) -> Github {
+        Github::custom("https://api.github.com", "test", None, transport)
+    }
+
+    #[cfg(feature = "httpcache")]
+    fn github(transport: Canned) -> Github {
+        Github::custom(
+            "https://api.github.com",
+            "test",
+            None,
+            transport,
+            <dyn crate::HttpCache>::noop(),
+        )
+    }
+
+    #[tokio::test]
+    async fn custom_transport() {
+        let github = github(Canned(
+            r#"[{"url":"https://api.github.com/repos/o/r/labels/bug","name":"bug","color":"f00"}]"#,
+        ));
+        let labels = github.repo("o", "r").labels().list().await.unwrap();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].name, "bug");
+    }
+}
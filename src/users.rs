@@ -1,5 +1,5 @@
 //! Users interface
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Paginator};
 use serde::{Deserialize, Serialize};
 
 /// User information
@@ -89,6 +89,11 @@ impl Users {
         self.github.get("/user/emails")
     }
 
+    /// Provides a stream over all pages of the current authenticated user's emails
+    pub fn iter_authenticated_emails(&self) -> Paginator<UserEmail> {
+        self.github.get_stream("/user/emails")
+    }
+
     pub fn get<U>(&self, username: U) -> Future<User>
     where
         U: Into<String>,
@@ -126,7 +131,7 @@ impl Contributors {
     }
 
     /// provides a stream over all pages of teams
-    pub fn iter(&self) -> Stream<User> {
+    pub fn iter(&self) -> Paginator<User> {
         self.github
             .get_stream(&format!("/repos/{}/{}/contributors", self.owner, self.repo))
     }
@@ -3,7 +3,7 @@
 use serde::Deserialize;
 
 use crate::repositories::Repo;
-use crate::{Future, Github, Stream};
+use crate::{Future, Github, Paginator};
 
 pub struct Watching {
     github: Github,
@@ -17,7 +17,7 @@ impl Watching {
 
     /// Provides a stream over all pages of the repositories watched by the authenticated user.
     /// https://developer.github.com/v3/activity/watching/#list-repositories-being-watched
-    pub fn iter(&self) -> Stream<Repo> {
+    pub fn iter(&self) -> Paginator<Repo> {
         self.github.get_stream("/user/subscriptions")
     }
 
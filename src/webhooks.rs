@@ -0,0 +1,404 @@
+//! Webhook deliveries
+//!
+//! Parses the payloads GitHub delivers to the webhooks configured via
+//! [hooks](../hooks/index.html) into typed events and verifies the
+//! `X-Hub-Signature-256` signatures computed with a hook's secret. With the
+//! `webhook-receiver` feature enabled, [receiver](receiver/index.html)
+//! provides a server routing deliveries to handlers.
+//!
+//! ```no_run
+//! use hubcaps::webhooks::{self, Event};
+//!
+//! # fn handle(event_header: &str, signature_header: &str, body: &[u8]) -> hubcaps::Result<()> {
+//! if !webhooks::verify("my-secret", signature_header, body) {
+//!     // reject the delivery
+//!     return Ok(());
+//! }
+//! match Event::parse(event_header, body)? {
+//!     Event::PullRequest(event) => println!("{} pull #{}", event.action, event.number),
+//!     Event::Push(event) => println!("{} commits pushed to {}", event.commits.len(), event.git_ref),
+//!     _ => (),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+#[cfg(feature = "webhook-receiver")]
+pub mod receiver;
+
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
+use ring::hmac;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+#[cfg(feature = "app")]
+use crate::app::Installation;
+use crate::checks::{CheckRun, CheckSuite};
+use crate::comments::Comment;
+use crate::deployments::Deployment;
+use crate::issues::Issue;
+use crate::pulls::Pull;
+use crate::releases::Release;
+use crate::repositories::Repo;
+use crate::users::User;
+use crate::Result;
+
+/// Header naming the event a delivery describes
+pub const X_GITHUB_EVENT: &str = "x-github-event";
+/// Header carrying a delivery's unique id
+pub const X_GITHUB_DELIVERY: &str = "x-github-delivery";
+/// Header carrying the HMAC-SHA256 hex digest of a delivery's body
+pub const X_HUB_SIGNATURE_256: &str = "x-hub-signature-256";
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// Return the `X-Hub-Signature-256` header value GitHub sends for `body` when
+/// the hook is configured with `secret`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, body);
+    format!("{}{}", SIGNATURE_PREFIX, HEXLOWER.encode(tag.as_ref()))
+}
+
+/// Return true when `signature`, an `X-Hub-Signature-256` header value,
+/// is the valid signature of `body` for the hook secret `secret`. The
+/// comparison takes constant time
+pub fn verify(secret: &str, signature: &str, body: &[u8]) -> bool {
+    let digest = match signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(|hex| HEXLOWER_PERMISSIVE.decode(hex.as_bytes()).ok())
+    {
+        Some(digest) => digest,
+        None => return false,
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, body, &digest).is_ok()
+}
+
+/// A webhook event, as named by a delivery's `X-GitHub-Event` header
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // events are parsed once per delivery
+pub enum Event {
+    Ping(PingEvent),
+    Push(PushEvent),
+    PullRequest(PullRequestEvent),
+    Issues(IssuesEvent),
+    IssueComment(IssueCommentEvent),
+    CheckRun(CheckRunEvent),
+    CheckSuite(CheckSuiteEvent),
+    Release(ReleaseEvent),
+    Deployment(DeploymentEvent),
+    #[cfg(feature = "app")]
+    Installation(InstallationEvent),
+    /// An event without a typed representation
+    Unknown {
+        name: String,
+        payload: Value,
+    },
+}
+
+impl Event {
+    /// Parse a delivery's body given the value of its `X-GitHub-Event` header
+    pub fn parse(name: &str, body: &[u8]) -> Result<Self> {
+        fn parse<E>(body: &[u8]) -> Result<E>
+        where
+            E: DeserializeOwned,
+        {
+            serde_json::from_slice(body).map_err(Into::into)
+        }
+        Ok(match name {
+            "ping" => Event::Ping(parse(body)?),
+            "push" => Event::Push(parse(body)?),
+            "pull_request" => Event::PullRequest(parse(body)?),
+            "issues" => Event::Issues(parse(body)?),
+            "issue_comment" => Event::IssueComment(parse(body)?),
+            "check_run" => Event::CheckRun(parse(body)?),
+            "check_suite" => Event::CheckSuite(parse(body)?),
+            "release" => Event::Release(parse(body)?),
+            "deployment" => Event::Deployment(parse(body)?),
+            #[cfg(feature = "app")]
+            "installation" => Event::Installation(parse(body)?),
+            _ => Event::Unknown {
+                name: name.into(),
+                payload: parse(body)?,
+            },
+        })
+    }
+
+    /// Return the event's name, as sent in the `X-GitHub-Event` header
+    pub fn name(&self) -> &str {
+        match self {
+            Event::Ping(_) => "ping",
+            Event::Push(_) => "push",
+            Event::PullRequest(_) => "pull_request",
+            Event::Issues(_) => "issues",
+            Event::IssueComment(_) => "issue_comment",
+            Event::CheckRun(_) => "check_run",
+            Event::CheckSuite(_) => "check_suite",
+            Event::Release(_) => "release",
+            Event::Deployment(_) => "deployment",
+            #[cfg(feature = "app")]
+            Event::Installation(_) => "installation",
+            Event::Unknown { name, .. } => name,
+        }
+    }
+
+    /// Return the GitHub App installation the event was delivered on behalf
+    /// of, if any
+    pub fn installation(&self) -> Option<u64> {
+        match self {
+            Event::Ping(_) => None,
+            Event::Push(event) => event.installation.as_ref().map(|i| i.id),
+            Event::PullRequest(event) => event.installation.as_ref().map(|i| i.id),
+            Event::Issues(event) => event.installation.as_ref().map(|i| i.id),
+            Event::IssueComment(event) => event.installation.as_ref().map(|i| i.id),
+            Event::CheckRun(event) => event.installation.as_ref().map(|i| i.id),
+            Event::CheckSuite(event) => event.installation.as_ref().map(|i| i.id),
+            Event::Release(event) => event.installation.as_ref().map(|i| i.id),
+            Event::Deployment(event) => event.installation.as_ref().map(|i| i.id),
+            #[cfg(feature = "app")]
+            Event::Installation(event) => Some(event.installation.id),
+            Event::Unknown { payload, .. } => payload["installation"]["id"].as_u64(),
+        }
+    }
+}
+
+// representations
+
+/// The installation a GitHub App delivery was sent on behalf of
+#[derive(Debug, Deserialize)]
+pub struct InstallationRef {
+    pub id: u64,
+}
+
+/// Sent when a hook is created
+#[derive(Debug, Deserialize)]
+pub struct PingEvent {
+    pub zen: String,
+    pub hook_id: u64,
+    pub hook: Value,
+    pub repository: Option<Repo>,
+    pub sender: Option<User>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PushEvent {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub before: String,
+    pub after: String,
+    pub created: bool,
+    pub deleted: bool,
+    pub forced: bool,
+    pub base_ref: Option<String>,
+    pub compare: String,
+    pub commits: Vec<PushCommit>,
+    pub head_commit: Option<PushCommit>,
+    /// push payloads represent timestamps differently than other
+    /// repository representations
+    pub repository: PushRepository,
+    pub pusher: GitUser,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PushCommit {
+    pub id: String,
+    pub tree_id: String,
+    pub distinct: bool,
+    pub message: String,
+    pub timestamp: String,
+    pub url: String,
+    pub author: GitUser,
+    pub committer: GitUser,
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitUser {
+    pub name: String,
+    pub email: Option<String>,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PushRepository {
+    pub id: u64,
+    pub name: String,
+    pub full_name: String,
+    pub private: bool,
+    pub html_url: String,
+    pub url: String,
+    pub default_branch: String,
+    pub owner: GitUser,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequestEvent {
+    pub action: String,
+    pub number: u64,
+    pub pull_request: Pull,
+    pub repository: Repo,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IssuesEvent {
+    pub action: String,
+    pub issue: Issue,
+    pub repository: Repo,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IssueCommentEvent {
+    pub action: String,
+    pub issue: Issue,
+    pub comment: Comment,
+    pub repository: Repo,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRunEvent {
+    pub action: String,
+    pub check_run: CheckRun,
+    pub repository: Repo,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckSuiteEvent {
+    pub action: String,
+    pub check_suite: CheckSuite,
+    pub repository: Repo,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseEvent {
+    pub action: String,
+    pub release: Release,
+    pub repository: Repo,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeploymentEvent {
+    pub action: Option<String>,
+    pub deployment: Deployment,
+    pub repository: Repo,
+    pub sender: User,
+    pub installation: Option<InstallationRef>,
+}
+
+#[cfg(feature = "app")]
+#[derive(Debug, Deserialize)]
+pub struct InstallationEvent {
+    pub action: String,
+    pub installation: Installation,
+    #[serde(default)]
+    pub repositories: Vec<InstallationRepository>,
+    pub sender: User,
+}
+
+#[cfg(feature = "app")]
+#[derive(Debug, Deserialize)]
+pub struct InstallationRepository {
+    pub id: u64,
+    pub name: String,
+    pub full_name: String,
+    pub private: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_and_verifies() {
+        // example from GitHub's webhook validation docs
+        let signature = sign("It's a Secret to Everybody", b"Hello, World!");
+        assert_eq!(
+            signature,
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+        assert!(verify(
+            "It's a Secret to Everybody",
+            &signature,
+            b"Hello, World!"
+        ));
+        assert!(!verify("another secret", &signature, b"Hello, World!"));
+        assert!(!verify(
+            "It's a Secret to Everybody",
+            &signature,
+            b"Hello, World?"
+        ));
+        assert!(!verify(
+            "It's a Secret to Everybody",
+            "sha1=757107ea",
+            b"Hello, World!"
+        ));
+        assert!(!verify("It's a Secret to Everybody", "sha256=zz", b""));
+    }
+
+    #[test]
+    fn parses_push() {
+        let event = Event::parse(
+            "push",
+            include_bytes!("../tests/fixtures/webhooks/push.json"),
+        )
+        .unwrap();
+        assert_eq!(event.name(), "push");
+        assert_eq!(event.installation(), Some(2));
+        match event {
+            Event::Push(push) => {
+                assert_eq!(push.git_ref, "refs/heads/main");
+                assert_eq!(push.commits.len(), 1);
+                assert_eq!(push.commits[0].modified, vec!["README.md"]);
+                assert_eq!(push.repository.full_name, "octocat/Hello-World");
+                assert_eq!(push.sender.login, "octocat");
+            }
+            otherwise => panic!("expected a push, got {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn parses_pull_request() {
+        match Event::parse(
+            "pull_request",
+            include_bytes!("../tests/fixtures/webhooks/pull_request.json"),
+        )
+        .unwrap()
+        {
+            Event::PullRequest(event) => {
+                assert_eq!(event.action, "opened");
+                assert_eq!(event.number, 2);
+                assert_eq!(event.pull_request.head.commit_ref, "feature");
+                assert_eq!(event.repository.full_name, "octocat/Hello-World");
+                assert!(event.installation.is_none());
+            }
+            otherwise => panic!("expected a pull request, got {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn parses_unknown() {
+        let event =
+            Event::parse("star", br#"{"action":"created","installation":{"id":7}}"#).unwrap();
+        assert_eq!(event.name(), "star");
+        assert_eq!(event.installation(), Some(7));
+    }
+}
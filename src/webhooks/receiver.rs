@@ -0,0 +1,496 @@
+//! An embedded webhook receiver
+//!
+//! `Receiver` accepts deliveries over http, rejects those whose
+//! `X-Hub-Signature-256` doesn't match the hook's secret, drops redeliveries
+//! of `X-GitHub-Delivery` ids it has already handled and routes the parsed
+//! `Event`s to async handlers registered by event name. Handlers receive a
+//! `Github` client to act on the event with. For GitHub App deliveries
+//! received by a `Receiver` configured with the App's credentials, that
+//! client authenticates as the installation the event was delivered for.
+//!
+//! This module is only available with the `webhook-receiver` cargo feature
+//! enabled and requires a running tokio runtime.
+//!
+//! ```no_run
+//! use hubcaps::webhooks::receiver::Receiver;
+//! use hubcaps::webhooks::Event;
+//! use hubcaps::Github;
+//!
+//! # async fn run() -> hubcaps::Result<()> {
+//! let mut receiver = Receiver::new(Github::new("user-agent-name", None)?, "my-secret");
+//! receiver.on("issues", |delivery| async move {
+//!     if let Event::Issues(event) = delivery.event {
+//!         println!("issue #{} {}", event.issue.number, event.action);
+//!     }
+//!     Ok(())
+//! });
+//! let running = receiver.start(([0, 0, 0, 0], 8080).into())?;
+//! println!("listening on {}", running.addr());
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future as StdFuture;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+
+use http::header::CONTENT_LENGTH;
+use http::{Method, StatusCode};
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use log::{debug, warn};
+use tokio::sync::oneshot;
+
+use super::{verify, Event, X_GITHUB_DELIVERY, X_GITHUB_EVENT, X_HUB_SIGNATURE_256};
+#[cfg(feature = "app")]
+use crate::{Credentials, InstallationTokenGenerator, JWTCredentials};
+use crate::{Error, Future, Github, Result};
+
+/// The number of delivery ids remembered for deduplication by default
+const DEFAULT_DEDUPE_CAPACITY: usize = 1024;
+
+/// The largest payload accepted. GitHub caps deliveries at 25 MB
+const MAX_PAYLOAD_SIZE: usize = 25 * 1024 * 1024;
+
+/// The name handlers registered for every unrouted event are stored under
+const ANY_EVENT: &str = "*";
+
+type Handler = Arc<dyn Fn(Delivery) -> Future<()> + Send + Sync>;
+
+/// A verified delivery, as provided to handlers
+#[derive(Debug)]
+pub struct Delivery {
+    /// the delivery's `X-GitHub-Delivery` id
+    pub id: String,
+    pub event: Event,
+    /// a client for acting on the event. For GitHub App deliveries this
+    /// authenticates as the event's installation
+    pub github: Github,
+}
+
+/// Receives webhook deliveries and routes them to handlers
+#[derive(Clone)]
+pub struct Receiver {
+    github: Github,
+    secret: String,
+    #[cfg(feature = "app")]
+    app: Option<JWTCredentials>,
+    #[cfg(feature = "app")]
+    installations: Arc<Mutex<HashMap<u64, Github>>>,
+    handlers: HashMap<String, Handler>,
+    deliveries: Arc<Mutex<Deliveries>>,
+}
+
+impl Receiver {
+    /// Create a receiver for deliveries signed with `secret`, the value
+    /// given to a hook's `HookCreateOptionsBuilder::secret`. Handlers are
+    /// provided `github`, or clones of it authenticated as an App
+    /// installation
+    pub fn new<S>(github: Github, secret: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Receiver {
+            github,
+            secret: secret.into(),
+            #[cfg(feature = "app")]
+            app: None,
+            #[cfg(feature = "app")]
+            installations: Arc::default(),
+            handlers: HashMap::new(),
+            deliveries: Arc::new(Mutex::new(Deliveries::new(DEFAULT_DEDUPE_CAPACITY))),
+        }
+    }
+
+    /// Authenticate the clients provided to handlers of deliveries made on
+    /// behalf of an App installation as that installation
+    #[cfg(feature = "app")]
+    pub fn set_app_credentials(&mut self, credentials: JWTCredentials) {
+        self.app = Some(credentials);
+    }
+
+    /// Configure how many of the most recent delivery ids are remembered to
+    /// drop redeliveries. Defaults to 1024
+    pub fn set_dedupe_capacity(&mut self, capacity: usize) {
+        self.deliveries = Arc::new(Mutex::new(Deliveries::new(capacity)));
+    }
+
+    /// Route events named `event`, as in the `X-GitHub-Event` header, to
+    /// `handler`, replacing any handler previously registered for it. A
+    /// handler failing responds to the delivery with an error and forgets
+    /// it, so that it may be redelivered
+    pub fn on<F, R>(&mut self, event: &str, handler: F) -> &mut Self
+    where
+        F: Fn(Delivery) -> R + Send + Sync + 'static,
+        R: StdFuture<Output = Result<()>> + Send + 'static,
+    {
+        self.handlers.insert(
+            event.into(),
+            Arc::new(move |delivery| Box::pin(handler(delivery))),
+        );
+        self
+    }
+
+    /// Route events without a handler of their own to `handler`
+    pub fn on_any<F, R>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(Delivery) -> R + Send + Sync + 'static,
+        R: StdFuture<Output = Result<()>> + Send + 'static,
+    {
+        self.on(ANY_EVENT, handler)
+    }
+
+    /// Start serving deliveries on `addr`
+    pub fn start(self, addr: SocketAddr) -> Result<RunningReceiver> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let receiver = Arc::new(self);
+        let service = make_service_fn(move |_| {
+            let receiver = receiver.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let receiver = receiver.clone();
+                    async move { Ok::<_, Infallible>(receiver.handle(req).await) }
+                }))
+            }
+        });
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)
+            .map_err(|e| Error::from(io::Error::other(e)))?
+            .serve(service)
+            .with_graceful_shutdown(async {
+                rx.await.ok();
+            });
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                debug!("webhook receiver error: {}", e);
+            }
+        });
+        Ok(RunningReceiver {
+            addr,
+            shutdown: Some(tx),
+        })
+    }
+
+    /// Respond to a single delivery. This may be used to mount the receiver
+    /// within an existing hyper service
+    pub async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        if req.method() != Method::POST {
+            return respond(StatusCode::METHOD_NOT_ALLOWED, "expected a POST");
+        }
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let (name, id, signature) = match (
+            header(X_GITHUB_EVENT),
+            header(X_GITHUB_DELIVERY),
+            header(X_HUB_SIGNATURE_256),
+        ) {
+            (Some(name), Some(id), Some(signature)) => (name, id, signature),
+            _ => return respond(StatusCode::BAD_REQUEST, "missing delivery headers"),
+        };
+        let length = req
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if length.is_some_and(|length| length > MAX_PAYLOAD_SIZE as u64) {
+            return respond(StatusCode::PAYLOAD_TOO_LARGE, "payload too large");
+        }
+        let body = match read_body(req.into_body(), MAX_PAYLOAD_SIZE).await {
+            Ok(Some(body)) => body,
+            Ok(None) => return respond(StatusCode::PAYLOAD_TOO_LARGE, "payload too large"),
+            Err(_) => return respond(StatusCode::BAD_REQUEST, "unreadable body"),
+        };
+        if !verify(&self.secret, &signature, &body) {
+            return respond(StatusCode::UNAUTHORIZED, "invalid signature");
+        }
+        let event = match Event::parse(&name, &body) {
+            Ok(event) => event,
+            Err(e) => return respond(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+        let handler = match self
+            .handlers
+            .get(&name)
+            .or_else(|| self.handlers.get(ANY_EVENT))
+        {
+            Some(handler) => handler.clone(),
+            None => return respond(StatusCode::OK, "unhandled"),
+        };
+        if !self.deliveries.lock().unwrap().insert(&id) {
+            debug!("dropping redelivery {}", id);
+            return respond(StatusCode::OK, "duplicate");
+        }
+        let delivery = Delivery {
+            id: id.clone(),
+            github: self.github_for(&event),
+            event,
+        };
+        match handler(delivery).await {
+            Ok(()) => respond(StatusCode::OK, "handled"),
+            Err(e) => {
+                warn!("handler for delivery {} failed: {}", id, e);
+                self.deliveries.lock().unwrap().remove(&id);
+                respond(StatusCode::INTERNAL_SERVER_ERROR, "handler failed")
+            }
+        }
+    }
+
+    #[cfg(feature = "app")]
+    fn github_for(&self, event: &Event) -> Github {
+        match (&self.app, event.installation()) {
+            (Some(app), Some(installation)) => self
+                .installations
+                .lock()
+                .unwrap()
+                .entry(installation)
+                .or_insert_with(|| {
+                    let mut github = self.github.clone();
+                    github.set_credentials(Credentials::InstallationToken(
+                        InstallationTokenGenerator::new(installation, app.clone()),
+                    ));
+                    github
+                })
+                .clone(),
+            _ => self.github.clone(),
+        }
+    }
+
+    #[cfg(not(feature = "app"))]
+    fn github_for(&self, _: &Event) -> Github {
+        self.github.clone()
+    }
+}
+
+impl fmt::Debug for Receiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut events: Vec<_> = self.handlers.keys().collect();
+        events.sort();
+        f.debug_struct("Receiver")
+            .field("github", &self.github)
+            .field("events", &events)
+            .finish()
+    }
+}
+
+/// A receiver serving deliveries. The server shuts down when dropped
+#[derive(Debug)]
+pub struct RunningReceiver {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl RunningReceiver {
+    /// The address the receiver is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for RunningReceiver {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// The most recently handled delivery ids, oldest first
+struct Deliveries {
+    capacity: usize,
+    seen: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl Deliveries {
+    fn new(capacity: usize) -> Self {
+        Deliveries {
+            capacity,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Record a delivery id, returning false if it was already recorded
+    fn insert(&mut self, id: &str) -> bool {
+        if self.seen.contains(id) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        if self.capacity > 0 {
+            self.seen.insert(id.into());
+            self.order.push_back(id.into());
+        }
+        true
+    }
+
+    fn remove(&mut self, id: &str) {
+        if self.seen.remove(id) {
+            self.order.retain(|seen| seen != id);
+        }
+    }
+}
+
+/// Read a body, unless it's larger than `limit` bytes
+async fn read_body(mut body: Body, limit: usize) -> hyper::Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+fn respond(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::sign;
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const SECRET: &str = "It's a Secret to Everybody";
+    const PUSH: &[u8] = include_bytes!("../../tests/fixtures/webhooks/push.json");
+
+    fn delivery(id: &str, event: &str, signature: &str, body: &'static [u8]) -> Request<Body> {
+        Request::post("/")
+            .header(X_GITHUB_DELIVERY, id)
+            .header(X_GITHUB_EVENT, event)
+            .header(X_HUB_SIGNATURE_256, signature)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn receiver(calls: Arc<AtomicUsize>) -> Receiver {
+        let mut receiver = Receiver::new(Github::new("test", None).unwrap(), SECRET);
+        receiver.on("push", move |delivery| {
+            let calls = calls.clone();
+            async move {
+                match delivery.event {
+                    Event::Push(event) => assert_eq!(event.git_ref, "refs/heads/main"),
+                    other => panic!("expected a push, got {:?}", other),
+                }
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(Error::from(io::Error::other("flaky")))
+                } else {
+                    Ok(())
+                }
+            }
+        });
+        receiver
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_signatures() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let receiver = receiver(calls.clone());
+        let response = receiver
+            .handle(delivery("1", "push", &sign("guess", PUSH), PUSH))
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn dedupes_handled_deliveries() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let receiver = receiver(calls.clone());
+        let signature = sign(SECRET, PUSH);
+        let statuses = vec![
+            // the first attempt fails and is forgotten so it may be redelivered
+            receiver
+                .handle(delivery("1", "push", &signature, PUSH))
+                .await,
+            receiver
+                .handle(delivery("1", "push", &signature, PUSH))
+                .await,
+            receiver
+                .handle(delivery("1", "push", &signature, PUSH))
+                .await,
+            receiver
+                .handle(delivery("2", "push", &signature, PUSH))
+                .await,
+        ]
+        .into_iter()
+        .map(|response| response.status())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::OK
+            ]
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn rejects_large_payloads() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let receiver = receiver(calls.clone());
+        let mut request = delivery("1", "push", &sign(SECRET, PUSH), PUSH);
+        request
+            .headers_mut()
+            .insert(CONTENT_LENGTH, (MAX_PAYLOAD_SIZE + 1).into());
+        let response = receiver.handle(request).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            let chunk = vec![b' '; 1024 * 1024];
+            for _ in 0..26 {
+                if sender.send_data(chunk.clone().into()).await.is_err() {
+                    break;
+                }
+            }
+        });
+        assert_eq!(read_body(body, MAX_PAYLOAD_SIZE).await.unwrap(), None);
+        assert_eq!(
+            read_body(Body::from(PUSH), MAX_PAYLOAD_SIZE).await.unwrap(),
+            Some(PUSH.to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn hides_handler_errors() {
+        let receiver = receiver(Arc::new(AtomicUsize::new(0)));
+        let response = receiver
+            .handle(delivery("1", "push", &sign(SECRET, PUSH), PUSH))
+            .await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, "handler failed");
+    }
+
+    #[test]
+    fn deliveries_are_bounded() {
+        let mut deliveries = Deliveries::new(2);
+        assert!(deliveries.insert("a"));
+        assert!(deliveries.insert("b"));
+        assert!(!deliveries.insert("a"));
+        assert!(deliveries.insert("c"));
+        assert!(deliveries.insert("a"));
+        assert!(!deliveries.insert("c"));
+    }
+}
@@ -0,0 +1,283 @@
+use futures::prelude::*;
+use http::StatusCode;
+use hubcaps::branches::{BranchListOptions, Protection};
+use hubcaps::hooks::{HookCreateOptions, WebHookContentType};
+use hubcaps::issues::{IssueListOptions, State};
+use hubcaps::labels::LabelOptions;
+use hubcaps::testing::{Cassette, Mode};
+use hubcaps::transport::Transport;
+use hubcaps::{Error, Github, Result};
+
+mod testkit;
+
+#[cfg(not(feature = "httpcache"))]
+fn github<T>(transport: T) -> Github
+where
+    T: Transport + Clone + Send + Sync + 'static,
+{
+    Github::custom("https://api.github.com", "hubcaps-test", None, transport)
+}
+
+#[cfg(feature = "httpcache")]
+fn github<T>(transport: T) -> Github
+where
+    T: Transport + Clone + Send + Sync + 'static,
+{
+    Github::custom(
+        "https://api.github.com",
+        "hubcaps-test",
+        None,
+        transport,
+        <dyn hubcaps::HttpCache>::noop(),
+    )
+}
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[tokio::test]
+async fn replay_with_response() -> Result<()> {
+    let github = github(Cassette::replay(fixture("labels.json"))?);
+    let response = github
+        .with_response(|github| github.repo("softprops", "hubcaps").labels().list())
+        .await?;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.request_id.as_deref(),
+        Some("C0DE:1234:5678AB:9ABCDE:5F0A1B2C")
+    );
+    assert_eq!(
+        response.links.last.as_deref(),
+        Some("https://api.github.com/repos/softprops/hubcaps/labels?page=2")
+    );
+    assert_eq!(response.body.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_labels() -> Result<()> {
+    let cassette = Cassette::replay(fixture("labels.json"))?;
+    assert_eq!(cassette.mode(), Mode::Replay);
+    let labels = github(cassette).repo("softprops", "hubcaps").labels();
+
+    let names = labels
+        .iter()
+        .map_ok(|label| label.name)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(names, vec!["bug", "enhancement"]);
+
+    let created = labels
+        .create(&LabelOptions::new("docs", "0075ca", "Documentation"))
+        .await?;
+    assert_eq!(created.name, "docs");
+
+    labels.delete("docs").await?;
+    match labels.delete("docs").await {
+        Err(Error::Fault { code, error, .. }) => {
+            assert_eq!(code, StatusCode::NOT_FOUND);
+            assert_eq!(error.message, "Not Found");
+        }
+        otherwise => panic!("expected a fault, got {:?}", otherwise),
+    }
+
+    // every recorded interaction has now been served exactly once
+    assert!(labels.list().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn record_then_replay() -> Result<()> {
+    let path = testkit::test_home().join("cassettes/labels.json");
+    let recorder = Cassette::record(&path, Cassette::replay(fixture("labels.json"))?);
+    let recorded = github(recorder.clone())
+        .repo("softprops", "hubcaps")
+        .labels()
+        .iter()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(recorder.interactions().len(), 2);
+
+    let replayed = github(Cassette::replay(&path)?)
+        .repo("softprops", "hubcaps")
+        .labels()
+        .iter()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(
+        recorded.iter().map(|l| &l.name).collect::<Vec<_>>(),
+        replayed.iter().map(|l| &l.name).collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+// replays of the interactions of the examples
+
+#[tokio::test]
+async fn replay_repos() -> Result<()> {
+    let github = github(Cassette::replay(fixture("repos.json"))?);
+    let repos = github
+        .user_repos("softprops")
+        .iter(&Default::default())
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(
+        repos.iter().map(|repo| &repo.name).collect::<Vec<_>>(),
+        vec!["hubcaps", "action-gh-release"]
+    );
+    let languages = repos[0].languages(github.clone()).await?;
+    assert_eq!(languages.get("Rust"), Some(&483726));
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_issues() -> Result<()> {
+    let issues = github(Cassette::replay(fixture("issues.json"))?)
+        .repo("matthiasbeyer", "imag")
+        .issues()
+        .iter(
+            &IssueListOptions::builder()
+                .per_page(100)
+                .state(State::All)
+                .build(),
+        )
+        .map_ok(|issue| (issue.number, issue.state))
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(
+        issues,
+        vec![
+            (3, "open".to_string()),
+            (2, "closed".to_string()),
+            (1, "closed".to_string())
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_pulls() -> Result<()> {
+    let github = github(Cassette::replay(fixture("pulls.json"))?);
+    let pulls = github
+        .repo("softprops", "hubcat")
+        .pulls()
+        .iter(&Default::default())
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(pulls.len(), 1);
+    assert_eq!(pulls[0].head.commit_ref, "feature");
+
+    let pull = github.repo("softprops", "hubcaps").pulls().get(28);
+    assert_eq!(pull.get().await?.mergeable, Some(true));
+    let comments = pull.comments().list(&Default::default()).await?;
+    assert_eq!(comments[0].body, "Looks good to me");
+    let commits = pull.commits().iter().try_collect::<Vec<_>>().await?;
+    assert_eq!(commits[0].commit.message, "Add pull request comments");
+
+    let requests = github
+        .repo("softprops", "hubcaps")
+        .pulls()
+        .get(190)
+        .review_requests()
+        .get()
+        .await?;
+    assert_eq!(requests.users[0].login, "softprops");
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_releases() -> Result<()> {
+    let releases = github(Cassette::replay(fixture("releases.json"))?)
+        .repo("octokit", "rest.js")
+        .releases();
+    assert_eq!(releases.list().await?.len(), 2);
+    assert_eq!(releases.latest().await?.tag_name, "v12.0.0");
+    assert_eq!(releases.by_tag("v11.0.0").await?.id, 7700);
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_hooks() -> Result<()> {
+    let hooks = github(Cassette::replay(fixture("hooks.json"))?)
+        .repo("softprops", "hubcaps")
+        .hooks();
+    let hook = hooks
+        .create(
+            &HookCreateOptions::web()
+                .url("http://localhost:8080")
+                .content_type(WebHookContentType::Json)
+                .build(),
+        )
+        .await?;
+    assert_eq!(
+        hook.config_string("url").as_deref(),
+        Some("http://localhost:8080")
+    );
+    assert_eq!(hooks.list().await?.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_branches() -> Result<()> {
+    let branches = github(Cassette::replay(fixture("branches.json"))?)
+        .repo("softprops", "hubcaps")
+        .branches();
+    let names = branches
+        .iter(&BranchListOptions::default())
+        .map_ok(|branch| branch.name)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(names, vec!["gh-pages", "master"]);
+    let master = branches.get("master").await?;
+    assert_eq!(
+        master.commit.sha,
+        "553c2077f0edc3d5dc5d17262f6aa498e69d6f8e"
+    );
+    assert!(master._links.is_some());
+    let protection = branches
+        .protection(
+            "master",
+            &Protection {
+                enforce_admins: false,
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(
+        protection.enforce_admins.map(|admins| admins.enabled),
+        Some(false)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_content() -> Result<()> {
+    let content = github(Cassette::replay(fixture("content.json"))?)
+        .repo("softprops", "hubcaps")
+        .content();
+    let license = content.file("/LICENSE", "master").await?;
+    assert!(license
+        .content
+        .starts_with(b"Copyright (c) 2015-2020 Doug Tangren"));
+
+    let examples = content
+        .iter("/examples", "master")
+        .map_ok(|item| item.path)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(
+        examples,
+        vec!["examples/branches.rs", "examples/content.rs"]
+    );
+    let root = content
+        .root("master")
+        .map_ok(|item| item.name)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(root, vec!["examples", "LICENSE"]);
+
+    let downloaded = content.download("/LICENSE", "master").try_concat().await?;
+    assert_eq!(downloaded, &license.content[..]);
+    Ok(())
+}
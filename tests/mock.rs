@@ -0,0 +1,290 @@
+#![cfg(feature = "mock")]
+use std::time::Duration;
+
+use futures::prelude::*;
+use http::StatusCode;
+use hubcaps::comments::{CommentListOptions, CommentOptions};
+use hubcaps::issues::{IssueListOptions, IssueOptions, State};
+use hubcaps::labels::LabelOptions;
+use hubcaps::mock::MockGithub;
+use hubcaps::pulls::{MergeMethod, MergeOptions, PullListOptions, PullOptions};
+use hubcaps::releases::ReleaseOptions;
+use hubcaps::repositories::RepoOptions;
+use hubcaps::{Error, Result};
+
+#[tokio::test]
+async fn issue_lifecycle() -> Result<()> {
+    let mock = MockGithub::start()?;
+    mock.add_repo("octocat", "hello-world");
+    let repo = mock.client()?.repo("octocat", "hello-world");
+
+    let issue = repo
+        .issues()
+        .create(&IssueOptions::new(
+            "Found a bug",
+            Some("It's broken"),
+            None::<String>,
+            None,
+            Vec::<String>::new(),
+        ))
+        .await?;
+    assert_eq!(issue.number, 1);
+    assert_eq!(issue.state, "open");
+
+    let labels = repo.issue(issue.number).labels().add(vec!["bug"]).await?;
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].name, "bug");
+    // labels applied to issues are created on demand
+    assert_eq!(repo.labels().list().await?.len(), 1);
+
+    let comments = repo.issue(issue.number).comments();
+    let comment = comments
+        .create(&CommentOptions {
+            body: "Confirmed".into(),
+        })
+        .await?;
+    assert_eq!(comment.body, "Confirmed");
+
+    let closed = repo.issue(issue.number).close().await?;
+    assert_eq!(closed.state, "closed");
+    assert!(closed.closed_at.is_some());
+    assert_eq!(closed.comments, 1);
+    assert_eq!(closed.labels[0].name, "bug");
+
+    let open = repo
+        .issues()
+        .list(&IssueListOptions::builder().build())
+        .await?;
+    assert!(open.is_empty());
+    let all = repo
+        .issues()
+        .list(&IssueListOptions::builder().state(State::All).build())
+        .await?;
+    assert_eq!(all.len(), 1);
+    assert_eq!(
+        comments
+            .list(&CommentListOptions::builder().build())
+            .await?
+            .len(),
+        1
+    );
+
+    repo.issue(issue.number).labels().remove("bug").await?;
+    assert!(repo.issue(issue.number).get().await?.labels.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn labels_paginate() -> Result<()> {
+    let mock = MockGithub::start()?;
+    mock.add_repo("octocat", "hello-world");
+    let labels = mock.client()?.repo("octocat", "hello-world").labels();
+    for i in 0..35 {
+        labels
+            .create(&LabelOptions::new(format!("label-{}", i), "ededed", ""))
+            .await?;
+    }
+    assert_eq!(labels.list().await?.len(), 30);
+    assert_eq!(labels.iter().try_collect::<Vec<_>>().await?.len(), 35);
+
+    match labels
+        .create(&LabelOptions::new("label-0", "ededed", ""))
+        .await
+    {
+        Err(Error::Fault { code, .. }) => assert_eq!(code, StatusCode::UNPROCESSABLE_ENTITY),
+        otherwise => panic!("expected a validation failure, got {:?}", otherwise),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn paginator_pages() -> Result<()> {
+    let mock = MockGithub::start()?;
+    mock.add_repo("octocat", "hello-world");
+    let issues = mock.client()?.repo("octocat", "hello-world").issues();
+    for i in 0..7 {
+        issues
+            .create(&IssueOptions::new(
+                format!("issue {}", i),
+                None::<String>,
+                None::<String>,
+                None,
+                Vec::<String>::new(),
+            ))
+            .await?;
+    }
+    let paginator = issues.iter(&IssueListOptions::builder().per_page(3).build());
+
+    let pages = paginator.pages().try_collect::<Vec<_>>().await?;
+    assert_eq!(
+        pages
+            .iter()
+            .map(|page| page.items.len())
+            .collect::<Vec<_>>(),
+        vec![3, 3, 1]
+    );
+    assert_eq!(pages[0].last_page(), Some(3));
+    assert_eq!(pages[2].next_cursor(), None);
+
+    let third = paginator.page(3).await?;
+    assert_eq!(third.number, 3);
+    assert_eq!(third.items[0].title, pages[2].items[0].title);
+
+    let concurrent = paginator
+        .concurrent_pages(2)
+        .map_ok(|page| page.number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(concurrent, vec![1, 2, 3]);
+
+    // cursors survive a round trip through serialization
+    let cursor = serde_json::to_string(&pages[0].next_cursor().unwrap())?;
+    let resumed = paginator
+        .resume(serde_json::from_str(&cursor)?)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(resumed.len(), 4);
+    assert_eq!(resumed[0].title, pages[1].items[0].title);
+    Ok(())
+}
+
+#[tokio::test]
+async fn listings_iterate_past_first_page() -> Result<()> {
+    let mock = MockGithub::start()?;
+    mock.add_repo("octocat", "hello-world");
+    let repo = mock.client()?.repo("octocat", "hello-world");
+    let issue = repo
+        .issues()
+        .create(&IssueOptions::new(
+            "Popular",
+            None::<String>,
+            None::<String>,
+            None,
+            Vec::<String>::new(),
+        ))
+        .await?;
+    let comments = repo.issue(issue.number).comments();
+    let releases = repo.releases();
+    for i in 0..32 {
+        comments
+            .create(&CommentOptions {
+                body: format!("+{}", i),
+            })
+            .await?;
+        releases
+            .create(&ReleaseOptions::builder(format!("v0.{}.0", i)).build())
+            .await?;
+    }
+    let options = CommentListOptions::builder().build();
+    assert_eq!(comments.list(&options).await?.len(), 30);
+    assert_eq!(
+        comments.iter(&options).try_collect::<Vec<_>>().await?.len(),
+        32
+    );
+    assert_eq!(releases.list().await?.len(), 30);
+    assert_eq!(releases.iter().try_collect::<Vec<_>>().await?.len(), 32);
+    Ok(())
+}
+
+#[tokio::test]
+async fn repos_pulls_and_releases() -> Result<()> {
+    let mock = MockGithub::start()?;
+    let github = mock.client()?;
+    let created = github
+        .repos()
+        .create(
+            &RepoOptions::builder("widgets")
+                .description("Widgets")
+                .build(),
+        )
+        .await?;
+    assert_eq!(created.full_name, "hubcaps-mock/widgets");
+    let repo = github.repo("hubcaps-mock", "widgets");
+    assert_eq!(repo.get().await?.description, Some("Widgets".into()));
+
+    let pull = repo
+        .pulls()
+        .create(&PullOptions::new(
+            "Add widgets",
+            "feature",
+            "main",
+            None::<String>,
+        ))
+        .await?;
+    assert_eq!(pull.head.commit_ref, "feature");
+    repo.pulls()
+        .get(pull.number)
+        .labels()
+        .add(vec!["enhancement"])
+        .await?;
+    let closed = repo.pulls().get(pull.number).close().await?;
+    assert_eq!(closed.state, "closed");
+    assert_eq!(closed.labels[0].name, "enhancement");
+    assert!(repo
+        .pulls()
+        .list(&PullListOptions::builder().build())
+        .await?
+        .is_empty());
+
+    let releases = repo.releases();
+    let release = releases
+        .create(&ReleaseOptions::builder("v1.0.0").name("One").build())
+        .await?;
+    assert_eq!(releases.latest().await?.id, release.id);
+    assert_eq!(releases.by_tag("v1.0.0").await?.name, "One");
+    releases.delete(release.id).await?;
+    assert!(releases.list().await?.is_empty());
+
+    repo.delete().await?;
+    match repo.get().await {
+        Err(Error::Fault { code, .. }) => assert_eq!(code, StatusCode::NOT_FOUND),
+        otherwise => panic!("expected not found, got {:?}", otherwise),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn pull_merging() -> Result<()> {
+    let mock = MockGithub::start()?;
+    mock.add_repo("octocat", "hello-world");
+    let pulls = mock.client()?.repo("octocat", "hello-world").pulls();
+    let pull = pulls
+        .create(&PullOptions::new(
+            "Add widgets",
+            "feature",
+            "main",
+            None::<String>,
+        ))
+        .await?;
+    let pull_ref = pulls.get(pull.number);
+    assert!(!pull_ref.is_merged().await?);
+    let update = pull_ref.update_branch(Some(&pull.head.sha)).await?;
+    assert_eq!(update.message, "Updating pull request branch.");
+
+    let ready = pull_ref
+        .wait_for_mergeability(Duration::from_millis(10), 3)
+        .await?;
+    assert_eq!(ready.mergeable, Some(true));
+
+    match pull_ref
+        .merge(&MergeOptions::builder().sha("0".repeat(40)).build())
+        .await
+    {
+        Err(Error::Fault { code, .. }) => assert_eq!(code, StatusCode::CONFLICT),
+        otherwise => panic!("expected a conflict, got {:?}", otherwise),
+    }
+    let merged = pull_ref
+        .merge(
+            &MergeOptions::builder()
+                .sha(pull.head.sha.as_str())
+                .merge_method(MergeMethod::Squash)
+                .build(),
+        )
+        .await?;
+    assert!(merged.merged);
+    assert!(pull_ref.is_merged().await?);
+    let pull = pull_ref.get().await?;
+    assert_eq!(pull.state, "closed");
+    assert_eq!(pull.merge_commit_sha, Some(merged.sha));
+    Ok(())
+}